@@ -0,0 +1,5 @@
+mod server;
+mod state;
+
+pub use server::run_gateway;
+pub use state::{CommitteeInfo, GatewayState, RequestStatus};
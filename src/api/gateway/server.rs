@@ -0,0 +1,276 @@
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    post,
+    web::{self, Data, Json, Path, ServiceConfig},
+    App, HttpResponse, HttpServer, ResponseError,
+};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    api::requests::{messages::unix_time, GenericRequest},
+    did::did_tag,
+    dlt::Transport,
+};
+
+use super::{GatewayState, RequestStatus};
+
+struct Gateway {
+    state: GatewayState,
+    transport: Arc<dyn Transport>,
+}
+
+#[derive(Serialize)]
+struct RequestResponse {
+    id: String,
+    #[serde(flatten)]
+    status: RequestStatus,
+}
+
+#[derive(Debug, Error)]
+enum GatewayError {
+    #[error("the committee is not ready yet")]
+    CommitteeNotReady,
+    #[error("not a valid committee DID: {0}")]
+    InvalidCommitteeDid(String),
+    #[error("request {0} not found")]
+    RequestNotFound(String),
+    #[error("could not publish the request: {0}")]
+    TransportError(String),
+}
+
+impl ResponseError for GatewayError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GatewayError::CommitteeNotReady => StatusCode::SERVICE_UNAVAILABLE,
+            GatewayError::InvalidCommitteeDid(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            GatewayError::RequestNotFound(_) => StatusCode::NOT_FOUND,
+            GatewayError::TransportError(_) => StatusCode::BAD_GATEWAY,
+        }
+    }
+}
+
+/// Runs the node's HTTP gateway, blocking the current thread until the server stops.
+pub fn run_gateway(
+    address: &str,
+    port: u16,
+    state: GatewayState,
+    transport: Arc<dyn Transport>,
+) -> anyhow::Result<()> {
+    let gateway = Data::new(Gateway { state, transport });
+    log::info!("HTTP gateway listening on {}:{}", address, port);
+    actix_web::rt::System::new().block_on(
+        HttpServer::new(move || App::new().app_data(gateway.clone()).configure(routes))
+            .workers(1)
+            .bind((address, port))?
+            .run(),
+    )?;
+    Ok(())
+}
+
+fn routes(config: &mut ServiceConfig) {
+    config
+        .service(post_request)
+        .service(get_request)
+        .service(get_committee);
+}
+
+/// Publishes the request on the committee's tag, so that every node of the committee receives
+/// it, and returns its id. Requests without a timestamp are sent with the current time, unless
+/// they are signed by their requester.
+#[post("/requests")]
+async fn post_request(
+    gateway: Data<Gateway>,
+    request: Json<GenericRequest>,
+) -> Result<HttpResponse, GatewayError> {
+    let committee = gateway
+        .state
+        .committee()
+        .ok_or(GatewayError::CommitteeNotReady)?;
+    let committee_tag = did_tag(&committee.did)
+        .ok_or_else(|| GatewayError::InvalidCommitteeDid(committee.did.clone()))?
+        .to_owned();
+    let mut request = request.into_inner();
+    if request.timestamp.is_none() && request.requester_signature.is_none() {
+        request.timestamp = Some(unix_time());
+    }
+    let message =
+        serde_json::to_vec(&request).map_err(|e| GatewayError::TransportError(e.to_string()))?;
+
+    let transport = gateway.transport.clone();
+    let id = web::block(move || transport.publish(&message, Some(committee_tag)))
+        .await
+        .map_err(|e| GatewayError::TransportError(e.to_string()))?
+        .map_err(|e| GatewayError::TransportError(e.to_string()))?;
+    log::info!(
+        "request published through the HTTP gateway (block_id: {})",
+        id
+    );
+
+    gateway.state.init_status(&id, RequestStatus::Pending);
+    Ok(HttpResponse::Accepted().json(RequestResponse {
+        id: id.clone(),
+        status: gateway.state.status(&id).unwrap_or(RequestStatus::Pending),
+    }))
+}
+
+#[get("/requests/{id}")]
+async fn get_request(
+    gateway: Data<Gateway>,
+    id: Path<String>,
+) -> Result<HttpResponse, GatewayError> {
+    let id = id.into_inner();
+    match gateway.state.status(&id) {
+        Some(status) => Ok(HttpResponse::Ok().json(RequestResponse { id, status })),
+        None => Err(GatewayError::RequestNotFound(id)),
+    }
+}
+
+#[get("/committee")]
+async fn get_committee(gateway: Data<Gateway>) -> Result<HttpResponse, GatewayError> {
+    match gateway.state.committee() {
+        Some(committee) => Ok(HttpResponse::Ok().json(committee)),
+        None => Err(GatewayError::CommitteeNotReady),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+    use serde_json::{json, Value};
+
+    use crate::{api::gateway::CommitteeInfo, dlt::memory::MemoryTransport};
+
+    use super::*;
+
+    const COMMITTEE_TAG: &str = "e0288c97b72b8d2503262c432f06d5aedc4";
+
+    fn committee(did: &str) -> CommitteeInfo {
+        CommitteeInfo {
+            did: did.to_owned(),
+            members: vec!["did:local:0x01".to_owned()],
+            public_key: "00".to_owned(),
+        }
+    }
+
+    fn gateway(state: GatewayState, transport: MemoryTransport) -> Data<Gateway> {
+        Data::new(Gateway {
+            state,
+            transport: Arc::new(transport),
+        })
+    }
+
+    fn submit() -> test::TestRequest {
+        test::TestRequest::post().uri("/requests").set_json(json!({
+            "input_uri": "literal:string:data",
+            "storage_uri": "storage:local:key",
+        }))
+    }
+
+    #[actix_web::test]
+    async fn submitted_requests_are_published_to_the_committee() {
+        let state = GatewayState::new();
+        state.set_committee(committee(&format!("did:local:0x{COMMITTEE_TAG}")));
+        let transport = MemoryTransport::new();
+        let published = transport.subscribe(COMMITTEE_TAG.to_owned()).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(gateway(state, transport))
+                .configure(routes),
+        )
+        .await;
+
+        let response = test::call_service(&app, submit().to_request()).await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body["status"], "pending");
+
+        let (message, id) = published.try_recv().unwrap();
+        assert_eq!(body["id"], id);
+        let request: GenericRequest = serde_json::from_slice(&message).unwrap();
+        assert!(request.timestamp.is_some());
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("/requests/{id}"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn requests_are_rejected_without_a_valid_committee() {
+        let state = GatewayState::new();
+        let app = test::init_service(
+            App::new()
+                .app_data(gateway(state.clone(), MemoryTransport::new()))
+                .configure(routes),
+        )
+        .await;
+        let response = test::call_service(&app, submit().to_request()).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        for did in ["did", "did:local:0", "did:local:é"] {
+            state.set_committee(committee(did));
+            let response = test::call_service(&app, submit().to_request()).await;
+            assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    #[actix_web::test]
+    async fn oldest_statuses_are_evicted() {
+        let state = GatewayState::with_max_statuses(2);
+        state.set_committee(committee(&format!("did:local:0x{COMMITTEE_TAG}")));
+        let app = test::init_service(
+            App::new()
+                .app_data(gateway(state, MemoryTransport::new()))
+                .configure(routes),
+        )
+        .await;
+
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let body: Value = test::call_and_read_body_json(&app, submit().to_request()).await;
+            ids.push(body["id"].as_str().unwrap().to_owned());
+        }
+        for (id, status) in ids
+            .iter()
+            .zip([StatusCode::NOT_FOUND, StatusCode::OK, StatusCode::OK])
+        {
+            let response = test::call_service(
+                &app,
+                test::TestRequest::get()
+                    .uri(&format!("/requests/{id}"))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(response.status(), status);
+        }
+    }
+
+    #[actix_web::test]
+    async fn committee_info_is_served_once_known() {
+        let state = GatewayState::new();
+        let app = test::init_service(
+            App::new()
+                .app_data(gateway(state.clone(), MemoryTransport::new()))
+                .configure(routes),
+        )
+        .await;
+        let info = || test::TestRequest::get().uri("/committee").to_request();
+
+        let response = test::call_service(&app, info()).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let did = format!("did:local:0x{COMMITTEE_TAG}");
+        state.set_committee(committee(&did));
+        let body: Value = test::call_and_read_body_json(&app, info()).await;
+        assert_eq!(body["did"], did);
+        assert_eq!(body["members"], json!(["did:local:0x01"]));
+    }
+}
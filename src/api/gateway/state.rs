@@ -0,0 +1,115 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::api::requests::messages::CommitteeLog;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CommitteeInfo {
+    pub did: String,
+    pub members: Vec<String>,
+    /// hex encoded distributed public key
+    pub public_key: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RequestStatus {
+    /// the request has been published but the node did not receive it yet
+    Pending,
+    Processing,
+    Done {
+        log: CommitteeLog,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+/// Time a request's status is kept after the gateway first heard of it.
+const REQUEST_STATUS_TTL: Duration = Duration::from_secs(24 * 3600);
+/// Maximum number of requests whose status is kept, the oldest ones are evicted first.
+const MAX_REQUEST_STATUSES: usize = 10_000;
+
+struct GatewayData {
+    committee: Option<CommitteeInfo>,
+    requests: HashMap<String, RequestStatus>,
+    /// ids of the requests, in the order the gateway heard of them
+    received: VecDeque<(Instant, String)>,
+    max_statuses: usize,
+}
+
+impl GatewayData {
+    fn track(&mut self, request_id: &str) {
+        let now = Instant::now();
+        while let Some((received_at, _)) = self.received.front() {
+            let expired = now.duration_since(*received_at) > REQUEST_STATUS_TTL;
+            if !expired && self.received.len() < self.max_statuses {
+                break;
+            }
+            if let Some((_, evicted)) = self.received.pop_front() {
+                self.requests.remove(&evicted);
+            }
+        }
+        self.received.push_back((now, request_id.to_owned()));
+    }
+}
+
+/// State shared between a node and its HTTP gateway, the node keeps it updated while it
+/// handles the committee's requests.
+#[derive(Clone)]
+pub struct GatewayState(Arc<Mutex<GatewayData>>);
+
+impl Default for GatewayState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GatewayState {
+    pub fn new() -> Self {
+        Self::with_max_statuses(MAX_REQUEST_STATUSES)
+    }
+
+    pub(super) fn with_max_statuses(max_statuses: usize) -> Self {
+        Self(Arc::new(Mutex::new(GatewayData {
+            committee: None,
+            requests: HashMap::new(),
+            received: VecDeque::new(),
+            max_statuses,
+        })))
+    }
+
+    pub fn set_committee(&self, committee: CommitteeInfo) {
+        self.0.lock().unwrap().committee = Some(committee);
+    }
+
+    pub fn committee(&self) -> Option<CommitteeInfo> {
+        self.0.lock().unwrap().committee.clone()
+    }
+
+    pub fn set_status(&self, request_id: &str, status: RequestStatus) {
+        let mut data = self.0.lock().unwrap();
+        if !data.requests.contains_key(request_id) {
+            data.track(request_id);
+        }
+        data.requests.insert(request_id.to_owned(), status);
+    }
+
+    /// Sets the status of a request unless the node already knows about it.
+    pub fn init_status(&self, request_id: &str, status: RequestStatus) {
+        let mut data = self.0.lock().unwrap();
+        if !data.requests.contains_key(request_id) {
+            data.track(request_id);
+            data.requests.insert(request_id.to_owned(), status);
+        }
+    }
+
+    pub fn status(&self, request_id: &str) -> Option<RequestStatus> {
+        self.0.lock().unwrap().requests.get(request_id).cloned()
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use anyhow::bail;
+use identity_iota::core::ToJson;
+use iota_client::crypto::hashes::{blake2b::Blake2b256, Digest};
+use kyber_rs::{group::edwards25519::Point, sign::eddsa};
+use serde::{Deserialize, Serialize};
+
+use crate::store::ObjectMetadata;
+
+use super::messages::{ChallengeResponse, DigestAlgorithm, Hasher};
+
+/// Maximum number of objects challenged by an audit.
+pub const AUDIT_SAMPLE_SIZE: usize = 4;
+
+/// Tag where the committee publishes its audit logs, next to the committee's tag.
+pub fn audit_tag(committee_tag: &str) -> String {
+    format!("{committee_tag}-audit")
+}
+
+/// Nonce of the audit of the given epoch, drawn from the id of the block of the epoch's tick,
+/// which nobody knows before the tick is published.
+pub(crate) fn audit_nonce(committee_did: &str, epoch: u64, tick_id: &str) -> Vec<u8> {
+    let mut hasher = Blake2b256::new();
+    hasher.update(committee_did.as_bytes());
+    hasher.update(epoch.to_be_bytes());
+    hasher.update(tick_id.as_bytes());
+    hasher.finalize().to_vec()
+}
+
+/// Versions challenged by the audit with the given nonce: up to [AUDIT_SAMPLE_SIZE] distinct
+/// versions drawn from the nonce, sorted by key and version.
+pub(crate) fn sample_versions(
+    mut versions: Vec<ObjectMetadata>,
+    nonce: &[u8],
+) -> Vec<ObjectMetadata> {
+    versions.sort_by(|a, b| (&a.key, a.version).cmp(&(&b.key, b.version)));
+    if versions.len() <= AUDIT_SAMPLE_SIZE {
+        return versions;
+    }
+    let mut drawn = BTreeSet::new();
+    let mut counter: u64 = 0;
+    while drawn.len() < AUDIT_SAMPLE_SIZE {
+        let mut hasher = Blake2b256::new();
+        hasher.update(nonce);
+        hasher.update(counter.to_be_bytes());
+        let mut seed = [0u8; 8];
+        seed.copy_from_slice(&hasher.finalize()[..8]);
+        drawn.insert((u64::from_be_bytes(seed) % versions.len() as u64) as usize);
+        counter += 1;
+    }
+    drawn.into_iter().map(|i| versions[i].clone()).collect()
+}
+
+/// Response to the challenge with the given nonce (see [super::messages::challenge_response])
+/// and Blake2b-256 digest of the object written by `stream`, without holding the object in
+/// memory.
+pub(crate) fn stream_response(
+    nonce: &[u8],
+    stream: impl FnOnce(&mut dyn Write) -> anyhow::Result<u64>,
+) -> anyhow::Result<(String, String)> {
+    let mut sink = AuditSink {
+        response: DigestAlgorithm::Blake2b256.hasher(),
+        digest: DigestAlgorithm::Blake2b256.hasher(),
+    };
+    sink.response.write_all(nonce)?;
+    stream(&mut sink)?;
+    Ok((sink.response.finalize(), sink.digest.finalize()))
+}
+
+struct AuditSink {
+    response: Hasher,
+    digest: Hasher,
+}
+
+impl Write for AuditSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.response.write_all(buf)?;
+        self.digest.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Challenges answered by the committee during an audit, this is the part of the
+/// [AuditLog] signed by the committee.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditChallenge {
+    pub(crate) committee_did: String,
+    pub(crate) epoch: u64,
+    /// id of the block of the tick that started the audit, see [audit_nonce]
+    pub(crate) tick_id: String,
+    pub(crate) nonce: String,
+    pub(crate) responses: Vec<ChallengeResponse>,
+}
+
+/// Outcome of a periodic conservation audit: the responses signed by the committee and the
+/// DIDs of the nodes that didn't take part in the signature or provided a wrong one. Each node
+/// also publishes its own signature log for the audit's session.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditLog {
+    #[serde(flatten)]
+    pub(crate) challenge: AuditChallenge,
+    pub(crate) absent_nodes: Vec<String>,
+    pub(crate) bad_signers: Vec<String>,
+    pub(crate) signature_hex: Option<String>,
+}
+
+impl AuditLog {
+    /// Verifies the committee's signature of the audit's challenge against the committee's
+    /// public key, and that its nonce was drawn from the audit's tick.
+    pub fn verify(&self, public_key: &Point) -> anyhow::Result<()> {
+        let challenge = &self.challenge;
+        let nonce = audit_nonce(
+            &challenge.committee_did,
+            challenge.epoch,
+            &challenge.tick_id,
+        );
+        if hex::encode(nonce) != challenge.nonce {
+            bail!("Nonce was not drawn from the audit's tick");
+        }
+        let signature_hex = match &self.signature_hex {
+            Some(signature_hex) => signature_hex,
+            None => bail!("Missing signature"),
+        };
+        eddsa::verify(
+            public_key,
+            &self.challenge.to_jcs()?,
+            &hex::decode(signature_hex)?,
+        )
+        .map_err(|_| anyhow::Error::msg("Signature is not valid"))?;
+        Ok(())
+    }
+
+    pub fn committee_did(&self) -> &str {
+        &self.challenge.committee_did
+    }
+}
+
+impl FromStr for AuditLog {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::de::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::requests::messages::challenge_response;
+
+    use super::*;
+
+    fn versions(count: u64) -> Vec<ObjectMetadata> {
+        (0..count)
+            .map(|i| ObjectMetadata {
+                key: format!("key-{}", i % 3),
+                version: i / 3 + 1,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn sorted(versions: &[ObjectMetadata]) -> bool {
+        versions
+            .windows(2)
+            .all(|pair| (&pair[0].key, pair[0].version) < (&pair[1].key, pair[1].version))
+    }
+
+    #[test]
+    fn samples_every_version_of_small_stores() {
+        let mut small = versions(AUDIT_SAMPLE_SIZE as u64);
+        small.reverse();
+        let sample = sample_versions(small.clone(), b"nonce");
+
+        assert_eq!(sample.len(), AUDIT_SAMPLE_SIZE);
+        assert!(sorted(&sample));
+        assert!(small.iter().all(|metadata| sample.contains(metadata)));
+        assert!(sample_versions(vec![], b"nonce").is_empty());
+    }
+
+    #[test]
+    fn samples_distinct_versions_drawn_from_the_nonce() {
+        let versions = versions(20);
+        let sample = sample_versions(versions.clone(), b"nonce");
+        assert_eq!(sample.len(), AUDIT_SAMPLE_SIZE);
+        assert!(sorted(&sample));
+        assert!(sample.iter().all(|metadata| versions.contains(metadata)));
+
+        // the sample doesn't depend on the order of the versions
+        let mut shuffled = versions.clone();
+        shuffled.reverse();
+        assert_eq!(sample_versions(shuffled, b"nonce"), sample);
+
+        let samples: Vec<_> = (0u8..8)
+            .map(|nonce| sample_versions(versions.clone(), &[nonce]))
+            .collect();
+        assert!(samples.iter().any(|other| *other != samples[0]));
+    }
+
+    #[test]
+    fn nonces_depend_on_the_tick() {
+        let nonce = audit_nonce("did:iota:rms:0x01", 1, "0x01");
+        assert_eq!(nonce, audit_nonce("did:iota:rms:0x01", 1, "0x01"));
+        assert_ne!(nonce, audit_nonce("did:iota:rms:0x01", 1, "0x02"));
+        assert_ne!(nonce, audit_nonce("did:iota:rms:0x01", 2, "0x01"));
+        assert_ne!(nonce, audit_nonce("did:iota:rms:0x02", 1, "0x01"));
+    }
+
+    #[test]
+    fn streamed_responses_match_the_challenge_response() {
+        let data = b"audited data";
+        let (response, digest) = stream_response(b"nonce", |writer| {
+            for chunk in data.chunks(5) {
+                writer.write_all(chunk)?;
+            }
+            Ok(data.len() as u64)
+        })
+        .unwrap();
+
+        assert_eq!(response, challenge_response(b"nonce", data));
+        assert_eq!(digest, DigestAlgorithm::Blake2b256.digest(data));
+    }
+}
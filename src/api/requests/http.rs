@@ -0,0 +1,338 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, ErrorKind, Read, Take},
+    path::Path,
+    time::Duration,
+};
+
+use reqwest::{
+    blocking::{Client, Response},
+    header::{HeaderMap, HeaderName, HeaderValue},
+    redirect::Policy,
+    Method,
+};
+use url::Url;
+
+use super::{
+    messages::{HttpMethod, HttpOptions},
+    ApiNodeError,
+};
+
+pub const DEFAULT_HTTP_TIMEOUT: u64 = 10;
+pub const MAX_HTTP_TIMEOUT: u64 = 60;
+pub const DEFAULT_MAX_RESPONSE_SIZE: u64 = 1024 * 1024;
+pub const MAX_RESPONSE_SIZE: u64 = 16 * 1024 * 1024;
+/// Limits of the responses piped into the storage, see [super::GenericRequest::is_streamed].
+/// Requests are executed one at a time, so the timeout stays short enough not to stall the
+/// node's other requests and the committee's sessions.
+pub const MAX_STREAM_TIMEOUT: u64 = 2 * MAX_HTTP_TIMEOUT;
+pub const MAX_STREAMED_SIZE: u64 = 16 * 1024 * 1024 * 1024;
+
+/// Headers added by a node to the HTTP requests it sends, by url prefix. Secrets are kept in
+/// the node's local configuration, so that API keys are never published on the Tangle. Since
+/// anyone can send requests, secrets are only added to GET requests without a body.
+#[derive(Clone, Debug, Default)]
+pub struct HttpSecrets(Vec<(Url, BTreeMap<String, String>)>);
+
+impl HttpSecrets {
+    /// Loads the secrets from a JSON file such as
+    /// `{"https://api.example.com/v1/prices/": {"Authorization": "Bearer some_token"}}`.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let data = fs::read_to_string(path)?;
+        Self::parse(&data)
+    }
+
+    fn parse(data: &str) -> anyhow::Result<Self> {
+        let secrets: HashMap<String, BTreeMap<String, String>> = serde_json::from_str(data)?;
+        let mut scoped = Vec::with_capacity(secrets.len());
+        for (prefix, headers) in secrets {
+            let prefix = Url::parse(&prefix)
+                .map_err(|e| anyhow::anyhow!("invalid url prefix {prefix}: {e}"))?;
+            if prefix.scheme() != "https" || prefix.host_str().is_none() {
+                anyhow::bail!("url prefix {prefix} is not an https url");
+            }
+            scoped.push((prefix, headers));
+        }
+        Ok(Self(scoped))
+    }
+
+    fn headers<'a>(&'a self, url: &'a Url) -> impl Iterator<Item = (&'a String, &'a String)> {
+        self.0
+            .iter()
+            .filter(|(prefix, _)| has_prefix(url, prefix))
+            .flat_map(|(_, headers)| headers)
+    }
+}
+
+/// Whether the url is on the prefix's origin and its path starts with the prefix's path, at a
+/// segment boundary, so that `https://api.example.com/v1` doesn't cover `/v10`.
+fn has_prefix(url: &Url, prefix: &Url) -> bool {
+    let path = prefix.path();
+    url.scheme() == prefix.scheme()
+        && url.host_str() == prefix.host_str()
+        && url.port_or_known_default() == prefix.port_or_known_default()
+        && (url.path() == path
+            || path.ends_with('/') && url.path().starts_with(path)
+            || url
+                .path()
+                .strip_prefix(path)
+                .is_some_and(|rest| rest.starts_with('/')))
+}
+
+impl From<&HttpMethod> for Method {
+    fn from(method: &HttpMethod) -> Self {
+        match method {
+            HttpMethod::Get => Method::GET,
+            HttpMethod::Post => Method::POST,
+            HttpMethod::Put => Method::PUT,
+            HttpMethod::Patch => Method::PATCH,
+            HttpMethod::Delete => Method::DELETE,
+        }
+    }
+}
+
+pub(crate) fn get_data_from_url(
+    url: &Url,
+    options: Option<&HttpOptions>,
+    secrets: &HttpSecrets,
+) -> Result<Vec<u8>, ApiNodeError> {
+    let (response, max_size) = open_url(url, options, secrets)?;
+    let mut body = Vec::new();
+    response
+        .take(max_size + 1)
+        .read_to_end(&mut body)
+        .map_err(read_error)?;
+    if body.len() as u64 > max_size {
+        return Err(ApiNodeError::ResponseTooLarge);
+    }
+    Ok(body)
+}
+
+/// Sends the request of an url input, returning the response's body to be read up to one
+/// byte past the maximum size, so that larger responses can be detected, and the maximum
+/// size.
+pub(crate) fn stream_from_url(
+    url: &Url,
+    options: Option<&HttpOptions>,
+    secrets: &HttpSecrets,
+) -> Result<(Take<Response>, u64), ApiNodeError> {
+    let (response, max_size) = open_url(url, options, secrets)?;
+    Ok((response.take(max_size + 1), max_size))
+}
+
+/// Errors reading a response's body.
+pub(crate) fn read_error(e: io::Error) -> ApiNodeError {
+    match e.kind() {
+        ErrorKind::TimedOut => ApiNodeError::HttpTimeout,
+        _ => e.into(),
+    }
+}
+
+fn open_url(
+    url: &Url,
+    options: Option<&HttpOptions>,
+    secrets: &HttpSecrets,
+) -> Result<(Response, u64), ApiNodeError> {
+    let default_options = HttpOptions::default();
+    let options = options.unwrap_or(&default_options);
+    let timeout = options.timeout.unwrap_or(DEFAULT_HTTP_TIMEOUT);
+    let max_size = options.max_size.unwrap_or(DEFAULT_MAX_RESPONSE_SIZE);
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in options.headers.iter() {
+        let (name, value) = header(name, value)?;
+        headers.append(name, value);
+    }
+    let mut secret_headers = secrets.headers(url).peekable();
+    let has_secrets = secret_headers.peek().is_some();
+    if has_secrets && (options.method != HttpMethod::Get || options.body.is_some()) {
+        return Err(http_error_msg(
+            "secret headers are only sent with GET requests without a body".to_owned(),
+        ));
+    }
+    // secrets are inserted last and the request can't set the headers they use, so that a
+    // request can neither override nor duplicate them
+    for (name, value) in secret_headers {
+        let (name, value) = header(name, value)?;
+        if options
+            .headers
+            .keys()
+            .any(|requested| requested.eq_ignore_ascii_case(name.as_str()))
+        {
+            return Err(http_error_msg(format!(
+                "header {name} is reserved by the node's secrets"
+            )));
+        }
+        headers.insert(name, value);
+    }
+
+    let mut client = Client::builder().timeout(Duration::from_secs(timeout));
+    if has_secrets {
+        // a redirect could forward the secrets to another host or over plain http
+        client = client.redirect(Policy::none());
+    }
+    let client = client.build().map_err(http_error)?;
+    let mut request = client
+        .request((&options.method).into(), url.as_str())
+        .headers(headers);
+    if let Some(body) = &options.body {
+        request = request.body(body.clone());
+    }
+
+    let response = request.send().map_err(http_error)?;
+    if !response.status().is_success() {
+        return Err(http_error_msg(format!(
+            "unexpected status: {}",
+            response.status()
+        )));
+    }
+    if response.content_length().unwrap_or_default() > max_size {
+        return Err(ApiNodeError::ResponseTooLarge);
+    }
+    Ok((response, max_size))
+}
+
+fn header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue), ApiNodeError> {
+    let name = HeaderName::from_bytes(name.as_bytes())
+        .map_err(|e| http_error_msg(format!("invalid header name {name}: {e}")))?;
+    let value = HeaderValue::from_str(value)
+        .map_err(|e| http_error_msg(format!("invalid value of header {name}: {e}")))?;
+    Ok((name, value))
+}
+
+fn http_error_msg(msg: String) -> ApiNodeError {
+    ApiNodeError::HttpError(anyhow::Error::msg(msg))
+}
+
+fn http_error(e: reqwest::Error) -> ApiNodeError {
+    if e.is_timeout() {
+        ApiNodeError::HttpTimeout
+    } else {
+        http_error_msg(format!("{e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, net::TcpListener, thread};
+
+    use super::*;
+
+    fn secrets() -> HttpSecrets {
+        HttpSecrets::parse(
+            r#"{
+                "https://api.example.com/v1": {"Authorization": "Bearer v1"},
+                "https://api.example.com/prices/": {"X-Api-Key": "prices"}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn header_names(secrets: &HttpSecrets, url: &str) -> Vec<String> {
+        let url = Url::parse(url).unwrap();
+        let mut names: Vec<String> = secrets
+            .headers(&url)
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn secrets_are_scoped_to_url_prefixes() {
+        let secrets = secrets();
+        for url in [
+            "https://api.example.com/v1",
+            "https://api.example.com/v1/prices",
+            "https://api.example.com:443/v1?a=b",
+        ] {
+            assert_eq!(header_names(&secrets, url), vec!["Authorization"]);
+        }
+        assert_eq!(
+            header_names(&secrets, "https://api.example.com/prices/eur"),
+            vec!["X-Api-Key"]
+        );
+        for url in [
+            "https://api.example.com/v10",
+            "https://api.example.com/prices",
+            "https://api.example.com/",
+            "http://api.example.com/v1",
+            "https://api.example.com:8443/v1",
+            "https://api.example.com.evil.com/v1",
+            "https://other.example.com/v1",
+        ] {
+            assert!(header_names(&secrets, url).is_empty(), "{url}");
+        }
+    }
+
+    #[test]
+    fn secrets_need_https_url_prefixes() {
+        for invalid in [
+            r#"{"api.example.com": {"Authorization": "Bearer token"}}"#,
+            r#"{"http://api.example.com/": {"Authorization": "Bearer token"}}"#,
+        ] {
+            assert!(HttpSecrets::parse(invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn secrets_are_only_sent_with_get_requests_without_body() {
+        let secrets = secrets();
+        let url = Url::parse("https://api.example.com/v1/orders").unwrap();
+        let post = HttpOptions {
+            method: HttpMethod::Post,
+            ..HttpOptions::default()
+        };
+        let body = HttpOptions {
+            body: Some("{}".to_owned()),
+            ..HttpOptions::default()
+        };
+        let overriding = HttpOptions {
+            headers: BTreeMap::from([("authorization".to_owned(), "Bearer mine".to_owned())]),
+            ..HttpOptions::default()
+        };
+        for options in [post, body, overriding] {
+            assert!(matches!(
+                get_data_from_url(&url, Some(&options), &secrets),
+                Err(ApiNodeError::HttpError(_))
+            ));
+        }
+    }
+
+    /// Serves a single response with the given body on a local port.
+    fn serve(body: &'static [u8]) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = stream.write_all(body);
+        });
+        url
+    }
+
+    #[test]
+    fn responses_are_limited_to_the_max_size() {
+        let options = HttpOptions {
+            max_size: Some(4),
+            ..HttpOptions::default()
+        };
+        let url = serve(b"data");
+        assert_eq!(
+            get_data_from_url(&url, Some(&options), &HttpSecrets::default()).unwrap(),
+            b"data"
+        );
+        let url = serve(b"more data");
+        assert!(matches!(
+            get_data_from_url(&url, Some(&options), &HttpSecrets::default()),
+            Err(ApiNodeError::ResponseTooLarge)
+        ));
+    }
+}
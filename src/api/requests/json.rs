@@ -0,0 +1,55 @@
+use serde_json::Value;
+
+/// Selects a subset of a JSON document. The path is either a JSON pointer (`/bpi/USD/rate`), a
+/// dotted path (`bpi.USD.rate`, optionally starting with `$.`, array elements are selected by
+/// index) or empty (`""` or `$`) to select the whole document.
+pub fn select<'a>(document: &'a Value, path: &str) -> Option<&'a Value> {
+    if path.starts_with('/') {
+        return document.pointer(path);
+    }
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let path = path.strip_prefix('.').unwrap_or(path);
+    if path.is_empty() {
+        return Some(document);
+    }
+    path.split('.')
+        .try_fold(document, |value, key| match value {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn selects_pointers_and_dotted_paths() {
+        let document = json!({"bpi": {"USD": {"rate": 1.5}}, "items": [{"id": 7}]});
+        for path in ["/bpi/USD/rate", "bpi.USD.rate", "$.bpi.USD.rate"] {
+            assert_eq!(select(&document, path), Some(&json!(1.5)), "{path}");
+        }
+        assert_eq!(select(&document, "items.0.id"), Some(&json!(7)));
+        assert_eq!(select(&document, "/items/0/id"), Some(&json!(7)));
+        for path in ["", "$"] {
+            assert_eq!(select(&document, path), Some(&document));
+        }
+    }
+
+    #[test]
+    fn missing_values_are_not_selected() {
+        let document = json!({"bpi": {"USD": {"rate": 1.5}}, "items": [{"id": 7}]});
+        for path in [
+            "/bpi/EUR",
+            "bpi.EUR.rate",
+            "items.1.id",
+            "items.first",
+            "bpi.USD.rate.x",
+        ] {
+            assert_eq!(select(&document, path), None, "{path}");
+        }
+    }
+}
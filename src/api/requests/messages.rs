@@ -1,13 +1,32 @@
 use core::fmt;
-use std::str::FromStr;
-
+use std::{
+    collections::BTreeMap,
+    io,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::bail;
+use base64::{engine::general_purpose::STANDARD, Engine};
 use enum_display::EnumDisplay;
 
+use identity_iota::core::ToJson;
+use iota_client::crypto::hashes::{blake2b::Blake2b256, Digest};
+use kyber_rs::{
+    encoding::{BinaryMarshaler, BinaryUnmarshaler},
+    group::edwards25519::Point,
+    sign::eddsa::{self, EdDSA},
+    util::key::Pair,
+};
 use serde::{Deserialize, Deserializer, Serialize};
+use sha2::Sha256;
 use thiserror::Error;
 use url::Url;
 
-use super::NodeMessage;
+use super::{
+    http::{MAX_HTTP_TIMEOUT, MAX_RESPONSE_SIZE, MAX_STREAMED_SIZE, MAX_STREAM_TIMEOUT},
+    NodeMessage,
+};
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct StorageLocalUri(pub String);
@@ -25,6 +44,7 @@ impl ToString for IotaMessageUri {
 
 #[derive(Clone, Deserialize, Debug, PartialEq, Eq)]
 pub enum InputUri {
+    None,
     Iota(IotaMessageUri),
     Local(StorageLocalUri),
     Literal(String),
@@ -45,6 +65,7 @@ impl Serialize for InputUri {
             },
             InputUri::Literal(s) => serializer.serialize_str(&format!("literal:string:{s}")),
             InputUri::Url(u) => serializer.serialize_str(u.as_str()),
+            InputUri::None => serializer.serialize_str("none"),
         }
     }
 }
@@ -84,6 +105,10 @@ impl Serialize for StorageUri {
 pub enum UriDeserializeError {
     #[error("not a valid URI")]
     InvalidUri,
+    #[error("not a valid encoding, supported values are: utf8, base64 and hex")]
+    InvalidEncoding,
+    #[error("not a valid digest algorithm, supported values are: blake2b256 and sha256")]
+    UnsupportedDigestAlgorithm,
 }
 
 fn deserialize_input_uri<'de, D>(deserializer: D) -> Result<InputUri, D::Error>
@@ -235,6 +260,10 @@ impl FromStr for InputUri {
     type Err = UriDeserializeError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s == "none" {
+            return Ok(InputUri::None);
+        }
+
         if let Ok(uri) = IotaMessageUri::from_str(s) {
             return Ok(InputUri::Iota(uri));
         }
@@ -244,9 +273,11 @@ impl FromStr for InputUri {
         }
 
         {
-            let parts: Vec<_> = s.split(':').collect();
-            if let ("literal", "string") = (parts[0], parts[1]) {
-                return Ok(InputUri::Literal(parts[2..].join(":")));
+            let mut parts = s.splitn(3, ':');
+            if let (Some("literal"), Some("string")) = (parts.next(), parts.next()) {
+                return Ok(InputUri::Literal(
+                    parts.next().unwrap_or_default().to_owned(),
+                ));
             }
         }
 
@@ -258,6 +289,12 @@ impl FromStr for InputUri {
     }
 }
 
+impl Default for InputUri {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub enum OutputUri {
     None,
@@ -294,6 +331,406 @@ impl Default for Execution {
     }
 }
 
+/// Version of the [GenericRequest] format, requests without a version are from before the
+/// `operation` field was introduced.
+pub const REQUEST_VERSION: u32 = 2;
+/// Current unix time [s], the timestamp of the requests sent now.
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Largest difference [s] between the timestamp of a request and the clock of a node handling
+/// it, which covers the clients' clock skew and the time requests wait in the nodes' queue.
+pub const TIMESTAMP_TOLERANCE: u64 = 600;
+
+/// Minimum size [bytes] of the nonce of a challenge request.
+pub const MIN_NONCE_SIZE: usize = 16;
+
+/// Operation requested to the committee.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum Operation {
+    /// publishes the input's data in the committee's log
+    Get,
+    /// stores the input's data at the request's storage uri
+    Store,
+    /// deletes the object at the request's storage uri
+    Delete,
+    /// lists the stored keys starting with `prefix`, as a sorted JSON array
+    List {
+        prefix: String,
+    },
+    /// publishes the metadata of the latest version of the object at the request's storage
+    /// uri, see [crate::store::ObjectMetadata]
+    Head,
+    /// publishes whether an object exists at the request's storage uri
+    Exists,
+    Execute,
+    /// publishes the aggregation of the numeric values observed by the nodes in the input
+    Oracle,
+    /// stores the input's data under its digest, see [content_key]
+    StoreContent {
+        algorithm: DigestAlgorithm,
+    },
+    /// publishes the digest of the object at the request's storage uri, along with a timestamp,
+    /// without publishing the object
+    Prove,
+    /// publishes the digest of the hex encoded nonce followed by the object at the request's
+    /// storage uri, proving that the committee holds the object without publishing it
+    Challenge {
+        nonce: String,
+    },
+    /// publishes the data sealed to the committee's key at the request's storage uri, once a
+    /// threshold of the nodes decrypted it together, encrypted to the recipient recorded when
+    /// sealing it, who must sign the request, see [super::SealedData]
+    Reveal,
+    /// stores the input's data erasure-coded across the committee, each node keeping only
+    /// its shard, see [super::StoredShard]
+    StoreShards,
+    /// publishes the object reconstructed from the shards of the committee
+    GetShards,
+    /// publishes the given version of the object at the request's storage uri
+    GetVersion {
+        version: u64,
+    },
+}
+
+/// How an oracle request extracts a numeric value from the input and aggregates the values
+/// observed by the nodes.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OracleOptions {
+    /// JSON pointer or path of the value in the input, e.g. `/bpi/USD/rate_float` or
+    /// `bpi.USD.rate_float`
+    pub pointer: String,
+    #[serde(default = "Default::default")]
+    pub aggregation: Aggregation,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub enum Aggregation {
+    #[default]
+    Median,
+    /// mean of the values, after discarding the `trim` fraction of the lowest and of the
+    /// highest ones
+    TrimmedMean { trim: f64 },
+}
+
+impl Aggregation {
+    pub fn aggregate(&self, values: &[f64]) -> Option<f64> {
+        let mut values = values.to_vec();
+        values.sort_by(|a, b| a.total_cmp(b));
+        let n = values.len();
+        if n == 0 {
+            return None;
+        }
+        match self {
+            Aggregation::Median => match n % 2 {
+                0 => Some((values[n / 2 - 1] + values[n / 2]) / 2.0),
+                _ => Some(values[n / 2]),
+            },
+            Aggregation::TrimmedMean { trim } => {
+                if !(0.0..0.5).contains(trim) {
+                    return None;
+                }
+                let k = (n as f64 * trim).floor() as usize;
+                let kept = &values[k..n - k];
+                Some(kept.iter().sum::<f64>() / kept.len() as f64)
+            }
+        }
+    }
+}
+
+/// Aggregated value signed by the committee for an oracle request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OracleResult {
+    pub value: f64,
+    /// DIDs of the nodes whose observations were aggregated, sorted
+    pub contributors: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+/// How the nodes query an url input. Headers holding secrets must not be set here, as requests
+/// are public, they are added by each node from its local configuration.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct HttpOptions {
+    #[serde(default = "Default::default")]
+    pub method: HttpMethod,
+    #[serde(default = "Default::default")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// maximum time [s] to wait for the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// maximum size [bytes] of the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u64>,
+}
+
+/// Encoding of the data in a [CommitteeLog], logs without encoding hold utf8 data.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum DataEncoding {
+    Utf8,
+    Base64,
+    Hex,
+}
+
+impl DataEncoding {
+    pub fn encode(&self, data: &[u8]) -> Result<String, FailureReason> {
+        match self {
+            DataEncoding::Utf8 => {
+                String::from_utf8(data.to_vec()).map_err(|_| FailureReason::InvalidData)
+            }
+            DataEncoding::Base64 => Ok(STANDARD.encode(data)),
+            DataEncoding::Hex => Ok(hex::encode(data)),
+        }
+    }
+
+    pub fn decode(&self, data: &str) -> anyhow::Result<Vec<u8>> {
+        match self {
+            DataEncoding::Utf8 => Ok(data.as_bytes().to_vec()),
+            DataEncoding::Base64 => Ok(STANDARD.decode(data)?),
+            DataEncoding::Hex => Ok(hex::decode(data)?),
+        }
+    }
+}
+
+impl FromStr for DataEncoding {
+    type Err = UriDeserializeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "utf8" => Ok(DataEncoding::Utf8),
+            "base64" => Ok(DataEncoding::Base64),
+            "hex" => Ok(DataEncoding::Hex),
+            _ => Err(UriDeserializeError::InvalidEncoding),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestAlgorithm {
+    Blake2b256,
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    /// Hex encoded digest of the data.
+    pub fn digest(&self, data: &[u8]) -> String {
+        match self {
+            DigestAlgorithm::Blake2b256 => hex::encode(Blake2b256::digest(data)),
+            DigestAlgorithm::Sha256 => hex::encode(Sha256::digest(data)),
+        }
+    }
+
+    /// Incremental digest of streamed data, e.g. with [io::copy].
+    pub fn hasher(&self) -> Hasher {
+        match self {
+            DigestAlgorithm::Blake2b256 => Hasher::Blake2b256(Blake2b256::new()),
+            DigestAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+        }
+    }
+}
+
+pub enum Hasher {
+    Blake2b256(Blake2b256),
+    Sha256(Sha256),
+}
+
+impl Hasher {
+    /// Hex encoded digest of the written data.
+    pub fn finalize(self) -> String {
+        match self {
+            Hasher::Blake2b256(hasher) => hex::encode(hasher.finalize()),
+            Hasher::Sha256(hasher) => hex::encode(hasher.finalize()),
+        }
+    }
+}
+
+impl io::Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Hasher::Blake2b256(hasher) => hasher.update(buf),
+            Hasher::Sha256(hasher) => hasher.update(buf),
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DigestAlgorithm::Blake2b256 => f.write_str("blake2b256"),
+            DigestAlgorithm::Sha256 => f.write_str("sha256"),
+        }
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = UriDeserializeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blake2b256" => Ok(DigestAlgorithm::Blake2b256),
+            "sha256" => Ok(DigestAlgorithm::Sha256),
+            _ => Err(UriDeserializeError::UnsupportedDigestAlgorithm),
+        }
+    }
+}
+
+/// Storage key of content-addressed objects, e.g. `blake2b256-{hex digest}`.
+pub fn content_key(algorithm: DigestAlgorithm, digest: &str) -> String {
+    format!("{algorithm}-{digest}")
+}
+
+/// Digest algorithm of a content-addressed key, `None` for keys chosen by clients.
+pub fn content_algorithm(key: &str) -> Option<DigestAlgorithm> {
+    let (algorithm, digest) = key.split_once('-')?;
+    let algorithm = DigestAlgorithm::from_str(algorithm).ok()?;
+    (digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit())).then_some(algorithm)
+}
+
+/// Proof that the committee holds an object, signed in place of the object itself.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConservationProof {
+    pub key: String,
+    pub algorithm: DigestAlgorithm,
+    pub digest: String,
+    /// unix time [s] of the request, checked by every node against its clock, absent if the
+    /// request had no timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+}
+
+/// Answer of the committee to a challenge request, see [challenge_response].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChallengeResponse {
+    pub key: String,
+    /// version of the object challenged by an audit, challenge requests answer for the latest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+    pub nonce: String,
+    /// hex encoded Blake2b-256 digest of the nonce followed by the object
+    pub response: String,
+}
+
+/// Hex encoded Blake2b-256 digest of `nonce || data`, which can only be computed by holding
+/// the data, and can't be precomputed without knowing the nonce.
+pub fn challenge_response(nonce: &[u8], data: &[u8]) -> String {
+    let mut hasher = Blake2b256::new();
+    hasher.update(nonce);
+    hasher.update(data);
+    hex::encode(hasher.finalize())
+}
+
+/// Reason of a failed request, reported in the [CommitteeLog].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Error)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    #[error("request version is not supported")]
+    UnsupportedVersion,
+    #[error("request has no operation")]
+    MissingOperation,
+    #[error("operation is not supported")]
+    UnsupportedOperation,
+    #[error("operation requires an input uri")]
+    MissingInput,
+    #[error("operation does not accept an input uri")]
+    UnexpectedInput,
+    #[error("operation requires a storage uri")]
+    MissingStorageUri,
+    #[error("operation does not accept a storage uri")]
+    UnexpectedStorageUri,
+    #[error("input could not be retrieved")]
+    InputUnavailable,
+    #[error("data is not a valid utf8 string")]
+    InvalidData,
+    #[error("result could not be serialized")]
+    SerializationFailed,
+    #[error("object could not be encoded into shards")]
+    EncodingFailed,
+    #[error("storage operation failed")]
+    StorageFailure,
+    #[error("oracle operation requires oracle options")]
+    MissingOracleOptions,
+    #[error("oracle options are not valid")]
+    InvalidOracleOptions,
+    #[error("oracle operation requires an url input")]
+    UnsupportedInput,
+    #[error("not enough nodes observed a value")]
+    NotEnoughObservations,
+    #[error("operation does not accept a json path")]
+    UnexpectedJsonPath,
+    #[error("input is not a valid JSON document")]
+    InvalidJson,
+    #[error("json path not found in the input")]
+    JsonPathNotFound,
+    #[error("operation does not accept http options")]
+    UnexpectedHttpOptions,
+    #[error("http options are not valid")]
+    InvalidHttpOptions,
+    #[error("http request timed out")]
+    HttpTimeout,
+    #[error("http response exceeds the maximum size")]
+    ResponseTooLarge,
+    #[error("stored object does not match its digest")]
+    CorruptedData,
+    #[error("challenge nonce must be a hex string of at least 16 bytes")]
+    InvalidNonce,
+    #[error("stored object is not sealed data")]
+    NotSealed,
+    #[error("not enough valid partial decryptions")]
+    NotEnoughShares,
+    #[error("stored object is not a shard")]
+    NotSharded,
+    #[error("not enough valid shards to reconstruct the object")]
+    NotEnoughShards,
+    #[error("node is not a member of the committee")]
+    NotMember,
+    #[error("operation does not accept a content type")]
+    UnexpectedContentType,
+    #[error("operation does not accept a time-to-live")]
+    UnexpectedTtl,
+    #[error("time-to-live must be positive")]
+    InvalidTtl,
+    #[error("object exceeds the maximum object size")]
+    ObjectTooLarge,
+    #[error("committee's storage quota exceeded")]
+    CommitteeQuotaExceeded,
+    #[error("requester's storage quota exceeded")]
+    RequesterQuotaExceeded,
+    #[error("request is not signed by its requester")]
+    InvalidRequesterSignature,
+    #[error("requester is not the recipient of the sealed data")]
+    NotRecipient,
+    #[error("requester did not store the object")]
+    Unauthorized,
+    #[error("request does not name the store request of the object's latest version")]
+    StaleVersion,
+    #[error("signed requests and requests with a time-to-live require a timestamp")]
+    MissingTimestamp,
+    #[error("request timestamp is too far from the current time")]
+    InvalidTimestamp,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct RequestId(pub String);
 
@@ -316,8 +753,17 @@ fn default_signature_flag() -> bool {
     false
 }
 
+fn default_request_version() -> u32 {
+    1
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct GenericRequest {
+    #[serde(default = "default_request_version")]
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation: Option<Operation>,
+    #[serde(default = "Default::default")]
     #[serde(deserialize_with = "deserialize_input_uri")]
     pub input_uri: InputUri,
     #[serde(default = "Default::default")]
@@ -330,6 +776,226 @@ pub struct GenericRequest {
     #[serde(default = "Default::default")]
     #[serde(deserialize_with = "deserialize_storage_uri")]
     pub storage_uri: StorageUri,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oracle: Option<OracleOptions>,
+    /// subset of the JSON input selected by get and store operations, see [super::json::select]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpOptions>,
+    /// encoding of the data in the committee's log, see [DataEncoding]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_encoding: Option<DataEncoding>,
+    /// content type recorded in the metadata of the objects stored by the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// time [s] after which the objects stored by the request expire and are deleted by the
+    /// garbage collection, see [super::DeletionLog]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+    /// hex encoded Ed25519 public key of the requester whose quota the stored objects count
+    /// towards, see [super::Quotas]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requester: Option<String>,
+    /// hex encoded signature of the request by the requester, see
+    /// [GenericRequest::sign_as_requester]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requester_signature: Option<String>,
+    /// unix time [s] at which the request was sent, recorded by the stored objects and the
+    /// conservation proofs, so that every node records the same time, see
+    /// [GenericRequest::timestamp]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    /// id of the store request of the latest version of the object deleted by a signed delete
+    /// request, so that publishing the request again can't delete a later store, see
+    /// [crate::store::ObjectMetadata::request_id]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_request_id: Option<String>,
+}
+
+impl GenericRequest {
+    /// Returns the requested operation, checking that the request's fields are consistent with
+    /// it. Requests older than version 2 are a store request if they have a storage uri and a
+    /// get request otherwise.
+    pub fn operation(&self) -> Result<Operation, FailureReason> {
+        if self.version > REQUEST_VERSION {
+            return Err(FailureReason::UnsupportedVersion);
+        }
+        let operation = match (&self.operation, self.version) {
+            (Some(operation), _) => operation.clone(),
+            (None, 0 | 1) => match self.storage_uri {
+                StorageUri::Storage(_) => Operation::Store,
+                StorageUri::None => Operation::Get,
+            },
+            (None, _) => return Err(FailureReason::MissingOperation),
+        };
+
+        let has_input = self.input_uri != InputUri::None;
+        let has_storage = self.storage_uri != StorageUri::None;
+        let (needs_input, needs_storage) = match operation {
+            Operation::Get | Operation::Oracle | Operation::StoreContent { .. } => (true, false),
+            Operation::Store | Operation::StoreShards => (true, true),
+            Operation::Delete
+            | Operation::Head
+            | Operation::Exists
+            | Operation::Prove
+            | Operation::Challenge { .. }
+            | Operation::Reveal
+            | Operation::GetShards
+            | Operation::GetVersion { .. } => (false, true),
+            Operation::List { .. } => (false, false),
+            Operation::Execute => return Err(FailureReason::UnsupportedOperation),
+        };
+        match (needs_input, has_input) {
+            (true, false) => return Err(FailureReason::MissingInput),
+            (false, true) => return Err(FailureReason::UnexpectedInput),
+            _ => (),
+        }
+        match (needs_storage, has_storage) {
+            (true, false) => return Err(FailureReason::MissingStorageUri),
+            (false, true) => return Err(FailureReason::UnexpectedStorageUri),
+            _ => (),
+        }
+        if self.json_path.is_some()
+            && !matches!(
+                operation,
+                Operation::Get
+                    | Operation::Store
+                    | Operation::StoreContent { .. }
+                    | Operation::StoreShards
+            )
+        {
+            return Err(FailureReason::UnexpectedJsonPath);
+        }
+        if self.content_type.is_some()
+            && !matches!(
+                operation,
+                Operation::Store | Operation::StoreContent { .. } | Operation::StoreShards
+            )
+        {
+            return Err(FailureReason::UnexpectedContentType);
+        }
+        if let Some(ttl) = self.ttl {
+            if !matches!(
+                operation,
+                Operation::Store | Operation::StoreContent { .. } | Operation::StoreShards
+            ) {
+                return Err(FailureReason::UnexpectedTtl);
+            }
+            if ttl == 0 {
+                return Err(FailureReason::InvalidTtl);
+            }
+        }
+        if let Some(http) = &self.http {
+            if !matches!(self.input_uri, InputUri::Url(_)) {
+                return Err(FailureReason::UnexpectedHttpOptions);
+            }
+            // streamed inputs are not held in memory, they can be larger and slower
+            let (max_timeout, max_response_size) = match self.is_streamed(&operation) {
+                true => (MAX_STREAM_TIMEOUT, MAX_STREAMED_SIZE),
+                false => (MAX_HTTP_TIMEOUT, MAX_RESPONSE_SIZE),
+            };
+            let valid_timeout = http
+                .timeout
+                .is_none_or(|timeout| (1..=max_timeout).contains(&timeout));
+            let valid_size = http
+                .max_size
+                .is_none_or(|max_size| max_size <= max_response_size);
+            if !valid_timeout || !valid_size {
+                return Err(FailureReason::InvalidHttpOptions);
+            }
+        }
+        if operation == Operation::Oracle {
+            match &self.oracle {
+                None => return Err(FailureReason::MissingOracleOptions),
+                Some(OracleOptions {
+                    aggregation: Aggregation::TrimmedMean { trim },
+                    ..
+                }) if !(0.0..0.5).contains(trim) => {
+                    return Err(FailureReason::InvalidOracleOptions)
+                }
+                _ => (),
+            }
+            if !matches!(self.input_uri, InputUri::Url(_)) {
+                return Err(FailureReason::UnsupportedInput);
+            }
+        }
+        if let Operation::Challenge { nonce } = &operation {
+            match hex::decode(nonce) {
+                Ok(nonce) if nonce.len() >= MIN_NONCE_SIZE => (),
+                _ => return Err(FailureReason::InvalidNonce),
+            }
+        }
+        self.requester_key()?;
+        Ok(operation)
+    }
+
+    /// Signs the request with the requester's key pair, declaring its public key as the
+    /// requester.
+    pub fn sign_as_requester(&mut self, keypair: &Pair<Point>) -> anyhow::Result<()> {
+        self.requester = Some(hex::encode(keypair.public.marshal_binary()?));
+        let signature = EdDSA::from(keypair.clone()).sign(&self.requester_message()?)?;
+        self.requester_signature = Some(hex::encode(signature));
+        Ok(())
+    }
+
+    /// Time of the request, checking that it is within [TIMESTAMP_TOLERANCE] of `now`, the
+    /// node's time. Nodes only disagree on requests sent with a timestamp close to the
+    /// tolerance, which honest clients never do. Requests from clients predating timestamps
+    /// have none, which is only allowed if they are neither signed nor have a `ttl`.
+    pub fn timestamp(&self, now: u64) -> Result<Option<u64>, FailureReason> {
+        let timestamp = match self.timestamp {
+            Some(timestamp) => timestamp,
+            None if self.requester_signature.is_some() || self.ttl.is_some() => {
+                return Err(FailureReason::MissingTimestamp)
+            }
+            None => return Ok(None),
+        };
+        if timestamp.abs_diff(now) > TIMESTAMP_TOLERANCE {
+            return Err(FailureReason::InvalidTimestamp);
+        }
+        Ok(Some(timestamp))
+    }
+
+    /// Public key of the requester, checking that it signed the request.
+    pub fn requester_key(&self) -> Result<Option<Point>, FailureReason> {
+        let (requester, signature) = match (&self.requester, &self.requester_signature) {
+            (None, None) => return Ok(None),
+            (Some(requester), Some(signature)) => (requester, signature),
+            _ => return Err(FailureReason::InvalidRequesterSignature),
+        };
+        let mut public_key = Point::default();
+        let valid = match (hex::decode(requester), hex::decode(signature)) {
+            (Ok(requester), Ok(signature)) => {
+                public_key.unmarshal_binary(&requester).is_ok()
+                    && self.requester_message().is_ok_and(|message| {
+                        eddsa::verify(&public_key, &message, &signature).is_ok()
+                    })
+            }
+            _ => false,
+        };
+        match valid {
+            true => Ok(Some(public_key)),
+            false => Err(FailureReason::InvalidRequesterSignature),
+        }
+    }
+
+    /// Bytes signed by the requester: the canonical JSON of the request without its signature.
+    fn requester_message(&self) -> anyhow::Result<Vec<u8>> {
+        let unsigned = GenericRequest {
+            requester_signature: None,
+            ..self.clone()
+        };
+        Ok(unsigned.to_jcs()?)
+    }
+
+    /// Whether the input is piped into the storage instead of being held in memory, which is
+    /// the case of store requests with an url input and no json path.
+    pub fn is_streamed(&self, operation: &Operation) -> bool {
+        *operation == Operation::Store
+            && matches!(self.input_uri, InputUri::Url(_))
+            && self.json_path.is_none()
+    }
 }
 
 #[derive(Error, Debug, EnumDisplay)]
@@ -340,8 +1006,14 @@ pub enum GenericRequestParseError {
 impl TryInto<NodeMessage> for GenericRequest {
     type Error = GenericRequestParseError;
 
+    /// Requests without an operation, which predate it, must have an input, as they always did,
+    /// so that the other payloads published on the committee's tag, e.g. the nodes' signature
+    /// logs, are never taken for requests.
     fn try_into(self) -> Result<NodeMessage, Self::Error> {
-        Ok(NodeMessage::GenericRequest(self))
+        if self.operation.is_none() && self.input_uri == InputUri::None {
+            return Err(GenericRequestParseError::NotAValidRequest);
+        }
+        Ok(NodeMessage::GenericRequest(Box::new(self)))
     }
 }
 
@@ -352,9 +1024,44 @@ pub struct CommitteeLog {
     pub(crate) result: ResponseState,
     pub(crate) output_uri: Option<OutputUri>,
     pub(crate) data: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) data_encoding: Option<DataEncoding>,
+    /// hex encoded digest of the object stored or reconstructed by the request, Blake2b-256
+    /// unless another algorithm was requested for a content-addressed store
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) digest: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<FailureReason>,
     pub(crate) signature_hex: Option<String>,
 }
 
+impl CommitteeLog {
+    /// Verifies the committee's signature of the log against the committee's public key.
+    pub fn verify(&self, public_key: &Point) -> anyhow::Result<()> {
+        let mut unsigned = self.clone();
+        let signature_hex = match unsigned.signature_hex.take() {
+            Some(signature_hex) => signature_hex,
+            None => bail!("Missing signature"),
+        };
+        eddsa::verify(
+            public_key,
+            &unsigned.to_jcs()?,
+            &hex::decode(signature_hex)?,
+        )
+        .map_err(|_| anyhow::Error::msg("Signature is not valid"))?;
+        Ok(())
+    }
+
+    /// Decodes the data of the log, if any.
+    pub fn decoded_data(&self) -> anyhow::Result<Option<Vec<u8>>> {
+        let data_encoding = self.data_encoding.clone().unwrap_or(DataEncoding::Utf8);
+        self.data
+            .as_ref()
+            .map(|data| data_encoding.decode(data))
+            .transpose()
+    }
+}
+
 #[derive(Error, Debug, EnumDisplay)]
 pub enum CommitteeLogParseError {
     NotAValidResponse,
@@ -367,3 +1074,177 @@ impl FromStr for CommitteeLog {
         serde_json::de::from_str(s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median() {
+        assert_eq!(Aggregation::Median.aggregate(&[]), None);
+        assert_eq!(Aggregation::Median.aggregate(&[3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(
+            Aggregation::Median.aggregate(&[4.0, 1.0, 3.0, 2.0]),
+            Some(2.5)
+        );
+    }
+
+    #[test]
+    fn trimmed_mean() {
+        let values = [100.0, 2.0, 1.0, 3.0, -50.0];
+        assert_eq!(
+            Aggregation::TrimmedMean { trim: 0.0 }.aggregate(&values),
+            Some(11.2)
+        );
+        assert_eq!(
+            Aggregation::TrimmedMean { trim: 0.2 }.aggregate(&values),
+            Some(2.0)
+        );
+        // the trimmed count is rounded down
+        assert_eq!(
+            Aggregation::TrimmedMean { trim: 0.1 }.aggregate(&values),
+            Some(11.2)
+        );
+        assert_eq!(Aggregation::TrimmedMean { trim: 0.2 }.aggregate(&[]), None);
+        for trim in [-0.1, 0.5, f64::NAN] {
+            assert_eq!(Aggregation::TrimmedMean { trim }.aggregate(&values), None);
+        }
+    }
+
+    #[test]
+    fn input_uris_round_trip() {
+        for uri in [
+            InputUri::None,
+            InputUri::Iota(IotaMessageUri("0x01".to_owned())),
+            InputUri::Local(StorageLocalUri("key".to_owned())),
+            InputUri::Literal("with: colons".to_owned()),
+            InputUri::Literal(String::new()),
+            InputUri::Url(Url::parse("https://example.com/a?b=c").unwrap()),
+        ] {
+            let serialized = serde_json::to_value(&uri).unwrap();
+            assert_eq!(
+                InputUri::from_str(serialized.as_str().unwrap()).unwrap(),
+                uri
+            );
+        }
+        assert_eq!(InputUri::from_str("").unwrap(), InputUri::None);
+    }
+
+    #[test]
+    fn storage_uris_round_trip() {
+        for uri in [
+            StorageUri::None,
+            StorageUri::Storage(StorageLocalUri("key".to_owned())),
+        ] {
+            let serialized = serde_json::to_value(&uri).unwrap();
+            assert_eq!(deserialize_storage_uri(serialized).unwrap(), uri);
+        }
+        assert_eq!(
+            deserialize_storage_uri(serde_json::Value::from("")).unwrap(),
+            StorageUri::None
+        );
+        for invalid in ["storage:local", "storage:remote:key", "iota:message:0x01"] {
+            assert!(deserialize_storage_uri(serde_json::Value::from(invalid)).is_err());
+            assert!(StorageLocalUri::from_str(invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn only_requests_parse_as_requests() {
+        let parse = |payload: serde_json::Value| {
+            serde_json::from_value::<GenericRequest>(payload)
+                .ok()
+                .and_then(|request| TryInto::<NodeMessage>::try_into(request).ok())
+        };
+        assert!(parse(serde_json::json!({"input_uri": "literal:string:data"})).is_some());
+        // fields of newer clients are ignored
+        assert!(
+            parse(serde_json::json!({"input_uri": "literal:string:data", "field": 1})).is_some()
+        );
+        assert!(
+            parse(serde_json::json!({"version": 2, "operation": {"List": {"prefix": ""}}}))
+                .is_some()
+        );
+
+        assert!(parse(serde_json::json!({})).is_none());
+        assert!(parse(serde_json::to_value(CommitteeLog::default()).unwrap()).is_none());
+        assert!(parse(serde_json::json!({
+            "session_id": "0123456789",
+            "sender_did": "did:iota:rms:0x01",
+            "absent_nodes": [],
+            "bad_signers": [],
+            "signature_hex": null,
+        }))
+        .is_none());
+    }
+
+    #[test]
+    fn timestamps_are_checked_against_the_tolerance() {
+        let mut request: GenericRequest =
+            serde_json::from_value(serde_json::json!({"input_uri": "literal:string:data"}))
+                .unwrap();
+        let now = 1_700_000_000;
+        for timestamp in [now, now - TIMESTAMP_TOLERANCE, now + TIMESTAMP_TOLERANCE] {
+            request.timestamp = Some(timestamp);
+            assert_eq!(request.timestamp(now), Ok(Some(timestamp)));
+        }
+        for timestamp in [
+            0,
+            now - TIMESTAMP_TOLERANCE - 1,
+            now + TIMESTAMP_TOLERANCE + 1,
+        ] {
+            request.timestamp = Some(timestamp);
+            assert_eq!(request.timestamp(now), Err(FailureReason::InvalidTimestamp));
+        }
+    }
+
+    #[test]
+    fn requests_without_timestamps_are_signed_or_expiring_only() {
+        // a store request of the clients predating the operations and the timestamps
+        let mut request: GenericRequest = serde_json::from_value(serde_json::json!({
+            "input_uri": "literal:string:data",
+            "storage_uri": "storage:local:key",
+        }))
+        .unwrap();
+        assert_eq!(request.operation(), Ok(Operation::Store));
+        assert_eq!(request.timestamp(1_700_000_000), Ok(None));
+
+        request.ttl = Some(60);
+        assert_eq!(
+            request.timestamp(1_700_000_000),
+            Err(FailureReason::MissingTimestamp)
+        );
+        request.ttl = None;
+        let suite = kyber_rs::group::edwards25519::SuiteEd25519::new_blake3_sha256_ed25519();
+        let requester = kyber_rs::util::key::new_key_pair(&suite).unwrap();
+        request.sign_as_requester(&requester).unwrap();
+        assert_eq!(
+            request.timestamp(1_700_000_000),
+            Err(FailureReason::MissingTimestamp)
+        );
+    }
+
+    #[test]
+    fn parses_input_uris() {
+        assert_eq!(InputUri::from_str("none").unwrap(), InputUri::None);
+        assert_eq!(
+            InputUri::from_str("storage:local:key").unwrap(),
+            InputUri::Local(StorageLocalUri("key".to_owned()))
+        );
+        assert_eq!(
+            InputUri::from_str("literal:string:a:b").unwrap(),
+            InputUri::Literal("a:b".to_owned())
+        );
+        assert_eq!(
+            InputUri::from_str("literal:string").unwrap(),
+            InputUri::Literal(String::new())
+        );
+        for uri in ["foo", "literal"] {
+            assert!(InputUri::from_str(uri).is_err(), "{uri}");
+        }
+        assert!(!matches!(
+            InputUri::from_str("storage:local"),
+            Ok(InputUri::Local(_))
+        ));
+    }
+}
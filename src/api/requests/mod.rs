@@ -5,13 +5,31 @@ use serde::{Deserialize, Serialize};
 use self::messages::CommitteeLog;
 pub use self::messages::GenericRequest;
 
+mod audit;
+mod http;
+mod json;
 pub mod messages;
+mod quota;
+mod repair;
+mod retention;
+mod schedule;
+mod sealed;
+mod shards;
+mod spool;
+mod sync;
 
 mod node;
+pub use audit::{audit_tag, AuditLog};
+pub use http::HttpSecrets;
 pub use node::*;
+pub use quota::Quotas;
+pub use retention::{deletions_tag, DeletionLog};
+pub use schedule::{EpochSchedule, EpochTick, ScheduledTask};
+pub use sealed::{DleqProof, SealedData};
+pub use sync::{sync_tag, SyncCommittees, SyncReport, SyncServer};
 
 #[derive(Clone, Debug, EnumDisplay, Serialize, Deserialize)]
 pub enum NodeMessage {
-    GenericRequest(GenericRequest),
+    GenericRequest(Box<GenericRequest>),
     GenericResponse(CommitteeLog),
 }
@@ -1,51 +1,113 @@
-use std::{
-    io::Read,
-    str::{FromStr, Utf8Error},
-};
+use std::{collections::BTreeMap, io::Write, sync::Arc};
 
 use identity_iota::core::ToJson;
-use iota_client::{
-    block::{payload::Payload, BlockId},
-    Client,
-};
 use kyber_rs::{
+    encoding::BinaryMarshaler,
     group::edwards25519::{Point, Scalar, SuiteEd25519},
-    share::dkg::rabin::DistKeyGenerator,
+    share::{
+        dkg::rabin::DistKeyGenerator,
+        poly::{PubPoly, PubShare},
+    },
+    sign::eddsa::EdDSA,
+    util::key::Pair,
 };
 use thiserror::Error;
 
 use crate::{
-    logging::{new_signature_log, signature_log_target, NodeSignatureLogger},
+    did::DidRegistry,
+    dlt::Transport,
+    logging::{
+        new_signature_log, public_to_did, signature_log_target, NodeSignatureLog,
+        NodeSignatureLogger,
+    },
     net::channel::{Receiver, Sender},
     states::{
         feed::{Feed, MessageWrapper},
         fsm::StateMachine,
+        oracle::{self, ObservationParams, OracleMessage, OracleTerminalStates},
+        reveal::{self, DecryptionParams, PartialDecryption, RevealMessage, RevealTerminalStates},
+        shards::{self, GatheringParams, ShardMessage, ShardTerminalStates},
         sign::{self, SignMessage, SignTerminalStates, SignTypes, Signature},
+        sync::{
+            Comparing, Fetching, IndexEntry, Indexing, ObjectRequest, SyncMessage, SyncParams,
+            SyncTerminalStates,
+        },
     },
-    store::Storage,
+    store::{ObjectMetadata, Storage},
 };
 
 use super::{
-    messages::{self, CommitteeLog, InputUri, IotaMessageUri, StorageLocalUri, StorageUri},
+    audit::{audit_nonce, sample_versions, stream_response, AuditChallenge, AuditLog},
+    http::{get_data_from_url, read_error, stream_from_url, HttpSecrets},
+    json,
+    messages::{
+        self, challenge_response, content_algorithm, content_key, unix_time, ChallengeResponse,
+        CommitteeLog, ConservationProof, DataEncoding, DigestAlgorithm, FailureReason, HttpOptions,
+        InputUri, IotaMessageUri, Operation, OracleResult, StorageLocalUri, StorageUri,
+    },
+    quota::Quotas,
+    repair::{check_versions, merkle_root},
+    retention::{DeletionLog, DeletionRecord},
+    schedule::EpochSchedule,
+    sealed::{combine_partials, SealedData},
+    shards::StoredShard,
+    spool::Spool,
+    sync::{ObjectVersion, SyncRecord, SyncReport},
     GenericRequest, NodeMessage,
 };
-use url::Url;
 
 pub struct ApiParams {
-    pub client: Client,
+    pub transport: Arc<dyn Transport>,
     pub dkg: DistKeyGenerator<SuiteEd25519>,
     pub secret: Scalar,
     pub public_key: Point,
     pub id: usize,
     pub(crate) signature_sender: std::sync::mpsc::Sender<MessageWrapper<SignMessage>>,
     pub(crate) signature_sleep_time: u64,
+    pub(crate) oracle_sender: std::sync::mpsc::Sender<MessageWrapper<OracleMessage>>,
+    pub(crate) reveal_sender: std::sync::mpsc::Sender<MessageWrapper<RevealMessage>>,
+    pub(crate) shard_sender: std::sync::mpsc::Sender<MessageWrapper<ShardMessage>>,
+    pub(crate) sync_sender: std::sync::mpsc::Sender<MessageWrapper<SyncMessage>>,
+    pub(crate) http_secrets: HttpSecrets,
+    pub(crate) quotas: Quotas,
 }
 
 pub struct HandlerParams {
     pub signature_logger: NodeSignatureLogger,
     pub committee_did: String,
     pub dids: Vec<String>,
-    pub node_url: String,
+    pub registry: Arc<dyn DidRegistry>,
+}
+
+/// Channels where the nodes exchange their observations for oracle requests.
+pub struct OracleChannels<R, S> {
+    pub input: R,
+    pub output: S,
+}
+
+/// Channels where the nodes exchange their partial decryptions for reveal requests.
+pub struct RevealChannels<R, S> {
+    pub input: R,
+    pub output: S,
+}
+
+/// Channels where the nodes exchange their shards for erasure-coded objects.
+pub struct ShardChannels<R, S> {
+    pub input: R,
+    pub output: S,
+}
+
+/// Channels where the node requests the objects kept by its peers when syncing its storage.
+pub struct SyncChannels<R, S> {
+    pub input: R,
+    pub output: S,
+}
+
+/// Channels of the protocols run by some requests, besides the committee's signature.
+pub struct ProtocolChannels<OR, OS, RR, RS, SR, SS> {
+    pub oracle: OracleChannels<OR, OS>,
+    pub reveal: RevealChannels<RR, RS>,
+    pub shards: ShardChannels<SR, SS>,
 }
 
 pub struct ApiNode {
@@ -59,15 +121,24 @@ impl ApiNode {
         message: NodeMessage,
         nodes_input: impl Receiver<MessageWrapper<SignMessage>>,
         nodes_output: impl Sender<MessageWrapper<SignMessage>>,
+        protocol_channels: ProtocolChannels<
+            impl Receiver<MessageWrapper<OracleMessage>>,
+            impl Sender<MessageWrapper<OracleMessage>>,
+            impl Receiver<MessageWrapper<RevealMessage>>,
+            impl Sender<MessageWrapper<RevealMessage>>,
+            impl Receiver<MessageWrapper<ShardMessage>>,
+            impl Sender<MessageWrapper<ShardMessage>>,
+        >,
         session_id: &str,
         handler_params: HandlerParams,
     ) -> Result<Option<(CommitteeLog, Vec<String>)>, ApiNodeError> {
         match message {
             NodeMessage::GenericRequest(r) => Ok(Some(self.handle_request(
-                r,
+                *r,
                 session_id,
                 nodes_input,
                 nodes_output,
+                protocol_channels,
                 handler_params,
             )?)),
             m => {
@@ -83,6 +154,14 @@ impl ApiNode {
         session_id: &str,
         sign_input: impl Receiver<MessageWrapper<SignMessage>>,
         sign_output: impl Sender<MessageWrapper<SignMessage>>,
+        protocol_channels: ProtocolChannels<
+            impl Receiver<MessageWrapper<OracleMessage>>,
+            impl Sender<MessageWrapper<OracleMessage>>,
+            impl Receiver<MessageWrapper<RevealMessage>>,
+            impl Sender<MessageWrapper<RevealMessage>>,
+            impl Receiver<MessageWrapper<ShardMessage>>,
+            impl Sender<MessageWrapper<ShardMessage>>,
+        >,
         handler_params: HandlerParams,
     ) -> Result<(CommitteeLog, Vec<String>), ApiNodeError> {
         let mut committee_log = CommitteeLog {
@@ -91,72 +170,640 @@ impl ApiNode {
             ..Default::default()
         };
 
-        let data = match self.get_data(&request.input_uri) {
-            Ok(d) => d,
-            Err(_e) => {
-                return self.sign_request_logs(
-                    committee_log,
-                    session_id.to_owned(),
-                    sign_input,
-                    sign_output,
-                    handler_params,
+        let result = self
+            .execute_request(
+                &request,
+                session_id,
+                protocol_channels,
+                &handler_params,
+                &mut committee_log,
+            )
+            .and_then(|data| {
+                data.map(|data| encode_data(&data, request.data_encoding.as_ref()))
+                    .transpose()
+            });
+        match result {
+            Ok(data) => {
+                committee_log.result = messages::ResponseState::Success;
+                if let Some((data, data_encoding)) = data {
+                    committee_log.data = Some(data);
+                    committee_log.data_encoding = Some(data_encoding);
+                }
+            }
+            Err(reason) => {
+                log::warn!("request [{}] failed: {}", session_id, reason);
+                committee_log.error = Some(reason);
+            }
+        }
+
+        let (committee_log, working_nodes) = self.sign_request_logs(
+            committee_log,
+            session_id.to_owned(),
+            sign_input,
+            sign_output,
+            handler_params,
+        )?;
+        // the signed log proves the stored version to the peers syncing it later
+        let stored = matches!(
+            request.operation(),
+            Ok(Operation::Store | Operation::StoreContent { .. } | Operation::StoreShards)
+        );
+        if stored && matches!(committee_log.result, messages::ResponseState::Success) {
+            let kept = serde_json::to_vec(&committee_log)
+                .map_err(anyhow::Error::from)
+                .and_then(|log| self.storage.put_log(session_id, &log));
+            if let Err(e) = kept {
+                log::warn!(
+                    "request [{}]: could not keep the signed log: {}",
+                    session_id,
+                    e
                 );
             }
+        }
+        Ok((committee_log, working_nodes))
+    }
+
+    /// Executes the request's operation, returning the data to publish in the committee's log.
+    /// The digest of stored objects is set in the log directly.
+    fn execute_request(
+        &self,
+        request: &GenericRequest,
+        session_id: &str,
+        protocol_channels: ProtocolChannels<
+            impl Receiver<MessageWrapper<OracleMessage>>,
+            impl Sender<MessageWrapper<OracleMessage>>,
+            impl Receiver<MessageWrapper<RevealMessage>>,
+            impl Sender<MessageWrapper<RevealMessage>>,
+            impl Receiver<MessageWrapper<ShardMessage>>,
+            impl Sender<MessageWrapper<ShardMessage>>,
+        >,
+        handler_params: &HandlerParams,
+        committee_log: &mut CommitteeLog,
+    ) -> Result<Option<Vec<u8>>, FailureReason> {
+        let operation = request.operation()?;
+        let item_name = match &request.storage_uri {
+            StorageUri::Storage(StorageLocalUri(item_name)) => item_name.as_str(),
+            StorageUri::None => "",
         };
-        match request.storage_uri {
-            // in this case it is a store request
-            StorageUri::Storage(StorageLocalUri(item_name)) => {
-                match self.storage.put(item_name, &data) {
-                    Ok(()) => {
-                        committee_log.result = messages::ResponseState::Success;
-                        self.sign_request_logs(
-                            committee_log,
-                            session_id.to_owned(),
-                            sign_input,
-                            sign_output,
-                            handler_params,
-                        )
+        match operation {
+            Operation::Get => {
+                let data = self
+                    .get_data(&request.input_uri, request.http.as_ref())
+                    .map_err(|e| {
+                        log::warn!("could not get input: {}", e);
+                        FailureReason::from(e)
+                    })?;
+                Ok(Some(select_data(data, request.json_path.as_deref())?))
+            }
+            Operation::Store if request.is_streamed(&operation) => {
+                let digest = self.store_stream(request, session_id, item_name)?;
+                committee_log.digest = Some(digest);
+                Ok(None)
+            }
+            Operation::Store => {
+                let data = self
+                    .get_data(&request.input_uri, request.http.as_ref())
+                    .map_err(|e| {
+                        log::warn!("could not get input: {}", e);
+                        FailureReason::from(e)
+                    })?;
+                let data = select_data(data, request.json_path.as_deref())?;
+                self.check_quotas(request, item_name, data.len() as u64)?;
+                let digest = DigestAlgorithm::Blake2b256.digest(&data);
+                let metadata =
+                    self.new_metadata(request, session_id, digest.clone(), data.len() as u64)?;
+                self.storage
+                    .put_version(item_name, &data, metadata)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                committee_log.digest = Some(digest);
+                Ok(None)
+            }
+            Operation::StoreContent { algorithm } => {
+                let data = self
+                    .get_data(&request.input_uri, request.http.as_ref())
+                    .map_err(|e| {
+                        log::warn!("could not get input: {}", e);
+                        FailureReason::from(e)
+                    })?;
+                let data = select_data(data, request.json_path.as_deref())?;
+                let digest = algorithm.digest(&data);
+                let key = content_key(algorithm, &digest);
+                self.check_quotas(request, &key, data.len() as u64)?;
+                let metadata = self.new_metadata(
+                    request,
+                    session_id,
+                    DigestAlgorithm::Blake2b256.digest(&data),
+                    data.len() as u64,
+                )?;
+                self.storage
+                    .put_version(&key, &data, metadata)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                committee_log.digest = Some(digest);
+                Ok(Some(key.into_bytes()))
+            }
+            Operation::Prove => {
+                // objects stored by clients' keys are proven with the default algorithm
+                let content_addressed = content_algorithm(item_name);
+                let algorithm = content_addressed.unwrap_or(DigestAlgorithm::Blake2b256);
+                let mut hasher = algorithm.hasher();
+                self.storage
+                    .get_stream(item_name, &mut hasher)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                let digest = hasher.finalize();
+                if content_addressed.is_some() && content_key(algorithm, &digest) != item_name {
+                    return Err(FailureReason::CorruptedData);
+                }
+                let proof = ConservationProof {
+                    key: item_name.to_owned(),
+                    algorithm,
+                    digest,
+                    timestamp: request.timestamp(unix_time())?,
+                };
+                Ok(Some(
+                    proof
+                        .to_jcs()
+                        .map_err(|_| FailureReason::SerializationFailed)?,
+                ))
+            }
+            Operation::Challenge { nonce } => {
+                let nonce_bytes = hex::decode(&nonce).map_err(|_| FailureReason::InvalidNonce)?;
+                // same as challenge_response, without holding the object in memory
+                let mut hasher = DigestAlgorithm::Blake2b256.hasher();
+                hasher
+                    .write_all(&nonce_bytes)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                self.storage
+                    .get_stream(item_name, &mut hasher)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                let response = ChallengeResponse {
+                    key: item_name.to_owned(),
+                    version: None,
+                    response: hasher.finalize(),
+                    nonce,
+                };
+                Ok(Some(
+                    response
+                        .to_jcs()
+                        .map_err(|_| FailureReason::SerializationFailed)?,
+                ))
+            }
+            Operation::Delete => {
+                let latest = self
+                    .storage
+                    .metadata(item_name)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                check_deletion(request, latest.as_ref())?;
+                self.storage
+                    .delete(item_name)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                Ok(None)
+            }
+            Operation::List { prefix } => {
+                // keys are sorted by the storage, so that every node signs the same list
+                let keys = self
+                    .storage
+                    .list(&prefix)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                Ok(Some(
+                    serde_json::to_vec(&keys).map_err(|_| FailureReason::SerializationFailed)?,
+                ))
+            }
+            Operation::Head => {
+                let metadata = self
+                    .storage
+                    .metadata(item_name)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                match metadata {
+                    Some(metadata) => Ok(Some(
+                        metadata
+                            .to_jcs()
+                            .map_err(|_| FailureReason::SerializationFailed)?,
+                    )),
+                    // objects stored before versioning only have their size
+                    None => {
+                        let info = self
+                            .storage
+                            .head(item_name)
+                            .map_err(|_| FailureReason::StorageFailure)?;
+                        Ok(Some(
+                            serde_json::to_vec(&info)
+                                .map_err(|_| FailureReason::SerializationFailed)?,
+                        ))
                     }
-
-                    Err(_) => self.sign_request_logs(
-                        committee_log,
-                        session_id.to_owned(),
-                        sign_input,
-                        sign_output,
-                        handler_params,
-                    ),
                 }
             }
-            // in this case it is a get request
-            StorageUri::None => {
-                let data = match self.get_data(&request.input_uri) {
-                    Ok(d) => d,
-                    Err(_e) => {
-                        return self.sign_request_logs(
-                            committee_log,
-                            session_id.to_owned(),
-                            sign_input,
-                            sign_output,
-                            handler_params,
+            Operation::GetVersion { version } => {
+                let data = self
+                    .storage
+                    .get_version(item_name, version)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                Ok(Some(data))
+            }
+            Operation::Exists => {
+                let exists = self
+                    .storage
+                    .exists(item_name)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                Ok(Some(exists.to_string().into_bytes()))
+            }
+            Operation::Oracle => {
+                let options = request
+                    .oracle
+                    .as_ref()
+                    .ok_or(FailureReason::MissingOracleOptions)?;
+                let value = self.observe(request, &options.pointer);
+                if value.is_none() {
+                    log::warn!("request [{}]: could not observe a value", session_id);
+                }
+                let observations = self.run_oracle(value, session_id, protocol_channels.oracle)?;
+
+                let values: Vec<f64> = observations.iter().map(|(_, value)| *value).collect();
+                let value = options
+                    .aggregation
+                    .aggregate(&values)
+                    .ok_or(FailureReason::NotEnoughObservations)?;
+                let mut contributors = Vec::with_capacity(observations.len());
+                for (owner, _) in observations {
+                    contributors.push(
+                        public_to_did(
+                            &handler_params.dids,
+                            owner,
+                            handler_params.registry.as_ref(),
                         )
-                    }
-                };
+                        .map_err(|_| FailureReason::NotEnoughObservations)?,
+                    );
+                }
+                contributors.sort();
 
-                let data_utf8 = match std::str::from_utf8(&data) {
-                    Ok(text) => text.to_string(),
-                    Err(e) => return Err(ApiNodeError::ConversionError(e)),
+                let result = OracleResult {
+                    value,
+                    contributors,
                 };
+                // canonical JSON, so that every node signs the same bytes
+                Ok(Some(
+                    result
+                        .to_jcs()
+                        .map_err(|_| FailureReason::SerializationFailed)?,
+                ))
+            }
+            Operation::Reveal => {
+                let data = self
+                    .storage
+                    .get(item_name)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                let sealed: SealedData =
+                    serde_json::from_slice(&data).map_err(|_| FailureReason::NotSealed)?;
+                let ephemeral = sealed
+                    .ephemeral_key()
+                    .map_err(|_| FailureReason::NotSealed)?;
+                // the data is only revealed to the recipient chosen when sealing it
+                let recipient = sealed
+                    .recipient_key()
+                    .map_err(|_| FailureReason::NotSealed)?
+                    .ok_or(FailureReason::NotRecipient)?;
+                if request.requester_key()? != Some(recipient) {
+                    return Err(FailureReason::NotRecipient);
+                }
+                let partials = self.run_reveal(&ephemeral, session_id, protocol_channels.reveal)?;
 
-                committee_log.result = messages::ResponseState::Success;
-                committee_log.data = Some(data_utf8);
-
-                self.sign_request_logs(
-                    committee_log,
-                    session_id.to_owned(),
-                    sign_input,
-                    sign_output,
-                    handler_params,
+                let shares: Vec<PubShare<Point>> = partials
+                    .into_iter()
+                    .map(|partial| PubShare {
+                        i: partial.index,
+                        v: partial.value,
+                    })
+                    .collect();
+                let dkg = &self.api_params.dkg;
+                let shared =
+                    combine_partials(&shares, dkg.t, dkg.participants.len()).map_err(|e| {
+                        log::warn!("request [{}]: {}", session_id, e);
+                        FailureReason::NotEnoughShares
+                    })?;
+                let data = sealed.open(&shared).map_err(|e| {
+                    log::warn!("request [{}]: {}", session_id, e);
+                    FailureReason::NotSealed
+                })?;
+                // every node derives the same ciphertext, so that they sign the same output
+                let output = SealedData::seal_deterministic(
+                    &recipient,
+                    &shared,
+                    session_id.as_bytes(),
+                    &data,
                 )
+                .map_err(|_| FailureReason::SerializationFailed)?;
+                Ok(Some(
+                    serde_json::to_vec(&output).map_err(|_| FailureReason::SerializationFailed)?,
+                ))
+            }
+            Operation::StoreShards => {
+                let data = self
+                    .get_data(&request.input_uri, request.http.as_ref())
+                    .map_err(|e| {
+                        log::warn!("could not get input: {}", e);
+                        FailureReason::from(e)
+                    })?;
+                let data = select_data(data, request.json_path.as_deref())?;
+                let index = self.shard_index().ok_or(FailureReason::NotMember)?;
+                let dkg = &self.api_params.dkg;
+                let stored = StoredShard::new(&data, index, dkg.t, dkg.participants.len() - dkg.t)
+                    .map_err(|e| {
+                        log::warn!("request [{}]: could not encode shards: {}", session_id, e);
+                        FailureReason::EncodingFailed
+                    })?;
+                let object =
+                    serde_json::to_vec(&stored).map_err(|_| FailureReason::SerializationFailed)?;
+                // quotas apply to the whole object, so that every node takes the same decision
+                self.check_quotas(request, item_name, stored.size as u64)?;
+                // the metadata describes the whole object, so that every node records the same
+                let metadata = self.new_metadata(
+                    request,
+                    session_id,
+                    stored.digest.clone(),
+                    stored.size as u64,
+                )?;
+                self.storage
+                    .put_version(item_name, &object, metadata)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                committee_log.digest = Some(stored.digest);
+                Ok(None)
+            }
+            Operation::GetShards => {
+                let object = self
+                    .storage
+                    .get(item_name)
+                    .map_err(|_| FailureReason::StorageFailure)?;
+                let stored = StoredShard::parse(&object).ok_or(FailureReason::NotSharded)?;
+                let shards = self.run_shards(&stored, session_id, protocol_channels.shards)?;
+                let data = stored.reconstruct(shards).map_err(|e| {
+                    log::warn!("request [{}]: {}", session_id, e);
+                    FailureReason::NotEnoughShards
+                })?;
+                committee_log.digest = Some(stored.digest);
+                Ok(Some(data))
+            }
+            Operation::Execute => Err(FailureReason::UnsupportedOperation),
+        }
+    }
+
+    /// Pipes the response of the request's url input into the storage, through a temporary
+    /// file so that the object is never held in memory. Returns the digest of the object.
+    fn store_stream(
+        &self,
+        request: &GenericRequest,
+        session_id: &str,
+        key: &str,
+    ) -> Result<String, FailureReason> {
+        let url = match &request.input_uri {
+            InputUri::Url(url) => url,
+            _ => return Err(FailureReason::UnsupportedInput),
+        };
+        let (mut response, max_size) =
+            stream_from_url(url, request.http.as_ref(), &self.api_params.http_secrets).map_err(
+                |e| {
+                    log::warn!("could not get input: {}", e);
+                    FailureReason::from(e)
+                },
+            )?;
+        let mut spool = Spool::new(&mut response).map_err(|e| {
+            log::warn!("could not get input: {}", e);
+            FailureReason::from(read_error(e))
+        })?;
+        if spool.size > max_size {
+            return Err(FailureReason::ResponseTooLarge);
+        }
+        self.check_quotas(request, key, spool.size)?;
+
+        let metadata = self.new_metadata(request, session_id, spool.digest.clone(), spool.size)?;
+        self.storage
+            .put_version_stream(key, spool.file(), metadata)
+            .map_err(|e| {
+                log::warn!("request [{}]: could not store the input: {}", session_id, e);
+                FailureReason::StorageFailure
+            })?;
+        Ok(spool.digest.clone())
+    }
+
+    /// Checks that storing a new version of `size` bytes at `key` fits the committee's quotas.
+    fn check_quotas(
+        &self,
+        request: &GenericRequest,
+        key: &str,
+        size: u64,
+    ) -> Result<(), FailureReason> {
+        // requests without a timestamp see no object expired
+        let now = request.timestamp(unix_time())?.unwrap_or_default();
+        let usage = self
+            .storage
+            .usage(now)
+            .map_err(|_| FailureReason::StorageFailure)?;
+        // an expired object counts as new, whether or not the garbage collection deleted it
+        let new_object = self
+            .storage
+            .metadata(key)
+            .map_err(|_| FailureReason::StorageFailure)?
+            .is_none_or(|metadata| metadata.expired(now));
+        self.api_params
+            .quotas
+            .check(&usage, request.requester.as_deref(), new_object, size)
+            .map_err(|reason| {
+                log::warn!("could not store {}: {}", key, reason);
+                reason
+            })
+    }
+
+    /// Metadata of an object stored by the request, its version is set by the storage.
+    fn new_metadata(
+        &self,
+        request: &GenericRequest,
+        session_id: &str,
+        digest: String,
+        size: u64,
+    ) -> Result<ObjectMetadata, FailureReason> {
+        let timestamp = request.timestamp(unix_time())?;
+        Ok(ObjectMetadata {
+            key: String::new(),
+            version: 0,
+            digest,
+            size,
+            content_type: request.content_type.clone(),
+            request_id: session_id.to_owned(),
+            timestamp,
+            // requests with a ttl have a timestamp
+            expires_at: timestamp
+                .zip(request.ttl)
+                .map(|(timestamp, ttl)| timestamp.saturating_add(ttl)),
+            requester: request.requester.clone(),
+        })
+    }
+
+    /// Extracts the numeric value at `pointer` from the JSON input, if any.
+    fn observe(&self, request: &GenericRequest, pointer: &str) -> Option<f64> {
+        let data = self
+            .get_data(&request.input_uri, request.http.as_ref())
+            .ok()?;
+        let json: serde_json::Value = serde_json::from_slice(&data).ok()?;
+        let value = match json::select(&json, pointer)? {
+            serde_json::Value::Number(number) => number.as_f64(),
+            serde_json::Value::String(string) => string.trim().parse().ok(),
+            _ => None,
+        };
+        value.filter(|value| value.is_finite())
+    }
+
+    /// Exchanges the node's observation with its peers, returning the values observed by the
+    /// committee.
+    fn run_oracle(
+        &self,
+        value: Option<f64>,
+        session_id: &str,
+        oracle_channels: OracleChannels<
+            impl Receiver<MessageWrapper<OracleMessage>>,
+            impl Sender<MessageWrapper<OracleMessage>>,
+        >,
+    ) -> Result<Vec<(Point, f64)>, FailureReason> {
+        let keypair = Pair {
+            private: self.api_params.secret,
+            public: self.api_params.public_key,
+        };
+        let initial_state = oracle::Collecting::new(
+            keypair,
+            session_id.to_owned(),
+            value,
+            ObservationParams {
+                participants: self.api_params.dkg.participants.clone(),
+                threshold: self.api_params.dkg.t,
+                sender: self.api_params.oracle_sender.clone(),
+                sleep_time: self.api_params.signature_sleep_time,
+            },
+        )
+        .map_err(|e| {
+            log::error!("could not create observation: {}", e);
+            FailureReason::NotEnoughObservations
+        })?;
+
+        let mut oracle_fsm = StateMachine::new(
+            Box::new(initial_state),
+            session_id.to_owned(),
+            Feed::new(oracle_channels.input, session_id.to_owned()),
+            oracle_channels.output,
+        );
+        match oracle_fsm.run() {
+            Ok(OracleTerminalStates::Completed(observations)) => Ok(observations),
+            Ok(OracleTerminalStates::Failed) => Err(FailureReason::NotEnoughObservations),
+            Err(e) => {
+                log::error!("observations exchange failed: {}", e);
+                Err(FailureReason::NotEnoughObservations)
+            }
+        }
+    }
+
+    /// Exchanges the node's partial decryption of the ephemeral key with its peers, returning
+    /// the partial decryptions of the committee proven against the public shares of its key.
+    fn run_reveal(
+        &self,
+        ephemeral: &Point,
+        session_id: &str,
+        reveal_channels: RevealChannels<
+            impl Receiver<MessageWrapper<RevealMessage>>,
+            impl Sender<MessageWrapper<RevealMessage>>,
+        >,
+    ) -> Result<Vec<PartialDecryption>, FailureReason> {
+        let keypair = Pair {
+            private: self.api_params.secret,
+            public: self.api_params.public_key,
+        };
+        let dist_key_share = self.api_params.dkg.dist_key_share().map_err(|e| {
+            log::error!("could not get the node's key share: {}", e);
+            FailureReason::NotEnoughShares
+        })?;
+        let public_poly = PubPoly::new(
+            &SuiteEd25519::new_blake3_sha256_ed25519(),
+            None,
+            &dist_key_share.commits,
+        );
+        let participants = self.api_params.dkg.participants.clone();
+        let public_shares = (0..participants.len())
+            .map(|i| public_poly.eval(i).v)
+            .collect();
+        let initial_state = reveal::Decrypting::new(
+            keypair,
+            session_id.to_owned(),
+            dist_key_share.share,
+            *ephemeral,
+            DecryptionParams {
+                participants,
+                public_shares,
+                threshold: self.api_params.dkg.t,
+                sender: self.api_params.reveal_sender.clone(),
+                sleep_time: self.api_params.signature_sleep_time,
+            },
+        )
+        .map_err(|e| {
+            log::error!("could not create partial decryption: {}", e);
+            FailureReason::NotEnoughShares
+        })?;
+
+        let mut reveal_fsm = StateMachine::new(
+            Box::new(initial_state),
+            session_id.to_owned(),
+            Feed::new(reveal_channels.input, session_id.to_owned()),
+            reveal_channels.output,
+        );
+        match reveal_fsm.run() {
+            Ok(RevealTerminalStates::Completed(partials)) => Ok(partials),
+            Ok(RevealTerminalStates::Failed) => Err(FailureReason::NotEnoughShares),
+            Err(e) => {
+                log::error!("partial decryptions exchange failed: {}", e);
+                Err(FailureReason::NotEnoughShares)
+            }
+        }
+    }
+
+    /// Exchanges the node's shard of an erasure-coded object with its peers, returning the
+    /// valid shards gathered from the committee.
+    fn run_shards(
+        &self,
+        stored: &StoredShard,
+        session_id: &str,
+        shard_channels: ShardChannels<
+            impl Receiver<MessageWrapper<ShardMessage>>,
+            impl Sender<MessageWrapper<ShardMessage>>,
+        >,
+    ) -> Result<Vec<(usize, Vec<u8>)>, FailureReason> {
+        let keypair = Pair {
+            private: self.api_params.secret,
+            public: self.api_params.public_key,
+        };
+        let initial_state = shards::Gathering::new(
+            keypair,
+            session_id.to_owned(),
+            stored.index,
+            stored.shard().ok(),
+            GatheringParams {
+                participants: self.api_params.dkg.participants.clone(),
+                threshold: stored.data_shards,
+                shard_digests: stored.shard_digests.clone(),
+                sender: self.api_params.shard_sender.clone(),
+                sleep_time: self.api_params.signature_sleep_time,
+            },
+        )
+        .map_err(|e| {
+            log::error!("could not encrypt the node's shard: {}", e);
+            FailureReason::NotEnoughShards
+        })?;
+
+        let mut shard_fsm = StateMachine::new(
+            Box::new(initial_state),
+            session_id.to_owned(),
+            Feed::new(shard_channels.input, session_id.to_owned()),
+            shard_channels.output,
+        );
+        match shard_fsm.run() {
+            Ok(ShardTerminalStates::Completed(shards)) => Ok(shards),
+            Ok(ShardTerminalStates::Failed) => Err(FailureReason::NotEnoughShards),
+            Err(e) => {
+                log::error!("shards exchange failed: {}", e);
+                Err(FailureReason::NotEnoughShards)
             }
         }
     }
@@ -180,12 +827,12 @@ impl ApiNode {
             Ok(state) => state,
             Err(e) => return Err(ApiNodeError::SignatureError(e)),
         };
-        let (signature, working_nodes) = manage_signature_terminal_state(
+        let (signature, _, working_nodes) = manage_signature_terminal_state(
             final_state,
             &session_id,
             handler_params.dids,
             handler_params.signature_logger,
-            handler_params.node_url,
+            handler_params.registry.as_ref(),
         )
         .map_err(ApiNodeError::SignatureError)?;
         committee_log.signature_hex = Some(hex::encode(signature.0));
@@ -193,33 +840,655 @@ impl ApiNode {
         Ok((committee_log, working_nodes))
     }
 
-    fn get_data(&self, location: &InputUri) -> Result<Vec<u8>, ApiNodeError> {
+    /// Runs the conservation audit of the given epoch: the node answers the epoch's challenge
+    /// for a sample of the versions logged by the committee and the committee signs the
+    /// responses. Returns `None` when there is nothing to audit.
+    pub fn audit(
+        &self,
+        epoch: u64,
+        tick_id: &str,
+        sign_input: impl Receiver<MessageWrapper<SignMessage>>,
+        sign_output: impl Sender<MessageWrapper<SignMessage>>,
+        handler_params: HandlerParams,
+    ) -> Result<Option<(AuditLog, Vec<String>)>, ApiNodeError> {
+        let nonce = audit_nonce(&handler_params.committee_did, epoch, tick_id);
+        let nonce_hex = hex::encode(&nonce);
+        let mut logged = vec![];
+        for key in self
+            .storage
+            .versioned_keys()
+            .map_err(ApiNodeError::StorageError)?
+        {
+            for metadata in self
+                .storage
+                .versions(&key)
+                .map_err(ApiNodeError::StorageError)?
+            {
+                if self
+                    .storage
+                    .has_log(&metadata.request_id)
+                    .map_err(ApiNodeError::StorageError)?
+                {
+                    logged.push(metadata);
+                }
+            }
+        }
+        let mut responses = vec![];
+        for metadata in sample_versions(logged, &nonce) {
+            let streamed = stream_response(&nonce, |writer| {
+                self.storage
+                    .get_version_stream(&metadata.key, metadata.version, writer)
+            });
+            match streamed {
+                Ok((response, digest)) if digest == metadata.digest => {
+                    responses.push(ChallengeResponse {
+                        key: metadata.key,
+                        version: Some(metadata.version),
+                        nonce: nonce_hex.clone(),
+                        response,
+                    })
+                }
+                Ok(_) => match self
+                    .storage
+                    .get_version(&metadata.key, metadata.version)
+                    .ok()
+                    .and_then(|data| StoredShard::parse(&data))
+                {
+                    // every node keeps a different shard: once it checked its own, the node
+                    // answers for the digests of the shards, which the committee shares
+                    Some(stored) if stored.digest == metadata.digest && stored.is_intact() => {
+                        responses.push(ChallengeResponse {
+                            key: metadata.key,
+                            version: Some(metadata.version),
+                            nonce: nonce_hex.clone(),
+                            response: challenge_response(
+                                &nonce,
+                                stored.shard_digests.concat().as_bytes(),
+                            ),
+                        })
+                    }
+                    _ => log::warn!(
+                        "audited version {} of {} does not match its digest",
+                        metadata.version,
+                        metadata.key
+                    ),
+                },
+                Err(e) => log::warn!(
+                    "audited version {} of {} is not available: {}",
+                    metadata.version,
+                    metadata.key,
+                    e
+                ),
+            }
+        }
+        if responses.is_empty() {
+            return Ok(None);
+        }
+        let challenge = AuditChallenge {
+            committee_did: handler_params.committee_did.clone(),
+            epoch,
+            tick_id: tick_id.to_owned(),
+            nonce: nonce_hex.clone(),
+            responses,
+        };
+
+        let session_id = nonce_hex;
+        let message = challenge
+            .to_jcs()
+            .map_err(|e| ApiNodeError::LogError(e.into()))?;
+        let mut sign_fsm =
+            self.get_sign_fsm(&message, session_id.clone(), sign_input, sign_output)?;
+        let final_state = sign_fsm.run().map_err(ApiNodeError::SignatureError)?;
+        let (signature, signature_log, working_nodes) = manage_signature_terminal_state(
+            final_state,
+            &session_id,
+            handler_params.dids,
+            handler_params.signature_logger,
+            handler_params.registry.as_ref(),
+        )
+        .map_err(ApiNodeError::SignatureError)?;
+
+        let audit_log = AuditLog {
+            challenge,
+            absent_nodes: signature_log.absent_nodes,
+            bad_signers: signature_log.bad_signers,
+            signature_hex: Some(hex::encode(signature.0)),
+        };
+        Ok(Some((audit_log, working_nodes)))
+    }
+
+    /// Has the committee sign the keys of the objects that expired before the epoch's cutoff,
+    /// then deletes them. Returns `None` when no object expired.
+    pub fn collect_garbage(
+        &self,
+        schedule: &EpochSchedule,
+        epoch: u64,
+        sign_input: impl Receiver<MessageWrapper<SignMessage>>,
+        sign_output: impl Sender<MessageWrapper<SignMessage>>,
+        handler_params: HandlerParams,
+    ) -> Result<Option<(DeletionLog, Vec<String>)>, ApiNodeError> {
+        let cutoff = schedule.start(epoch);
+        let mut expired = vec![];
+        for key in self.storage.list("").map_err(ApiNodeError::StorageError)? {
+            let collected = self
+                .storage
+                .metadata(&key)
+                .map_err(ApiNodeError::StorageError)?
+                .is_some_and(|metadata| metadata.expired(cutoff));
+            if collected {
+                expired.push(key);
+            }
+        }
+        if expired.is_empty() {
+            return Ok(None);
+        }
+        let record = DeletionRecord {
+            committee_did: handler_params.committee_did.clone(),
+            epoch,
+            cutoff,
+            deleted: expired,
+        };
+
+        let session_id = format!("gc-{epoch}");
+        let message = record
+            .to_jcs()
+            .map_err(|e| ApiNodeError::LogError(e.into()))?;
+        let mut sign_fsm =
+            self.get_sign_fsm(&message, session_id.clone(), sign_input, sign_output)?;
+        let final_state = sign_fsm.run().map_err(ApiNodeError::SignatureError)?;
+        let (signature, signature_log, working_nodes) = manage_signature_terminal_state(
+            final_state,
+            &session_id,
+            handler_params.dids,
+            handler_params.signature_logger,
+            handler_params.registry.as_ref(),
+        )
+        .map_err(ApiNodeError::SignatureError)?;
+
+        // the objects are deleted only once the committee signed their keys, so that a failed
+        // signature leaves them to the next garbage collection instead of deleting them unlogged
+        for key in &record.deleted {
+            self.storage
+                .delete(key)
+                .map_err(ApiNodeError::StorageError)?;
+        }
+        let deletion_log = DeletionLog {
+            record,
+            absent_nodes: signature_log.absent_nodes,
+            bad_signers: signature_log.bad_signers,
+            signature_hex: Some(hex::encode(signature.0)),
+        };
+        Ok(Some((deletion_log, working_nodes)))
+    }
+
+    /// Fetches from the peers the versions of the objects this node is missing, checking each
+    /// of them against the committee's signed log of the request that stored it, and returns
+    /// the report of the sync signed by the node.
+    pub fn sync(
+        &self,
+        node_did: &str,
+        committee_did: &str,
+        sync_channels: SyncChannels<
+            &std::sync::mpsc::Receiver<MessageWrapper<SyncMessage>>,
+            impl Sender<MessageWrapper<SyncMessage>>,
+        >,
+    ) -> Result<SyncReport, ApiNodeError> {
+        let session_id = format!("sync-{}", self.api_params.id);
+        let index = self.run_indexing(&session_id, committee_did, &sync_channels)?;
+        let mut record = SyncRecord {
+            committee_did: committee_did.to_owned(),
+            node_did: node_did.to_owned(),
+            synced: vec![],
+            repaired: vec![],
+            failed: vec![],
+        };
+        self.sync_missing(
+            index,
+            &session_id,
+            committee_did,
+            &sync_channels,
+            &mut record,
+        )?;
+        self.sign_sync_record(record)
+    }
+
+    /// Runs the anti-entropy repair of the given epoch: the node checks its stored versions and
+    /// compares the Merkle root of the intact ones with its peers' roots. When some versions
+    /// are corrupted or the roots differ, the node fetches its corrupted and missing versions
+    /// from the peers whose copies match the committee's signed logs. Returns `None` when
+    /// nothing had to be fetched.
+    pub fn repair(
+        &self,
+        node_did: &str,
+        committee_did: &str,
+        epoch: u64,
+        sync_channels: SyncChannels<
+            &std::sync::mpsc::Receiver<MessageWrapper<SyncMessage>>,
+            impl Sender<MessageWrapper<SyncMessage>>,
+        >,
+    ) -> Result<Option<SyncReport>, ApiNodeError> {
+        let (intact, corrupted) =
+            check_versions(&self.storage).map_err(ApiNodeError::StorageError)?;
+        let root = merkle_root(&intact);
+
+        let session_id = format!("repair-{}-{}", self.api_params.id, epoch);
+        let roots_session_id = format!("{session_id}-roots");
+        let keypair = Pair {
+            private: self.api_params.secret,
+            public: self.api_params.public_key,
+        };
+        let initial_state = Comparing::new(
+            &keypair,
+            roots_session_id.clone(),
+            committee_did.to_owned(),
+            epoch,
+            self.sync_params(),
+        )
+        .map_err(ApiNodeError::SyncError)?;
+        let mut roots_fsm = StateMachine::new(
+            Box::new(initial_state),
+            roots_session_id.clone(),
+            Feed::new(sync_channels.input, roots_session_id),
+            sync_channels.output.clone(),
+        );
+        let roots = match roots_fsm.run().map_err(ApiNodeError::SyncError)? {
+            SyncTerminalStates::Compared(roots) => roots,
+            _ => vec![],
+        };
+        let differing = roots.iter().filter(|peer_root| **peer_root != root).count();
+        if corrupted.is_empty() && differing == 0 {
+            return Ok(None);
+        }
+        log::info!(
+            "{} corrupted versions, {} of {} peers hold other versions",
+            corrupted.len(),
+            differing,
+            roots.len()
+        );
+
+        let mut index = self.run_indexing(
+            &format!("{session_id}-index"),
+            committee_did,
+            &sync_channels,
+        )?;
+        let mut record = SyncRecord {
+            committee_did: committee_did.to_owned(),
+            node_did: node_did.to_owned(),
+            synced: vec![],
+            repaired: vec![],
+            failed: vec![],
+        };
+        for metadata in corrupted {
+            let entries = index
+                .get_mut(&metadata.key)
+                .and_then(|versions| versions.remove(&metadata.version))
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|entry| entry.metadata == metadata)
+                .collect();
+            let version = ObjectVersion {
+                key: metadata.key,
+                version: metadata.version,
+            };
+            match self.sync_version(entries, committee_did, &session_id, true, &sync_channels) {
+                Ok(()) => record.repaired.push(version),
+                Err(e) => {
+                    log::warn!(
+                        "could not repair version {} of {}: {}",
+                        version.version,
+                        version.key,
+                        e
+                    );
+                    record.failed.push(version);
+                }
+            }
+        }
+        self.sync_missing(
+            index,
+            &session_id,
+            committee_did,
+            &sync_channels,
+            &mut record,
+        )?;
+
+        if record.synced.is_empty() && record.repaired.is_empty() && record.failed.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(self.sign_sync_record(record)?))
+    }
+
+    /// Requests the peers' indexes, returning their entries by key and version.
+    fn run_indexing(
+        &self,
+        session_id: &str,
+        committee_did: &str,
+        sync_channels: &SyncChannels<
+            &std::sync::mpsc::Receiver<MessageWrapper<SyncMessage>>,
+            impl Sender<MessageWrapper<SyncMessage>>,
+        >,
+    ) -> Result<BTreeMap<String, BTreeMap<u64, Vec<IndexEntry>>>, ApiNodeError> {
+        let keypair = Pair {
+            private: self.api_params.secret,
+            public: self.api_params.public_key,
+        };
+        let initial_state = Indexing::new(
+            &keypair,
+            session_id.to_owned(),
+            committee_did.to_owned(),
+            self.sync_params(),
+        )
+        .map_err(ApiNodeError::SyncError)?;
+        let mut index_fsm = StateMachine::new(
+            Box::new(initial_state),
+            session_id.to_owned(),
+            Feed::new(sync_channels.input, session_id.to_owned()),
+            sync_channels.output.clone(),
+        );
+        let pieces = match index_fsm.run().map_err(ApiNodeError::SyncError)? {
+            SyncTerminalStates::Indexed(pieces) => pieces,
+            _ => {
+                return Err(ApiNodeError::SyncError(anyhow::Error::msg(
+                    "no index received",
+                )))
+            }
+        };
+
+        let mut index: BTreeMap<String, BTreeMap<u64, Vec<IndexEntry>>> = BTreeMap::new();
+        for entry in pieces.into_iter().flat_map(|piece| piece.entries) {
+            index
+                .entry(entry.metadata.key.clone())
+                .or_default()
+                .entry(entry.metadata.version)
+                .or_default()
+                .push(entry);
+        }
+        Ok(index)
+    }
+
+    /// Fetches the versions of the index newer than the node's latest ones, recording them in
+    /// the report.
+    fn sync_missing(
+        &self,
+        index: BTreeMap<String, BTreeMap<u64, Vec<IndexEntry>>>,
+        session_id: &str,
+        committee_did: &str,
+        sync_channels: &SyncChannels<
+            &std::sync::mpsc::Receiver<MessageWrapper<SyncMessage>>,
+            impl Sender<MessageWrapper<SyncMessage>>,
+        >,
+        record: &mut SyncRecord,
+    ) -> Result<(), ApiNodeError> {
+        for (key, versions) in index {
+            let latest = self
+                .storage
+                .metadata(&key)
+                .map_err(ApiNodeError::StorageError)?
+                .map_or(0, |metadata| metadata.version);
+            let mut versions = versions
+                .into_iter()
+                .filter(|(version, _)| *version > latest);
+            for (version, entries) in versions.by_ref() {
+                let result =
+                    self.sync_version(entries, committee_did, session_id, false, sync_channels);
+                if let Err(e) = result {
+                    log::warn!("could not sync version {} of {}: {}", version, key, e);
+                    record.failed.push(ObjectVersion {
+                        key: key.clone(),
+                        version,
+                    });
+                    break;
+                }
+                record.synced.push(ObjectVersion {
+                    key: key.clone(),
+                    version,
+                });
+            }
+            // versions are stored in order, the following ones can't be synced either
+            record
+                .failed
+                .extend(versions.map(|(version, _)| ObjectVersion {
+                    key: key.clone(),
+                    version,
+                }));
+        }
+        Ok(())
+    }
+
+    /// Fetches a version listed by the peers and stores it, either as a new version or in
+    /// place of a corrupted one. The metadata listed by most peers is tried first, so that a
+    /// single faulty peer can't impose its own.
+    fn sync_version(
+        &self,
+        entries: Vec<IndexEntry>,
+        committee_did: &str,
+        session_id: &str,
+        repair: bool,
+        sync_channels: &SyncChannels<
+            &std::sync::mpsc::Receiver<MessageWrapper<SyncMessage>>,
+            impl Sender<MessageWrapper<SyncMessage>>,
+        >,
+    ) -> anyhow::Result<()> {
+        let mut candidates: Vec<(IndexEntry, usize)> = vec![];
+        for entry in entries {
+            match candidates
+                .iter_mut()
+                .find(|(candidate, _)| candidate.metadata == entry.metadata)
+            {
+                Some((_, count)) => *count += 1,
+                None => candidates.push((entry, 1)),
+            }
+        }
+        candidates.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        for (entry, _) in candidates {
+            let target = match self.verify_entry(&entry, committee_did) {
+                Ok(target) => target,
+                Err(e) => {
+                    log::warn!("discarding index entry of {}: {}", entry.metadata.key, e);
+                    continue;
+                }
+            };
+            let content =
+                self.fetch_version(&entry, &target, committee_did, session_id, sync_channels)?;
+            if repair {
+                self.storage.repair_version(&content, &entry.metadata)?;
+            } else {
+                self.storage
+                    .import_version(&content, entry.metadata.clone())?;
+            }
+            self.storage
+                .put_log(&entry.metadata.request_id, &serde_json::to_vec(&entry.log)?)?;
+            return Ok(());
+        }
+        anyhow::bail!("no peer listed a valid version")
+    }
+
+    fn sign_sync_record(&self, record: SyncRecord) -> Result<SyncReport, ApiNodeError> {
+        let keypair = Pair {
+            private: self.api_params.secret,
+            public: self.api_params.public_key,
+        };
+        let signature = record
+            .to_jcs()
+            .map_err(anyhow::Error::from)
+            .and_then(|message| Ok(EdDSA::from(keypair).sign(&message)?))
+            .map_err(ApiNodeError::LogError)?;
+        Ok(SyncReport {
+            record,
+            signature_hex: Some(hex::encode(signature)),
+        })
+    }
+
+    /// Checks that the entry's log is the committee's signed log of a store request of the
+    /// entry's object, and returns what the fetched copies must match.
+    fn verify_entry(&self, entry: &IndexEntry, committee_did: &str) -> anyhow::Result<SyncTarget> {
+        let (metadata, log) = (&entry.metadata, &entry.log);
+        log.verify(&self.api_params.dkg.dist_key_share()?.public())?;
+        if log.committee_did != committee_did
+            || log.request_id.0 != metadata.request_id
+            || !matches!(log.result, messages::ResponseState::Success)
+        {
+            anyhow::bail!("the log does not record the store of the version");
+        }
+        let digest = match &log.digest {
+            Some(digest) => digest.clone(),
+            None => anyhow::bail!("the log has no digest"),
+        };
+
+        // the key is only bound to the log through the store request
+        let request: GenericRequest =
+            serde_json::from_slice(&self.api_params.transport.fetch(&metadata.request_id)?)?;
+        let item_name = match &request.storage_uri {
+            StorageUri::Storage(StorageLocalUri(item_name)) => item_name.clone(),
+            StorageUri::None => String::new(),
+        };
+        let (key, algorithm, sharded) = match request.operation()? {
+            Operation::Store => (item_name, DigestAlgorithm::Blake2b256, false),
+            Operation::StoreShards => (item_name, DigestAlgorithm::Blake2b256, true),
+            Operation::StoreContent { algorithm } => {
+                (content_key(algorithm, &digest), algorithm, false)
+            }
+            _ => anyhow::bail!("not a store request"),
+        };
+        if key != metadata.key {
+            anyhow::bail!("the request did not store {}", metadata.key);
+        }
+        if algorithm == DigestAlgorithm::Blake2b256 && digest != metadata.digest {
+            anyhow::bail!("the metadata does not match the log");
+        }
+        Ok(SyncTarget {
+            algorithm,
+            digest,
+            sharded,
+        })
+    }
+
+    /// Fetches a version from the peers. The node's own shard of an erasure-coded object is
+    /// encoded again from the object rebuilt with the peers' shards.
+    fn fetch_version(
+        &self,
+        entry: &IndexEntry,
+        target: &SyncTarget,
+        committee_did: &str,
+        session_id: &str,
+        sync_channels: &SyncChannels<
+            &std::sync::mpsc::Receiver<MessageWrapper<SyncMessage>>,
+            impl Sender<MessageWrapper<SyncMessage>>,
+        >,
+    ) -> anyhow::Result<Vec<u8>> {
+        let metadata = &entry.metadata;
+        let session_id = format!("{}-{}-{}", session_id, metadata.key, metadata.version);
+        let keypair = Pair {
+            private: self.api_params.secret,
+            public: self.api_params.public_key,
+        };
+        let request = ObjectRequest::new(
+            &keypair,
+            &session_id,
+            committee_did.to_owned(),
+            metadata.key.clone(),
+            metadata.version,
+        )?;
+        // shards differ from node to node, only the rebuilt object can be checked
+        let digest = (!target.sharded).then(|| metadata.digest.clone());
+        let initial_state = Fetching::new(
+            session_id.clone(),
+            keypair.private,
+            request,
+            digest,
+            self.sync_params(),
+        );
+        let mut fetch_fsm = StateMachine::new(
+            Box::new(initial_state),
+            session_id.clone(),
+            Feed::new(sync_channels.input, session_id),
+            sync_channels.output.clone(),
+        );
+        let mut copies = match fetch_fsm.run()? {
+            SyncTerminalStates::Fetched(copies) => copies,
+            _ => anyhow::bail!("no valid copy received"),
+        };
+
+        if target.sharded {
+            return self.rebuild_shard(copies, &target.digest);
+        }
+        let data = copies.pop().unwrap_or_default();
+        if target.algorithm.digest(&data) != target.digest {
+            anyhow::bail!("the copy does not match the log");
+        }
+        Ok(data)
+    }
+
+    /// Rebuilds the object from the shards of the peers and encodes the node's own shard.
+    fn rebuild_shard(&self, copies: Vec<Vec<u8>>, digest: &str) -> anyhow::Result<Vec<u8>> {
+        let stored: Vec<StoredShard> = copies
+            .iter()
+            .filter_map(|copy| StoredShard::parse(copy))
+            .filter(|stored| stored.digest == digest)
+            .collect();
+        for reference in &stored {
+            let shards = stored
+                .iter()
+                .filter(|shard| shard.shard_digests == reference.shard_digests)
+                .filter_map(|shard| Some((shard.index, shard.shard().ok()?)))
+                .filter(|(index, shard)| {
+                    reference.shard_digests.get(*index)
+                        == Some(&DigestAlgorithm::Blake2b256.digest(shard))
+                })
+                .collect();
+            if let Ok(data) = reference.reconstruct(shards) {
+                let index = match self.shard_index() {
+                    Some(index) => index,
+                    None => anyhow::bail!("the node is not a member of the committee"),
+                };
+                let own =
+                    StoredShard::new(&data, index, reference.data_shards, reference.parity_shards)?;
+                return Ok(serde_json::to_vec(&own)?);
+            }
+        }
+        anyhow::bail!("not enough valid shards to rebuild the object")
+    }
+
+    /// Index of the node's shard of the erasure-coded objects, `None` if the node is not a
+    /// member of the committee.
+    fn shard_index(&self) -> Option<usize> {
+        self.api_params.id.checked_sub(1)
+    }
+
+    fn sync_params(&self) -> SyncParams {
+        SyncParams {
+            peers: self
+                .api_params
+                .dkg
+                .participants
+                .iter()
+                .filter(|participant| **participant != self.api_params.public_key)
+                .cloned()
+                .collect(),
+            sender: self.api_params.sync_sender.clone(),
+            sleep_time: self.api_params.signature_sleep_time,
+        }
+    }
+
+    fn get_data(
+        &self,
+        location: &InputUri,
+        http_options: Option<&HttpOptions>,
+    ) -> Result<Vec<u8>, ApiNodeError> {
         let data = match location {
             InputUri::Iota(uri) => match uri {
-                IotaMessageUri(id) => {
-                    let rt = tokio::runtime::Runtime::new()?;
-                    let block_id = BlockId::from_str(id)
-                        .map_err(|e| ApiNodeError::InvalidMessageId(e.into()))?;
-                    let block = rt.block_on(self.api_params.client.get_block(&block_id))?;
-                    let payload = match block.payload() {
-                        Some(p) => p,
-                        None => return Err(ApiNodeError::MissingPayload(block_id)),
-                    };
-                    let tagged_data = match payload {
-                        Payload::TaggedData(td) => td,
-                        _ => return Err(ApiNodeError::UnsupportedPayload),
-                    };
-                    tagged_data.data().to_vec()
-                }
+                IotaMessageUri(id) => self
+                    .api_params
+                    .transport
+                    .fetch(id)
+                    .map_err(ApiNodeError::TransportError)?,
             },
             InputUri::Local(uri) => match uri {
-                StorageLocalUri(id) => self
-                    .storage
-                    .get(id.to_owned())
-                    .map_err(ApiNodeError::StorageError)?,
+                StorageLocalUri(id) => self.storage.get(id).map_err(ApiNodeError::StorageError)?,
             },
             InputUri::Literal(s) => s.as_bytes().to_vec(),
-            InputUri::Url(u) => get_data_from_url(u)?,
+            InputUri::Url(u) => get_data_from_url(u, http_options, &self.api_params.http_secrets)?,
+            InputUri::None => return Err(ApiNodeError::MissingInput),
         };
         Ok(data)
     }
@@ -255,38 +1524,106 @@ impl ApiNode {
     }
 }
 
-fn get_data_from_url(url: &Url) -> Result<Vec<u8>, ApiNodeError> {
-    let mut body = Vec::new();
-    let _ = reqwest::blocking::get(url.as_str())
-        .map_err(|e| ApiNodeError::HttpError(anyhow::Error::msg(format!("{e}"))))?
-        .read_to_end(&mut body)?;
-    Ok(body)
+/// Encodes the data published in the committee's log, with the requested encoding or, if none
+/// was requested, as utf8 when the data is a valid utf8 string and as base64 otherwise.
+fn encode_data(
+    data: &[u8],
+    data_encoding: Option<&DataEncoding>,
+) -> Result<(String, DataEncoding), FailureReason> {
+    let data_encoding = match data_encoding {
+        Some(data_encoding) => data_encoding.clone(),
+        None if std::str::from_utf8(data).is_ok() => DataEncoding::Utf8,
+        None => DataEncoding::Base64,
+    };
+    Ok((data_encoding.encode(data)?, data_encoding))
+}
+
+/// Selects the subset of the JSON data at `json_path` and canonicalizes it (JCS), so that
+/// every node signs the same bytes regardless of the formatting of the input. Data is left
+/// untouched when no path is given.
+fn select_data(data: Vec<u8>, json_path: Option<&str>) -> Result<Vec<u8>, FailureReason> {
+    let json_path = match json_path {
+        Some(json_path) => json_path,
+        None => return Ok(data),
+    };
+    let document: serde_json::Value =
+        serde_json::from_slice(&data).map_err(|_| FailureReason::InvalidJson)?;
+    json::select(&document, json_path)
+        .ok_or(FailureReason::JsonPathNotFound)?
+        .to_jcs()
+        .map_err(|_| FailureReason::InvalidJson)
+}
+
+/// Checks that the request can delete the object whose latest version is `latest`. Objects
+/// stored by a requester can only be deleted by a request it signed, naming the store request
+/// of the latest version, so that the same request can't delete a later store. Objects stored
+/// without a requester can be deleted by any request, as before requesters were introduced.
+fn check_deletion(
+    request: &GenericRequest,
+    latest: Option<&ObjectMetadata>,
+) -> Result<(), FailureReason> {
+    let requester = request
+        .requester_key()?
+        .and_then(|requester| requester.marshal_binary().ok());
+    let latest = match latest {
+        Some(latest) => latest,
+        None => return Ok(()),
+    };
+    if let Some(owner) = &latest.requester {
+        if hex::decode(owner).ok() != requester {
+            return Err(FailureReason::Unauthorized);
+        }
+        if request.object_request_id.is_none() {
+            return Err(FailureReason::StaleVersion);
+        }
+    }
+    match &request.object_request_id {
+        Some(request_id) if *request_id != latest.request_id => Err(FailureReason::StaleVersion),
+        _ => Ok(()),
+    }
 }
 
 type Fsm<'a, R, S> = StateMachine<SignTypes, R, S>;
 
+/// What the copies of a synced version must match, as recorded in the committee's log.
+struct SyncTarget {
+    algorithm: DigestAlgorithm,
+    digest: String,
+    sharded: bool,
+}
+
 #[derive(Debug, Error)]
 pub enum ApiNodeError {
-    #[error("async runtime error")]
-    AsyncRuntimeError(#[from] std::io::Error),
-    #[error("message id is not valid")]
-    InvalidMessageId(#[source] anyhow::Error),
-    #[error("iota client error")]
-    IotaError(#[from] iota_client::Error),
-    #[error("missing payload {0}")]
-    MissingPayload(BlockId),
-    #[error("payload is not supported")]
-    UnsupportedPayload,
+    #[error("io error")]
+    IoError(#[from] std::io::Error),
+    #[error("transport error")]
+    TransportError(#[source] anyhow::Error),
     #[error("storage error")]
     StorageError(#[source] anyhow::Error),
     #[error("signature error")]
     SignatureError(#[source] anyhow::Error),
-    #[error("data is not a valid utf8 string")]
-    ConversionError(#[from] Utf8Error),
     #[error("dlt logging failed")]
     LogError(#[source] anyhow::Error),
+    #[error("storage sync failed")]
+    SyncError(#[source] anyhow::Error),
     #[error("http connection error")]
     HttpError(#[source] anyhow::Error),
+    #[error("the request has no input")]
+    MissingInput,
+    #[error("http request timed out")]
+    HttpTimeout,
+    #[error("http response exceeds the maximum size")]
+    ResponseTooLarge,
+}
+
+impl From<ApiNodeError> for FailureReason {
+    fn from(e: ApiNodeError) -> Self {
+        match e {
+            ApiNodeError::HttpTimeout => FailureReason::HttpTimeout,
+            ApiNodeError::ResponseTooLarge => FailureReason::ResponseTooLarge,
+            _ => FailureReason::InputUnavailable,
+        }
+    }
 }
 
 fn manage_signature_terminal_state(
@@ -294,8 +1631,8 @@ fn manage_signature_terminal_state(
     session_id: &str,
     dids: Vec<String>,
     logger: NodeSignatureLogger,
-    node_url: String,
-) -> anyhow::Result<(Signature, Vec<String>)> {
+    registry: &dyn DidRegistry,
+) -> anyhow::Result<(Signature, NodeSignatureLog, Vec<String>)> {
     match final_state {
         SignTerminalStates::Completed(signature, processed_partial_owners, bad_signers) => {
             let (mut log, working_nodes) = new_signature_log(
@@ -303,12 +1640,12 @@ fn manage_signature_terminal_state(
                 processed_partial_owners,
                 bad_signers,
                 dids,
-                node_url,
+                registry,
             )
             .map_err(ApiNodeError::LogError)?;
             logger.publish(&mut log).map_err(ApiNodeError::LogError)?;
 
-            Ok((signature, working_nodes))
+            Ok((signature, log, working_nodes))
         }
         SignTerminalStates::Failed => {
             log::error!(
@@ -319,3 +1656,84 @@ fn manage_signature_terminal_state(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use kyber_rs::{group::edwards25519::SuiteEd25519, util::key::new_key_pair};
+
+    use super::*;
+
+    fn delete(object_request_id: Option<&str>) -> GenericRequest {
+        serde_json::from_value(serde_json::json!({
+            "version": 2,
+            "operation": "Delete",
+            "storage_uri": "storage:local:key",
+            "timestamp": unix_time(),
+            "object_request_id": object_request_id,
+        }))
+        .unwrap()
+    }
+
+    fn stored(request_id: &str, requester: Option<String>) -> ObjectMetadata {
+        ObjectMetadata {
+            key: "key".to_owned(),
+            version: 1,
+            request_id: request_id.to_owned(),
+            requester,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn objects_without_requester_are_deleted_by_any_request() {
+        let latest = stored("store", None);
+        assert_eq!(check_deletion(&delete(None), Some(&latest)), Ok(()));
+        assert_eq!(
+            check_deletion(&delete(Some("store")), Some(&latest)),
+            Ok(())
+        );
+        assert_eq!(
+            check_deletion(&delete(Some("other")), Some(&latest)),
+            Err(FailureReason::StaleVersion)
+        );
+        assert_eq!(check_deletion(&delete(None), None), Ok(()));
+    }
+
+    #[test]
+    fn objects_are_deleted_by_their_requester_for_the_named_store_only() {
+        let suite = SuiteEd25519::new_blake3_sha256_ed25519();
+        let owner = new_key_pair(&suite).unwrap();
+        let owner_hex = Some(hex::encode(owner.public.marshal_binary().unwrap()));
+        let signed = |object_request_id, keypair| {
+            let mut request = delete(object_request_id);
+            request.sign_as_requester(keypair).unwrap();
+            request
+        };
+        let latest = stored("store", owner_hex.clone());
+
+        assert_eq!(
+            check_deletion(&signed(Some("store"), &owner), Some(&latest)),
+            Ok(())
+        );
+        assert_eq!(
+            check_deletion(&delete(Some("store")), Some(&latest)),
+            Err(FailureReason::Unauthorized)
+        );
+        let other = new_key_pair(&suite).unwrap();
+        assert_eq!(
+            check_deletion(&signed(Some("store"), &other), Some(&latest)),
+            Err(FailureReason::Unauthorized)
+        );
+        assert_eq!(
+            check_deletion(&signed(None, &owner), Some(&latest)),
+            Err(FailureReason::StaleVersion)
+        );
+
+        // the same signed delete can't delete a later store of the requester
+        let restored = stored("restore", owner_hex);
+        assert_eq!(
+            check_deletion(&signed(Some("store"), &owner), Some(&restored)),
+            Err(FailureReason::StaleVersion)
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+use crate::store::Usage;
+
+use super::messages::FailureReason;
+
+/// Limits of the objects stored by a committee, checked before storing an object. They are
+/// part of the committee's configuration given by the governor, so that every node of the
+/// committee rejects the same requests. Unset limits are not enforced.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Quotas {
+    /// maximum size [bytes] of an object
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_object_size: Option<u64>,
+    /// maximum number of objects stored by the committee
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_objects: Option<u64>,
+    /// maximum size [bytes] of every version of the objects stored by the committee
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+    /// maximum number of objects stored by the requests of a requester
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requester_max_objects: Option<u64>,
+    /// maximum size [bytes] of the versions stored by the requests of a requester
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requester_max_bytes: Option<u64>,
+}
+
+impl Quotas {
+    /// Checks that a new version of `size` bytes fits the quotas, `new_object` is whether it
+    /// is the first version of the object.
+    pub fn check(
+        &self,
+        usage: &Usage,
+        requester: Option<&str>,
+        new_object: bool,
+        size: u64,
+    ) -> Result<(), FailureReason> {
+        if exceeds(self.max_object_size, 0, size) {
+            return Err(FailureReason::ObjectTooLarge);
+        }
+        let objects = u64::from(new_object);
+        if exceeds(self.max_objects, usage.objects, objects)
+            || exceeds(self.max_bytes, usage.bytes, size)
+        {
+            return Err(FailureReason::CommitteeQuotaExceeded);
+        }
+        if let Some(requester) = requester {
+            let usage = usage.requester(requester);
+            if exceeds(self.requester_max_objects, usage.objects, objects)
+                || exceeds(self.requester_max_bytes, usage.bytes, size)
+            {
+                return Err(FailureReason::RequesterQuotaExceeded);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn exceeds(limit: Option<u64>, used: u64, added: u64) -> bool {
+    limit.is_some_and(|limit| used.saturating_add(added) > limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::ObjectMetadata;
+
+    use super::*;
+
+    fn usage() -> Usage {
+        let mut usage = Usage::default();
+        for (version, requester) in [(1, Some("alice")), (2, Some("bob")), (1, None)] {
+            usage.add_version(&ObjectMetadata {
+                version,
+                size: 10,
+                requester: requester.map(str::to_owned),
+                ..Default::default()
+            });
+        }
+        usage
+    }
+
+    #[test]
+    fn unset_quotas_accept_everything() {
+        assert_eq!(
+            Quotas::default().check(&usage(), Some("alice"), true, u64::MAX),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_large_objects() {
+        let quotas = Quotas {
+            max_object_size: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(quotas.check(&usage(), None, true, 10), Ok(()));
+        assert_eq!(
+            quotas.check(&usage(), None, false, 11),
+            Err(FailureReason::ObjectTooLarge)
+        );
+    }
+
+    #[test]
+    fn checks_the_committee_quotas() {
+        let quotas = Quotas {
+            max_objects: Some(2),
+            max_bytes: Some(35),
+            ..Default::default()
+        };
+        // a new version of an existing object doesn't count as an object
+        assert_eq!(quotas.check(&usage(), None, false, 5), Ok(()));
+        assert_eq!(
+            quotas.check(&usage(), None, true, 5),
+            Err(FailureReason::CommitteeQuotaExceeded)
+        );
+        assert_eq!(
+            quotas.check(&usage(), None, false, 6),
+            Err(FailureReason::CommitteeQuotaExceeded)
+        );
+    }
+
+    #[test]
+    fn checks_the_requester_quotas() {
+        let quotas = Quotas {
+            requester_max_objects: Some(1),
+            requester_max_bytes: Some(15),
+            ..Default::default()
+        };
+        assert_eq!(quotas.check(&usage(), Some("bob"), true, 5), Ok(()));
+        assert_eq!(quotas.check(&usage(), Some("carol"), true, 15), Ok(()));
+        assert_eq!(
+            quotas.check(&usage(), Some("alice"), true, 1),
+            Err(FailureReason::RequesterQuotaExceeded)
+        );
+        assert_eq!(
+            quotas.check(&usage(), Some("bob"), false, 6),
+            Err(FailureReason::RequesterQuotaExceeded)
+        );
+        // requests without a requester are only bound by the committee quotas
+        assert_eq!(quotas.check(&usage(), None, true, 100), Ok(()));
+    }
+}
@@ -0,0 +1,138 @@
+use iota_client::crypto::hashes::{blake2b::Blake2b256, Digest};
+
+use crate::store::{ObjectMetadata, Storage};
+
+use super::{messages::DigestAlgorithm, shards::StoredShard};
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+/// Stored versions of the objects, split between the intact ones and the ones whose content
+/// is missing or doesn't match their metadata.
+pub(crate) fn check_versions(
+    storage: &Storage,
+) -> anyhow::Result<(Vec<ObjectMetadata>, Vec<ObjectMetadata>)> {
+    let mut intact = vec![];
+    let mut corrupted = vec![];
+    for key in storage.versioned_keys()? {
+        for metadata in storage.versions(&key)? {
+            if matches_metadata(storage.get_version(&key, metadata.version), &metadata) {
+                intact.push(metadata);
+            } else {
+                corrupted.push(metadata);
+            }
+        }
+    }
+    Ok((intact, corrupted))
+}
+
+/// Whether the content is the object described by the metadata or, for an erasure-coded
+/// object, an intact shard of it.
+fn matches_metadata(content: anyhow::Result<Vec<u8>>, metadata: &ObjectMetadata) -> bool {
+    let content = match content {
+        Ok(content) => content,
+        Err(_) => return false,
+    };
+    if DigestAlgorithm::Blake2b256.digest(&content) == metadata.digest {
+        return true;
+    }
+    match StoredShard::parse(&content) {
+        Some(stored) => stored.digest == metadata.digest && stored.is_intact(),
+        None => false,
+    }
+}
+
+/// Hex encoded root of the Merkle tree of the given versions, whose leaves are the key,
+/// version and digest of each version sorted by key and version. Nodes holding the same
+/// intact versions agree on the root without exchanging their index.
+pub(crate) fn merkle_root(versions: &[ObjectMetadata]) -> String {
+    let mut versions: Vec<&ObjectMetadata> = versions.iter().collect();
+    versions.sort_by(|a, b| (&a.key, a.version).cmp(&(&b.key, b.version)));
+
+    let mut level: Vec<Vec<u8>> = versions
+        .into_iter()
+        .map(|metadata| {
+            let mut hasher = Blake2b256::new();
+            hasher.update([LEAF_PREFIX]);
+            hasher.update((metadata.key.len() as u64).to_be_bytes());
+            hasher.update(metadata.key.as_bytes());
+            hasher.update(metadata.version.to_be_bytes());
+            hasher.update(metadata.digest.as_bytes());
+            hasher.finalize().to_vec()
+        })
+        .collect();
+    if level.is_empty() {
+        return hex::encode(Blake2b256::digest(b""));
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Blake2b256::new();
+                    hasher.update([NODE_PREFIX]);
+                    hasher.update(left);
+                    hasher.update(right);
+                    hasher.finalize().to_vec()
+                }
+                // an odd node is promoted to the next level
+                _ => pair[0].clone(),
+            })
+            .collect();
+    }
+    hex::encode(&level[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(key: &str, version: u64, content: &[u8]) -> ObjectMetadata {
+        ObjectMetadata {
+            key: key.to_owned(),
+            version,
+            digest: DigestAlgorithm::Blake2b256.digest(content),
+            size: content.len() as u64,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merkle_root_ignores_the_order_of_the_versions() {
+        let versions = vec![
+            version("a", 1, b"a1"),
+            version("a", 2, b"a2"),
+            version("b", 1, b"b1"),
+        ];
+        let mut shuffled = versions.clone();
+        shuffled.rotate_left(1);
+
+        assert_eq!(merkle_root(&versions), merkle_root(&shuffled));
+        assert_eq!(merkle_root(&[]), hex::encode(Blake2b256::digest(b"")));
+        assert_eq!(
+            merkle_root(&versions[..1]),
+            merkle_root(&[version("a", 1, b"a1")])
+        );
+    }
+
+    #[test]
+    fn merkle_root_depends_on_every_version() {
+        let versions = vec![
+            version("a", 1, b"a1"),
+            version("a", 2, b"a2"),
+            version("b", 1, b"b1"),
+        ];
+        let root = merkle_root(&versions);
+
+        assert_ne!(merkle_root(&versions[..2]), root);
+        for changed in [
+            version("b", 1, b"b2"),
+            version("b", 2, b"b1"),
+            version("c", 1, b"b1"),
+        ] {
+            let mut changed_versions = versions.clone();
+            changed_versions[2] = changed;
+            assert_ne!(merkle_root(&changed_versions), root);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::str::FromStr;
+
+use anyhow::bail;
+use identity_iota::core::ToJson;
+use kyber_rs::{group::edwards25519::Point, sign::eddsa};
+use serde::{Deserialize, Serialize};
+
+/// Tag where the committee publishes its deletion logs, next to the committee's tag.
+pub fn deletions_tag(committee_tag: &str) -> String {
+    format!("{committee_tag}-deletions")
+}
+
+/// Objects deleted by a garbage collection, this is the part of the [DeletionLog] signed by
+/// the committee.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DeletionRecord {
+    pub(crate) committee_did: String,
+    pub(crate) epoch: u64,
+    /// unix time [s] before which the deleted objects expired
+    pub(crate) cutoff: u64,
+    /// sorted keys of the deleted objects
+    pub(crate) deleted: Vec<String>,
+}
+
+/// Outcome of a garbage collection: the deleted keys signed by the committee and the DIDs of
+/// the nodes that didn't take part in the signature or provided a wrong one.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DeletionLog {
+    #[serde(flatten)]
+    pub(crate) record: DeletionRecord,
+    pub(crate) absent_nodes: Vec<String>,
+    pub(crate) bad_signers: Vec<String>,
+    pub(crate) signature_hex: Option<String>,
+}
+
+impl DeletionLog {
+    /// Verifies the committee's signature of the deleted keys against the committee's public
+    /// key.
+    pub fn verify(&self, public_key: &Point) -> anyhow::Result<()> {
+        let signature_hex = match &self.signature_hex {
+            Some(signature_hex) => signature_hex,
+            None => bail!("Missing signature"),
+        };
+        eddsa::verify(
+            public_key,
+            &self.record.to_jcs()?,
+            &hex::decode(signature_hex)?,
+        )
+        .map_err(|_| anyhow::Error::msg("Signature is not valid"))?;
+        Ok(())
+    }
+
+    pub fn committee_did(&self) -> &str {
+        &self.record.committee_did
+    }
+}
+
+impl FromStr for DeletionLog {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::de::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kyber_rs::{
+        group::edwards25519::SuiteEd25519,
+        sign::eddsa::EdDSA,
+        util::key::{new_key_pair, Pair},
+    };
+
+    use crate::store::ObjectMetadata;
+
+    use super::*;
+
+    fn signed_log(committee: &Pair<Point>) -> DeletionLog {
+        let record = DeletionRecord {
+            committee_did: "did:iota:rms:0x01".to_owned(),
+            epoch: 470000,
+            cutoff: 1692000000,
+            deleted: vec!["test".to_owned()],
+        };
+        let signature = EdDSA::from(committee.clone())
+            .sign(&record.to_jcs().unwrap())
+            .unwrap();
+        DeletionLog {
+            record,
+            absent_nodes: vec![],
+            bad_signers: vec![],
+            signature_hex: Some(hex::encode(signature)),
+        }
+    }
+
+    #[test]
+    fn verifies_the_signed_deletions() {
+        let suite = SuiteEd25519::new_blake3_sha256_ed25519();
+        let committee = new_key_pair(&suite).unwrap();
+        let log = signed_log(&committee);
+        assert!(log.verify(&committee.public).is_ok());
+
+        let parsed: DeletionLog = serde_json::to_string(&log).unwrap().parse().unwrap();
+        assert!(parsed.verify(&committee.public).is_ok());
+        assert!(log.verify(&new_key_pair(&suite).unwrap().public).is_err());
+
+        let mut tampered = log.clone();
+        tampered.record.deleted.push("other".to_owned());
+        assert!(tampered.verify(&committee.public).is_err());
+        let unsigned = DeletionLog {
+            signature_hex: None,
+            ..log
+        };
+        assert!(unsigned.verify(&committee.public).is_err());
+    }
+
+    #[test]
+    fn objects_expire_at_their_expiry() {
+        let metadata = |expires_at| ObjectMetadata {
+            expires_at,
+            ..Default::default()
+        };
+        assert!(!metadata(None).expired(u64::MAX));
+        assert!(!metadata(Some(100)).expired(99));
+        assert!(metadata(Some(100)).expired(100));
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::bail;
+use identity_iota::core::ToJson;
+use kyber_rs::{
+    group::edwards25519::Point,
+    sign::eddsa::{self, EdDSA},
+    util::key::Pair,
+};
+use serde::{Deserialize, Serialize};
+
+/// Periodic task of the committee, started by an [EpochTick].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledTask {
+    Audit,
+    GarbageCollection,
+    Repair,
+}
+
+/// Epochs of a periodic task: the Unix time divided by the task's interval.
+#[derive(Clone, Debug)]
+pub struct EpochSchedule {
+    task: ScheduledTask,
+    /// time [s] between two runs of the task
+    interval: u64,
+}
+
+impl EpochSchedule {
+    pub fn new(task: ScheduledTask, interval: u64) -> Self {
+        Self {
+            task,
+            interval: interval.max(1),
+        }
+    }
+
+    pub fn task(&self) -> ScheduledTask {
+        self.task
+    }
+
+    /// Epoch of the current time.
+    pub fn current_epoch(&self) -> u64 {
+        now().as_secs() / self.interval
+    }
+
+    /// Turn of the current time when the epoch is split in `turns` equal turns, the leader of
+    /// each turn starts the epoch if it was not started yet (see [EpochTick::leader]).
+    pub fn current_turn(&self, turns: u64) -> u64 {
+        let interval = self.interval as u128 * 1000;
+        (now().as_millis() % interval * turns.max(1) as u128 / interval) as u64
+    }
+
+    /// Time left until the next turn, or the next epoch after the last turn.
+    pub fn until_next_turn(&self, turns: u64) -> Duration {
+        let turns = turns.max(1) as u128;
+        let interval = self.interval as u128 * 1000;
+        let now = now().as_millis();
+        let start = now - now % interval;
+        let turn = (now - start) * turns / interval;
+        // first millisecond of the next turn
+        let next = start + ((turn + 1) * interval).div_ceil(turns);
+        Duration::from_millis((next - now) as u64)
+    }
+
+    /// Unix time [s] of the start of the epoch.
+    pub fn start(&self, epoch: u64) -> u64 {
+        epoch * self.interval
+    }
+
+    /// Whether a tick of the epoch can still start the task: the epoch is the current one, the
+    /// previous one (the node was busy or the tick was slow) or the next one (the leader's
+    /// clock is ahead), so that old ticks can't be replayed.
+    pub fn accepts(&self, epoch: u64) -> bool {
+        let current = self.current_epoch();
+        (current.saturating_sub(1)..=current + 1).contains(&epoch)
+    }
+}
+
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// Start of an epoch of a periodic task, published on the committee's tag by the leader of a
+/// turn of the epoch (see [EpochTick::leader]) and signed with its node key. Ticks are queued
+/// with the committee's requests, so every node runs the task of an epoch once, with the first
+/// tick of the epoch and in the same order relative to the requests, even if it was busy when
+/// the epoch started.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EpochTick {
+    pub(crate) committee_did: String,
+    pub(crate) task: ScheduledTask,
+    pub(crate) epoch: u64,
+    /// turn of the epoch whose leader published the tick
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub(crate) turn: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) signature_hex: Option<String>,
+}
+
+fn is_zero(turn: &u64) -> bool {
+    *turn == 0
+}
+
+impl EpochTick {
+    pub fn new(
+        committee_did: String,
+        task: ScheduledTask,
+        epoch: u64,
+        turn: u64,
+        keypair: &Pair<Point>,
+    ) -> anyhow::Result<Self> {
+        let mut tick = Self {
+            committee_did,
+            task,
+            epoch,
+            turn,
+            signature_hex: None,
+        };
+        let signature = EdDSA::from(keypair.clone()).sign(&tick.to_jcs()?)?;
+        tick.signature_hex = Some(hex::encode(signature));
+        Ok(tick)
+    }
+
+    /// Node publishing the tick of a turn of the epoch: the committee's nodes take turns to
+    /// lead the epochs, and the next ones start the epoch in the following turns if its leader
+    /// did not, so that an unavailable node doesn't skip the epochs it leads.
+    pub fn leader(participants: &[Point], epoch: u64, turn: u64) -> Option<Point> {
+        let mut participants = participants.to_vec();
+        participants.sort_by_key(|pk| pk.to_string());
+        let n = participants.len() as u64;
+        (turn < n).then(|| participants.swap_remove((epoch.wrapping_add(turn) % n) as usize))
+    }
+
+    /// Verifies that the tick is signed by the leader of its turn.
+    pub fn verify(&self, participants: &[Point]) -> anyhow::Result<()> {
+        let leader = match Self::leader(participants, self.epoch, self.turn) {
+            Some(leader) => leader,
+            None => bail!("turn {} has no leader", self.turn),
+        };
+        let mut unsigned = self.clone();
+        let signature_hex = match unsigned.signature_hex.take() {
+            Some(signature_hex) => signature_hex,
+            None => bail!("Missing signature"),
+        };
+        eddsa::verify(&leader, &unsigned.to_jcs()?, &hex::decode(signature_hex)?)
+            .map_err(|_| anyhow::Error::msg("Signature is not valid"))?;
+        Ok(())
+    }
+}
+
+impl FromStr for EpochTick {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::de::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kyber_rs::{group::edwards25519::SuiteEd25519, util::key::new_key_pair};
+
+    use super::*;
+
+    #[test]
+    fn next_participants_lead_the_following_turns() {
+        let suite = SuiteEd25519::new_blake3_sha256_ed25519();
+        let keypairs: Vec<_> = (0..3).map(|_| new_key_pair(&suite).unwrap()).collect();
+        let participants: Vec<_> = keypairs.iter().map(|keypair| keypair.public).collect();
+
+        let leaders: Vec<_> = (0..3)
+            .map(|turn| EpochTick::leader(&participants, 7, turn).unwrap())
+            .collect();
+        assert!(participants.iter().all(|pk| leaders.contains(pk)));
+        assert_eq!(
+            EpochTick::leader(&participants, 8, 0),
+            EpochTick::leader(&participants, 7, 1)
+        );
+        assert_eq!(EpochTick::leader(&participants, 7, 3), None);
+        assert_eq!(EpochTick::leader(&[], 7, 0), None);
+
+        for (turn, leader) in leaders.iter().enumerate() {
+            let keypair = keypairs.iter().find(|k| k.public == *leader).unwrap();
+            let tick = EpochTick::new(
+                "did:iota:rms:0x01".to_owned(),
+                ScheduledTask::Audit,
+                7,
+                turn as u64,
+                keypair,
+            )
+            .unwrap();
+            assert!(tick.verify(&participants).is_ok());
+
+            let mut other_turn = tick.clone();
+            other_turn.turn = (turn as u64 + 1) % 3;
+            assert!(other_turn.verify(&participants).is_err());
+        }
+    }
+
+    #[test]
+    fn turns_split_the_epoch() {
+        let schedule = EpochSchedule::new(ScheduledTask::Audit, 3600);
+        assert!(schedule.current_turn(4) < 4);
+        assert_eq!(schedule.current_turn(0), 0);
+        assert!(schedule.until_next_turn(4) <= Duration::from_secs(900));
+        assert!(schedule.until_next_turn(1) <= Duration::from_secs(3600));
+    }
+}
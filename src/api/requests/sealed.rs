@@ -0,0 +1,451 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use hkdf::Hkdf;
+use kyber_rs::{
+    encoding::{BinaryMarshaler, BinaryUnmarshaler},
+    group::edwards25519::{Point, Scalar, SuiteEd25519},
+    share::poly::{recover_commit, PubShare},
+    util::key::new_key_pair,
+    Point as _, Scalar as _,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+
+const NONCE_SIZE: usize = 24;
+/// Size of the bytes reduced to a scalar, twice the size of the group order so that the
+/// scalar is uniform.
+const WIDE_SCALAR_SIZE: usize = 64;
+const SHARED_KEY_INFO: &[u8] = b"dora-sealed-data";
+const DETERMINISTIC_SEAL_INFO: &[u8] = b"dora-sealed-data-deterministic";
+const DLEQ_CONTEXT: &[u8] = b"dora-dleq-proof";
+
+/// Data encrypted to a public key `X`, usually the committee's one: the ephemeral key
+/// `R = rG` and the data encrypted with XChaCha20-Poly1305, under a key derived from the
+/// shared point `rX`. The committee can only compute the shared point `xR` by combining the
+/// partial decryptions `x_i R` of a threshold of its nodes. The ephemeral key and the
+/// recipient are authenticated along with the ciphertext, so that neither can be swapped.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SealedData {
+    /// hex encoded ephemeral key
+    pub ephemeral: String,
+    /// hex encoded public key of the requester the committee reveals the data to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    /// hex encoded nonce
+    pub nonce: String,
+    /// base64 encoded ciphertext
+    pub ciphertext: String,
+}
+
+impl SealedData {
+    /// Encrypts the data to the public key, e.g. the committee's one.
+    pub fn seal(public_key: &Point, data: &[u8]) -> Result<Self> {
+        Self::seal_for(public_key, None, data)
+    }
+
+    /// Encrypts the data to the public key, recording the recipient the committee may reveal
+    /// it to.
+    pub fn seal_for(public_key: &Point, recipient: Option<&Point>, data: &[u8]) -> Result<Self> {
+        let ephemeral = new_key_pair(&SuiteEd25519::new_blake3_sha256_ed25519())?;
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        Self::encrypt(public_key, recipient, &ephemeral.private, &nonce, data)
+    }
+
+    /// Encrypts the data to the public key with an ephemeral key and a nonce derived from the
+    /// secret point and the context, so that the nodes sharing the secret get the same
+    /// ciphertext. The context must be unique for each secret.
+    pub fn seal_deterministic(
+        public_key: &Point,
+        secret: &Point,
+        context: &[u8],
+        data: &[u8],
+    ) -> Result<Self> {
+        let mut seed = [0; WIDE_SCALAR_SIZE + NONCE_SIZE];
+        Hkdf::<Sha256>::new(Some(context), &secret.marshal_binary()?)
+            .expand(DETERMINISTIC_SEAL_INFO, &mut seed)
+            .map_err(|_| anyhow::Error::msg("could not derive the ephemeral key"))?;
+        let ephemeral = Scalar::default().set_bytes(&seed[..WIDE_SCALAR_SIZE]);
+        Self::encrypt(
+            public_key,
+            None,
+            &ephemeral,
+            &seed[WIDE_SCALAR_SIZE..],
+            data,
+        )
+    }
+
+    fn encrypt(
+        public_key: &Point,
+        recipient: Option<&Point>,
+        ephemeral: &Scalar,
+        nonce: &[u8],
+        data: &[u8],
+    ) -> Result<Self> {
+        let shared = Point::default().mul(ephemeral, Some(public_key));
+        let mut sealed = Self {
+            ephemeral: hex::encode(Point::default().mul(ephemeral, None).marshal_binary()?),
+            recipient: recipient
+                .map(|recipient| recipient.marshal_binary().map(hex::encode))
+                .transpose()?,
+            nonce: hex::encode(nonce),
+            ciphertext: String::new(),
+        };
+        let ciphertext = XChaCha20Poly1305::new(&shared_key(&shared)?)
+            .encrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: data,
+                    aad: &sealed.associated_data(),
+                },
+            )
+            .map_err(|_| anyhow::Error::msg("could not encrypt the data"))?;
+        sealed.ciphertext = STANDARD.encode(ciphertext);
+        Ok(sealed)
+    }
+
+    pub fn ephemeral_key(&self) -> Result<Point> {
+        let mut ephemeral = Point::default();
+        ephemeral.unmarshal_binary(&hex::decode(&self.ephemeral)?)?;
+        Ok(ephemeral)
+    }
+
+    pub fn recipient_key(&self) -> Result<Option<Point>> {
+        let recipient = match &self.recipient {
+            Some(recipient) => recipient,
+            None => return Ok(None),
+        };
+        let mut key = Point::default();
+        key.unmarshal_binary(&hex::decode(recipient)?)?;
+        Ok(Some(key))
+    }
+
+    fn associated_data(&self) -> Vec<u8> {
+        format!(
+            "{}:{}",
+            self.ephemeral,
+            self.recipient.as_deref().unwrap_or_default()
+        )
+        .into_bytes()
+    }
+
+    /// Decrypts the data with the shared point.
+    pub fn open(&self, shared: &Point) -> Result<Vec<u8>> {
+        let nonce = hex::decode(&self.nonce)?;
+        if nonce.len() != NONCE_SIZE {
+            bail!("not a valid nonce");
+        }
+        XChaCha20Poly1305::new(&shared_key(shared)?)
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: STANDARD.decode(&self.ciphertext)?.as_slice(),
+                    aad: &self.associated_data(),
+                },
+            )
+            .map_err(|_| anyhow::Error::msg("could not decrypt the data"))
+    }
+
+    /// Decrypts the data sealed to the public key of the given private key.
+    pub fn open_with_key(&self, private_key: &Scalar) -> Result<Vec<u8>> {
+        self.open(&Point::default().mul(private_key, Some(&self.ephemeral_key()?)))
+    }
+}
+
+impl FromStr for SealedData {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::de::from_str(s)
+    }
+}
+
+/// Combines `threshold` of the given partial decryptions `x_i R` into the shared point `xR`.
+/// The partial decryptions must have been checked against their [DleqProof].
+pub fn combine_partials(
+    partials: &[PubShare<Point>],
+    threshold: usize,
+    participants: usize,
+) -> Result<Point> {
+    if partials.len() < threshold {
+        bail!("not enough partial decryptions");
+    }
+    let mut indexed: Vec<Option<PubShare<Point>>> = (0..participants).map(|_| None).collect();
+    for partial in partials {
+        if let Some(slot) = indexed.get_mut(partial.i) {
+            *slot = Some(PubShare {
+                i: partial.i,
+                v: partial.v,
+            });
+        }
+    }
+    let suite = SuiteEd25519::new_blake3_sha256_ed25519();
+    Ok(recover_commit(suite, &indexed, threshold, participants)?)
+}
+
+/// Chaum-Pedersen proof that a partial decryption `D_i = x_i R` and the public share
+/// `X_i = x_i G` of a node share the same discrete logarithm, i.e. that the partial
+/// decryption was computed with the node's share of the committee's key.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DleqProof {
+    /// commitment `wG`
+    pub base_commitment: Point,
+    /// commitment `wR`
+    pub ephemeral_commitment: Point,
+    /// hex encoded response `s = w + c x_i`
+    pub response: String,
+}
+
+impl DleqProof {
+    /// Proves that `x_i R` was computed with the share `x_i`, for the given context.
+    pub fn new(share: &Scalar, ephemeral: &Point, context: &[u8]) -> Result<Self> {
+        let public_share = Point::default().mul(share, None);
+        let partial = Point::default().mul(share, Some(ephemeral));
+        let nonce = new_key_pair(&SuiteEd25519::new_blake3_sha256_ed25519())?;
+        let ephemeral_commitment = Point::default().mul(&nonce.private, Some(ephemeral));
+        let challenge = dleq_challenge(
+            context,
+            &[
+                &public_share,
+                ephemeral,
+                &partial,
+                &nonce.public,
+                &ephemeral_commitment,
+            ],
+        )?;
+        let response = nonce.private + challenge * *share;
+        Ok(Self {
+            base_commitment: nonce.public,
+            ephemeral_commitment,
+            response: hex::encode(response.marshal_binary()?),
+        })
+    }
+
+    /// Verifies that the partial decryption `x_i R` matches the public share `x_i G`:
+    /// `sG = wG + c x_i G` and `sR = wR + c x_i R`.
+    pub fn verify(
+        &self,
+        public_share: &Point,
+        ephemeral: &Point,
+        partial: &Point,
+        context: &[u8],
+    ) -> Result<()> {
+        let mut response = Scalar::default();
+        response.unmarshal_binary(&hex::decode(&self.response)?)?;
+        let challenge = dleq_challenge(
+            context,
+            &[
+                public_share,
+                ephemeral,
+                partial,
+                &self.base_commitment,
+                &self.ephemeral_commitment,
+            ],
+        )?;
+        let base = Point::default().add(
+            &self.base_commitment,
+            &Point::default().mul(&challenge, Some(public_share)),
+        );
+        let shared = Point::default().add(
+            &self.ephemeral_commitment,
+            &Point::default().mul(&challenge, Some(partial)),
+        );
+        if Point::default().mul(&response, None) != base
+            || Point::default().mul(&response, Some(ephemeral)) != shared
+        {
+            bail!("the partial decryption does not match the public share");
+        }
+        Ok(())
+    }
+}
+
+fn dleq_challenge(context: &[u8], points: &[&Point]) -> Result<Scalar> {
+    let mut hasher = Sha512::new();
+    hasher.update(DLEQ_CONTEXT);
+    hasher.update(context);
+    for point in points {
+        hasher.update(point.marshal_binary()?);
+    }
+    Ok(Scalar::default().set_bytes(&hasher.finalize()))
+}
+
+fn shared_key(shared: &Point) -> Result<Key> {
+    let mut key = Key::default();
+    Hkdf::<Sha256>::new(None, &shared.marshal_binary()?)
+        .expand(SHARED_KEY_INFO, &mut key[..])
+        .map_err(|_| anyhow::Error::msg("could not derive the key"))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use kyber_rs::util::key::Pair;
+
+    use super::*;
+
+    const THRESHOLD: usize = 2;
+    const PARTICIPANTS: usize = 3;
+
+    fn key_pair() -> Pair<Point> {
+        new_key_pair(&SuiteEd25519::new_blake3_sha256_ed25519()).unwrap()
+    }
+
+    /// Shares `x + a (i + 1)` of the private key `x` of the returned pair, the points of a
+    /// polynomial of degree `THRESHOLD - 1`.
+    fn shared_key_pair() -> (Pair<Point>, Vec<Scalar>) {
+        let committee = key_pair();
+        let coefficient = key_pair().private;
+        let shares = (0..PARTICIPANTS)
+            .map(|i| committee.private + coefficient * Scalar::default().set_int64(i as i64 + 1))
+            .collect();
+        (committee, shares)
+    }
+
+    #[test]
+    fn seal_and_open() {
+        let recipient = key_pair();
+        let sealed = SealedData::seal(&recipient.public, b"sealed data").unwrap();
+
+        assert_eq!(
+            sealed.open_with_key(&recipient.private).unwrap(),
+            b"sealed data"
+        );
+        assert!(sealed.open_with_key(&key_pair().private).is_err());
+        assert!(sealed.recipient_key().unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_swapped_recipients_and_ephemeral_keys() {
+        let committee = key_pair();
+        let recipient = key_pair();
+        let sealed =
+            SealedData::seal_for(&committee.public, Some(&recipient.public), b"sealed data")
+                .unwrap();
+        assert_eq!(sealed.recipient_key().unwrap(), Some(recipient.public));
+        assert!(sealed.open_with_key(&committee.private).is_ok());
+
+        let mut swapped = sealed.clone();
+        swapped.recipient = Some(hex::encode(key_pair().public.marshal_binary().unwrap()));
+        assert!(swapped.open_with_key(&committee.private).is_err());
+
+        let mut removed = sealed.clone();
+        removed.recipient = None;
+        assert!(removed.open_with_key(&committee.private).is_err());
+
+        // with the shared point of the original ephemeral key, the AAD still differs
+        let shared =
+            Point::default().mul(&committee.private, Some(&sealed.ephemeral_key().unwrap()));
+        let mut replaced = sealed.clone();
+        replaced.ephemeral = hex::encode(key_pair().public.marshal_binary().unwrap());
+        assert!(replaced.open(&shared).is_err());
+        assert!(sealed.open(&shared).is_ok());
+    }
+
+    #[test]
+    fn deterministic_seals_depend_on_the_secret_and_context_only() {
+        let recipient = key_pair();
+        let secret = key_pair().public;
+        let seal = |secret: &Point, context: &[u8]| {
+            SealedData::seal_deterministic(&recipient.public, secret, context, b"revealed data")
+                .unwrap()
+        };
+        let sealed = seal(&secret, b"session");
+
+        assert_eq!(
+            serde_json::to_string(&sealed).unwrap(),
+            serde_json::to_string(&seal(&secret, b"session")).unwrap()
+        );
+        assert_ne!(seal(&secret, b"other session").ephemeral, sealed.ephemeral);
+        assert_ne!(
+            seal(&key_pair().public, b"session").ephemeral,
+            sealed.ephemeral
+        );
+        assert_eq!(
+            sealed.open_with_key(&recipient.private).unwrap(),
+            b"revealed data"
+        );
+    }
+
+    #[test]
+    fn combines_any_threshold_of_partial_decryptions() {
+        let (committee, shares) = shared_key_pair();
+        let sealed = SealedData::seal(&committee.public, b"sealed data").unwrap();
+        let ephemeral = sealed.ephemeral_key().unwrap();
+        let partials: Vec<PubShare<Point>> = shares
+            .iter()
+            .enumerate()
+            .map(|(i, share)| PubShare {
+                i,
+                v: Point::default().mul(share, Some(&ephemeral)),
+            })
+            .collect();
+
+        for pair in [[0, 1], [0, 2], [2, 1]] {
+            let selected: Vec<_> = pair
+                .iter()
+                .map(|&i| PubShare {
+                    i: partials[i].i,
+                    v: partials[i].v,
+                })
+                .collect();
+            let shared = combine_partials(&selected, THRESHOLD, PARTICIPANTS).unwrap();
+            assert_eq!(sealed.open(&shared).unwrap(), b"sealed data");
+        }
+        assert!(combine_partials(&partials[..1], THRESHOLD, PARTICIPANTS).is_err());
+    }
+
+    #[test]
+    fn dleq_proofs_bind_the_partial_decryption_to_the_share() {
+        let (_, shares) = shared_key_pair();
+        let ephemeral = key_pair().public;
+        let public_share = |share: &Scalar| Point::default().mul(share, None);
+        let partial = |share: &Scalar| Point::default().mul(share, Some(&ephemeral));
+        let proof = DleqProof::new(&shares[0], &ephemeral, b"session").unwrap();
+
+        assert!(proof
+            .verify(
+                &public_share(&shares[0]),
+                &ephemeral,
+                &partial(&shares[0]),
+                b"session"
+            )
+            .is_ok());
+        assert!(proof
+            .verify(
+                &public_share(&shares[0]),
+                &ephemeral,
+                &partial(&shares[1]),
+                b"session"
+            )
+            .is_err());
+        assert!(proof
+            .verify(
+                &public_share(&shares[1]),
+                &ephemeral,
+                &partial(&shares[0]),
+                b"session"
+            )
+            .is_err());
+        assert!(proof
+            .verify(
+                &public_share(&shares[0]),
+                &ephemeral,
+                &partial(&shares[0]),
+                b"other"
+            )
+            .is_err());
+        assert!(proof
+            .verify(
+                &public_share(&shares[0]),
+                &key_pair().public,
+                &partial(&shares[0]),
+                b"session"
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use anyhow::{bail, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use serde::{Deserialize, Serialize};
+
+use super::messages::DigestAlgorithm;
+
+const SHARD_FORMAT: &str = "dora-shard-v1";
+
+/// Shard of an erasure-coded object kept by a node, along with what is needed to check the
+/// shards of its peers and the reconstructed object.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct StoredShard {
+    format: String,
+    /// Blake2b-256 digest of the object, published in the committee's log of the store request
+    pub digest: String,
+    pub size: usize,
+    pub data_shards: usize,
+    pub parity_shards: usize,
+    pub index: usize,
+    /// Blake2b-256 digests of every shard of the object, by shard index
+    pub shard_digests: Vec<String>,
+    /// base64 encoded content of the shard
+    shard: String,
+}
+
+impl StoredShard {
+    /// Splits the data in `data_shards + parity_shards` shards, any `data_shards` of which
+    /// reconstruct it, and keeps the shard at `index`.
+    pub fn new(
+        data: &[u8],
+        index: usize,
+        data_shards: usize,
+        parity_shards: usize,
+    ) -> Result<Self> {
+        let shard_size = data.len().div_ceil(data_shards).max(1);
+        let mut shards: Vec<Vec<u8>> = data
+            .chunks(shard_size)
+            .map(|chunk| {
+                let mut shard = chunk.to_vec();
+                shard.resize(shard_size, 0);
+                shard
+            })
+            .collect();
+        shards.resize(data_shards + parity_shards, vec![0; shard_size]);
+        ReedSolomon::new(data_shards, parity_shards)?.encode(&mut shards)?;
+
+        let shard = match shards.get(index) {
+            Some(shard) => STANDARD.encode(shard),
+            None => bail!("no shard at index {index}"),
+        };
+        Ok(Self {
+            format: SHARD_FORMAT.to_owned(),
+            digest: DigestAlgorithm::Blake2b256.digest(data),
+            size: data.len(),
+            data_shards,
+            parity_shards,
+            index,
+            shard_digests: shards
+                .iter()
+                .map(|shard| DigestAlgorithm::Blake2b256.digest(shard))
+                .collect(),
+            shard,
+        })
+    }
+
+    /// Parses a stored object, `None` if it is not a shard.
+    pub fn parse(object: &[u8]) -> Option<Self> {
+        serde_json::from_slice::<Self>(object)
+            .ok()
+            .filter(|stored| stored.format == SHARD_FORMAT)
+    }
+
+    pub fn shard(&self) -> Result<Vec<u8>> {
+        Ok(STANDARD.decode(&self.shard)?)
+    }
+
+    /// Whether the node's shard matches the digest recorded for its index.
+    pub fn is_intact(&self) -> bool {
+        match self.shard() {
+            Ok(shard) => {
+                self.shard_digests.get(self.index)
+                    == Some(&DigestAlgorithm::Blake2b256.digest(&shard))
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Reconstructs the object from the given shards, checking it against the digest
+    /// published when it was stored.
+    pub fn reconstruct(&self, shards: Vec<(usize, Vec<u8>)>) -> Result<Vec<u8>> {
+        let mut slots: Vec<Option<Vec<u8>>> = vec![None; self.data_shards + self.parity_shards];
+        for (index, shard) in shards {
+            if let Some(slot) = slots.get_mut(index) {
+                *slot = Some(shard);
+            }
+        }
+        ReedSolomon::new(self.data_shards, self.parity_shards)?.reconstruct_data(&mut slots)?;
+
+        let mut data: Vec<u8> = slots
+            .into_iter()
+            .take(self.data_shards)
+            .flatten()
+            .flatten()
+            .collect();
+        data.truncate(self.size);
+        if DigestAlgorithm::Blake2b256.digest(&data) != self.digest {
+            bail!("reconstructed object does not match its digest");
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA_SHARDS: usize = 3;
+    const PARITY_SHARDS: usize = 2;
+
+    fn shards(data: &[u8]) -> Vec<StoredShard> {
+        (0..DATA_SHARDS + PARITY_SHARDS)
+            .map(|index| StoredShard::new(data, index, DATA_SHARDS, PARITY_SHARDS).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn reconstructs_with_erased_shards() {
+        for data in [&b""[..], &b"a"[..], &b"erasure-coded data"[..]] {
+            let shards = shards(data);
+            assert!(shards.iter().all(StoredShard::is_intact));
+            for erased in [[0, 1], [1, 3], [3, 4]] {
+                let remaining = shards
+                    .iter()
+                    .filter(|shard| !erased.contains(&shard.index))
+                    .map(|shard| (shard.index, shard.shard().unwrap()))
+                    .collect();
+                assert_eq!(shards[0].reconstruct(remaining).unwrap(), data);
+            }
+        }
+    }
+
+    #[test]
+    fn fails_without_enough_shards() {
+        let shards = shards(b"erasure-coded data");
+        let remaining = shards[..DATA_SHARDS - 1]
+            .iter()
+            .map(|shard| (shard.index, shard.shard().unwrap()))
+            .collect();
+        assert!(shards[0].reconstruct(remaining).is_err());
+        assert!(StoredShard::new(b"data", DATA_SHARDS + PARITY_SHARDS, 3, 2).is_err());
+    }
+
+    #[test]
+    fn detects_altered_shards() {
+        let shards = shards(b"erasure-coded data");
+        let mut altered = shards[0].shard().unwrap();
+        altered[0] ^= 1;
+        let mut remaining: Vec<(usize, Vec<u8>)> = shards[1..DATA_SHARDS]
+            .iter()
+            .map(|shard| (shard.index, shard.shard().unwrap()))
+            .collect();
+        remaining.push((0, altered.clone()));
+        assert!(shards[0].reconstruct(remaining).is_err());
+
+        let mut tampered = shards[0].clone();
+        tampered.shard = STANDARD.encode(altered);
+        assert!(!tampered.is_intact());
+    }
+
+    #[test]
+    fn parses_stored_shards_only() {
+        let shard = &shards(b"erasure-coded data")[2];
+        let parsed = StoredShard::parse(&serde_json::to_vec(shard).unwrap()).unwrap();
+        assert_eq!(parsed.index, 2);
+        assert_eq!(parsed.shard().unwrap(), shard.shard().unwrap());
+        assert!(StoredShard::parse(b"erasure-coded data").is_none());
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+};
+
+use super::messages::DigestAlgorithm;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Temporary file holding a streamed input, so that it can be stored without holding it in
+/// memory once its digest is known. The file is removed when the spool is dropped.
+pub(crate) struct Spool {
+    file: File,
+    path: PathBuf,
+    /// hex encoded Blake2b-256 digest of the input
+    pub(crate) digest: String,
+    pub(crate) size: u64,
+}
+
+impl Spool {
+    pub(crate) fn new(reader: &mut dyn Read) -> io::Result<Self> {
+        let path =
+            std::env::temp_dir().join(format!("dora-{}", hex::encode(rand::random::<[u8; 8]>())));
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        let mut spool = Self {
+            file,
+            path,
+            digest: String::new(),
+            size: 0,
+        };
+
+        let mut hasher = DigestAlgorithm::Blake2b256.hasher();
+        let mut buffer = vec![0; BUFFER_SIZE];
+        loop {
+            let read = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            hasher.write_all(&buffer[..read])?;
+            spool.file.write_all(&buffer[..read])?;
+            spool.size += read as u64;
+        }
+        spool.file.flush()?;
+        spool.file.seek(SeekFrom::Start(0))?;
+        spool.digest = hasher.finalize();
+        Ok(spool)
+    }
+
+    pub(crate) fn file(&mut self) -> &mut File {
+        &mut self.file
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            log::warn!("could not remove {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spools_the_input_with_its_digest() {
+        let input = vec![7; 3 * BUFFER_SIZE + 1];
+        let mut spool = Spool::new(&mut input.as_slice()).unwrap();
+        let path = spool.path.clone();
+
+        assert_eq!(spool.size, input.len() as u64);
+        assert_eq!(spool.digest, DigestAlgorithm::Blake2b256.digest(&input));
+        let mut spooled = vec![];
+        spool.file().read_to_end(&mut spooled).unwrap();
+        assert_eq!(spooled, input);
+
+        drop(spool);
+        assert!(!path.exists());
+    }
+}
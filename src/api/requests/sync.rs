@@ -0,0 +1,305 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::bail;
+use identity_iota::core::ToJson;
+use kyber_rs::{group::edwards25519::Point, sign::eddsa, util::key::Pair};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    net::channel::{Receiver, Sender},
+    states::{
+        feed::MessageWrapper,
+        sync::{IndexEntry, IndexPiece, ObjectPiece, RootPiece, SyncMessage},
+    },
+    store::Storage,
+};
+
+use super::{
+    messages::CommitteeLog,
+    repair::{check_versions, merkle_root},
+    EpochSchedule, SealedData,
+};
+
+/// Tag where the nodes publish their sync reports, next to the committee's tag.
+pub fn sync_tag(committee_tag: &str) -> String {
+    format!("{committee_tag}-sync")
+}
+
+/// Participants of the committees whose objects the [SyncServer] hands out, shared between
+/// the server and the node, which sets them once the committee's DKG is done.
+#[derive(Clone, Default)]
+pub struct SyncCommittees(Arc<Mutex<HashMap<String, Vec<Point>>>>);
+
+impl SyncCommittees {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, committee_did: &str, participants: Vec<Point>) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(committee_did.to_owned(), participants);
+    }
+
+    /// Whether the node is a participant of the committee, `false` for an unknown committee.
+    pub fn is_member(&self, committee_did: &str, node: &Point) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .get(committee_did)
+            .is_some_and(|participants| participants.contains(node))
+    }
+}
+
+/// Answers the peers syncing their storage with the objects this node keeps for them. Only
+/// the signed requests of the committees' members are answered, and the answers are signed
+/// with the node's key.
+pub struct SyncServer {
+    storage: Storage,
+    keypair: Pair<Point>,
+    committees: SyncCommittees,
+    repair_schedule: Option<EpochSchedule>,
+    /// Merkle root of each committee's intact objects, with the repair epoch it was computed
+    /// for, so that the objects are checked at most once per epoch whatever the number of
+    /// requests
+    roots: HashMap<String, (u64, Option<String>)>,
+}
+
+impl SyncServer {
+    pub fn new(
+        storage: Storage,
+        keypair: Pair<Point>,
+        committees: SyncCommittees,
+        repair_schedule: Option<EpochSchedule>,
+    ) -> Self {
+        Self {
+            storage,
+            keypair,
+            committees,
+            repair_schedule,
+            roots: HashMap::new(),
+        }
+    }
+
+    /// Answers the root, index and object requests received on `input` until it is closed. The
+    /// answers of the peers to the node's own requests are forwarded to `client`.
+    pub fn serve(
+        &mut self,
+        mut input: impl Receiver<MessageWrapper<SyncMessage>>,
+        output: impl Sender<MessageWrapper<SyncMessage>>,
+        client: impl Sender<MessageWrapper<SyncMessage>>,
+    ) {
+        while let Ok(MessageWrapper {
+            session_id,
+            message,
+        }) = input.recv()
+        {
+            let answer = match message {
+                SyncMessage::RootRequest(request) => {
+                    if !self.is_authorized(&request.committee_did, &request.owner)
+                        || !request.verify(&session_id)
+                    {
+                        continue;
+                    }
+                    let root = match self.root(&request.committee_did, request.epoch) {
+                        Some(root) => root,
+                        None => {
+                            log::warn!(
+                                "ignoring root request of {}: epoch {} is not current",
+                                request.owner.to_string(),
+                                request.epoch
+                            );
+                            continue;
+                        }
+                    };
+                    RootPiece::new(&self.keypair, &session_id, root).map(SyncMessage::RootPiece)
+                }
+                SyncMessage::IndexRequest(request) => {
+                    if !self.is_authorized(&request.committee_did, &request.owner)
+                        || !request.verify(&session_id)
+                    {
+                        continue;
+                    }
+                    let storage = self.storage.for_committee(&request.committee_did);
+                    let entries = index(&storage).unwrap_or_else(|e| {
+                        log::warn!("could not index the stored objects: {}", e);
+                        vec![]
+                    });
+                    IndexPiece::new(&self.keypair, &session_id, entries)
+                        .map(SyncMessage::IndexPiece)
+                }
+                SyncMessage::ObjectRequest(request) => {
+                    if !self.is_authorized(&request.committee_did, &request.owner)
+                        || !request.verify(&session_id)
+                    {
+                        continue;
+                    }
+                    let content = self
+                        .storage
+                        .for_committee(&request.committee_did)
+                        .get_version(&request.key, request.version)
+                        .ok()
+                        .and_then(|content| {
+                            SealedData::seal(&request.owner, &content)
+                                .map_err(|e| log::warn!("could not encrypt the object: {}", e))
+                                .ok()
+                        });
+                    ObjectPiece::new(&self.keypair, &session_id, content)
+                        .map(SyncMessage::ObjectPiece)
+                }
+                message => {
+                    if let Err(e) = client.send(MessageWrapper {
+                        session_id,
+                        message,
+                    }) {
+                        log::error!("could not relay sync message: {}", e);
+                    }
+                    continue;
+                }
+            };
+            let message = match answer {
+                Ok(message) => message,
+                Err(e) => {
+                    log::error!("could not sign sync answer: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = output.send(MessageWrapper {
+                session_id,
+                message,
+            }) {
+                log::error!("could not answer sync request: {}", e);
+            }
+        }
+    }
+
+    /// Whether the requester is a member of the committee whose objects it asks for, the
+    /// objects are only handed out to the nodes that keep them.
+    fn is_authorized(&self, committee_did: &str, requester: &Point) -> bool {
+        let is_member = self.committees.is_member(committee_did, requester);
+        if !is_member {
+            log::warn!(
+                "ignoring sync request of {}: not a member of {}",
+                requester.to_string(),
+                committee_did
+            );
+        }
+        is_member
+    }
+
+    /// Root of the committee's intact objects at the repair epoch, `None` if the epoch is not
+    /// a current one. The objects are checked again only for a later epoch than the cached
+    /// root's one.
+    fn root(&mut self, committee_did: &str, epoch: u64) -> Option<Option<String>> {
+        if !self
+            .repair_schedule
+            .as_ref()
+            .is_some_and(|schedule| schedule.accepts(epoch))
+        {
+            return None;
+        }
+        if let Some((cached_epoch, root)) = self.roots.get(committee_did) {
+            if *cached_epoch >= epoch {
+                return Some(root.clone());
+            }
+        }
+        let storage = self.storage.for_committee(committee_did);
+        let root = check_versions(&storage)
+            .map(|(intact, _)| merkle_root(&intact))
+            .map_err(|e| log::warn!("could not check the stored objects: {}", e))
+            .ok();
+        self.roots
+            .insert(committee_did.to_owned(), (epoch, root.clone()));
+        Some(root)
+    }
+}
+
+/// Every version of the stored objects whose store log is kept, oldest first.
+fn index(storage: &Storage) -> anyhow::Result<Vec<IndexEntry>> {
+    let mut entries = vec![];
+    for key in storage.list("")? {
+        for metadata in storage.versions(&key)? {
+            let log = match storage.get_log(&metadata.request_id)? {
+                Some(log) => serde_json::from_slice::<CommitteeLog>(&log)?,
+                None => continue,
+            };
+            entries.push(IndexEntry { metadata, log });
+        }
+    }
+    Ok(entries)
+}
+
+/// Version of an object, as listed in a [SyncReport].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ObjectVersion {
+    pub key: String,
+    pub version: u64,
+}
+
+/// Versions a node fetched from its peers, when syncing or repairing its storage, this is the
+/// part of the [SyncReport] signed by the node.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SyncRecord {
+    pub(crate) committee_did: String,
+    pub(crate) node_did: String,
+    pub(crate) synced: Vec<ObjectVersion>,
+    /// corrupted versions replaced with a copy fetched from the peers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) repaired: Vec<ObjectVersion>,
+    /// versions listed by the peers that could not be fetched or verified
+    pub(crate) failed: Vec<ObjectVersion>,
+}
+
+/// Outcome of the sync of a node's storage with its peers, signed by the node.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SyncReport {
+    #[serde(flatten)]
+    pub(crate) record: SyncRecord,
+    pub(crate) signature_hex: Option<String>,
+}
+
+impl SyncReport {
+    /// Verifies the node's signature of the report against the node's public key.
+    pub fn verify(&self, public_key: &Point) -> anyhow::Result<()> {
+        let signature_hex = match &self.signature_hex {
+            Some(signature_hex) => signature_hex,
+            None => bail!("Missing signature"),
+        };
+        eddsa::verify(
+            public_key,
+            &self.record.to_jcs()?,
+            &hex::decode(signature_hex)?,
+        )
+        .map_err(|_| anyhow::Error::msg("Signature is not valid"))?;
+        Ok(())
+    }
+
+    pub fn node_did(&self) -> &str {
+        &self.record.node_did
+    }
+
+    pub fn synced(&self) -> &[ObjectVersion] {
+        &self.record.synced
+    }
+
+    pub fn repaired(&self) -> &[ObjectVersion] {
+        &self.record.repaired
+    }
+
+    pub fn failed(&self) -> &[ObjectVersion] {
+        &self.record.failed
+    }
+}
+
+impl FromStr for SyncReport {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::de::from_str(s)
+    }
+}
@@ -10,12 +10,17 @@ use kyber_rs::{
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
-use crate::did::Document;
+use crate::{api::requests::Quotas, did::Document};
 
 pub mod node;
 pub mod run;
+#[cfg(feature = "simulation")]
+pub mod simulate;
 
 const SAVE_FILE: &str = "node-state.json";
+/// Time [s] between two runs of each periodic task of a committee, unless its configuration
+/// sets another one.
+pub(crate) const DEFAULT_TASK_INTERVAL: u64 = 3600;
 const SAVE_FILE_DIR_CONFIG: &str = "DORA_SAVE_DIR";
 fn save_location() -> String {
     match std::env::var(SAVE_FILE_DIR_CONFIG) {
@@ -28,6 +33,9 @@ fn save_location() -> String {
 pub struct SaveData {
     node_state: Option<NodeState>,
     committee_state: Option<CommitteeState>,
+    /// Volatile save data is never written to disk.
+    #[serde(skip)]
+    volatile: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,6 +59,35 @@ struct CommitteeState {
     dist_key: EdPoint,
     did_urls: Vec<String>,
     committee_did: Option<String>,
+    /// committees saved before their configuration was given by the governor use the default
+    /// one
+    #[serde(default)]
+    config: CommitteeConfig,
+}
+
+/// Settings given by the governor to the nodes of a new committee, which must be the same on
+/// every node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct CommitteeConfig {
+    /// time [s] between two conservation audits, audits are disabled if 0
+    pub audit_interval: u64,
+    /// time [s] between two garbage collections of the expired objects, disabled if 0
+    pub gc_interval: u64,
+    /// time [s] between two anti-entropy repairs of the stored objects, disabled if 0
+    pub repair_interval: u64,
+    pub quotas: Quotas,
+}
+
+impl Default for CommitteeConfig {
+    fn default() -> Self {
+        Self {
+            audit_interval: DEFAULT_TASK_INTERVAL,
+            gc_interval: DEFAULT_TASK_INTERVAL,
+            repair_interval: DEFAULT_TASK_INTERVAL,
+            quotas: Quotas::default(),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -83,7 +120,20 @@ impl SaveData {
         Ok(save_data)
     }
 
+    /// Creates empty save data that is only kept in memory, as needed when several nodes run in
+    /// the same process.
+    #[cfg(feature = "simulation")]
+    pub(crate) fn volatile() -> Self {
+        Self {
+            volatile: true,
+            ..Default::default()
+        }
+    }
+
     fn save(&self) -> Result<(), SaveDataError> {
+        if self.volatile {
+            return Ok(());
+        }
         let data = serde_json::ser::to_string_pretty(self)?;
         fs::write(save_location(), data)?;
         log::debug!("saved data to: {:?}", save_location());
@@ -1,24 +1,32 @@
-use std::sync::mpsc::{Receiver, Sender};
+use std::{
+    sync::{
+        mpsc::{Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    time::Duration,
+};
 
-use crate::api::requests::{ApiNode, ApiParams, GenericRequest, HandlerParams};
-use crate::demo::run::{get_address, get_address_balance, request_faucet_funds};
-use crate::demo::CommitteeState;
-use crate::did::{new_document, resolve_document};
+use crate::api::gateway::{CommitteeInfo, GatewayState, RequestStatus};
+use crate::api::requests::{
+    audit_tag, deletions_tag, sync_tag, ApiNode, ApiParams, EpochSchedule, EpochTick,
+    GenericRequest, HandlerParams, HttpSecrets, OracleChannels, ProtocolChannels, RevealChannels,
+    ScheduledTask, ShardChannels, SyncChannels, SyncCommittees,
+};
+use crate::demo::{CommitteeConfig, CommitteeState};
+use crate::did::DidRegistry;
 use crate::dkg::{DkgMessage, DkgTerminalStates};
-use crate::dlt::iota::{FsmSigner, Listener, Publisher};
+use crate::dlt::{iota::FsmSigner, Transport};
 use crate::logging::{new_node_signature_logger, NodeSignatureLogger};
 use crate::states::dkg::InitializingIota;
 use crate::states::feed::{Feed, MessageWrapper};
 use crate::states::fsm::StateMachine;
+use crate::states::oracle::OracleMessage;
+use crate::states::reveal::RevealMessage;
+use crate::states::shards::ShardMessage;
 use crate::states::sign::{self, SignMessage};
+use crate::states::sync::SyncMessage;
 use crate::store::Storage;
 
-use identity_iota::iota::NetworkName;
-use identity_iota::prelude::IotaDID;
-use iota_client::block::address::Address;
-use iota_client::block::output::AliasId;
-use iota_client::node_api::indexer::query_parameters::QueryParameter;
-use iota_client::Client;
 use kyber_rs::encoding::BinaryMarshaler;
 use kyber_rs::group::edwards25519::SuiteEd25519;
 use kyber_rs::share::dkg::rabin::DistKeyGenerator;
@@ -34,6 +42,26 @@ pub struct NodeChannels {
     pub dkg_output_channel: Sender<MessageWrapper<DkgMessage>>,
     pub sign_output_channel: Sender<MessageWrapper<SignMessage>>,
     pub sign_input_channel_sender: Sender<MessageWrapper<SignMessage>>,
+    pub oracle_input_channel: Receiver<MessageWrapper<OracleMessage>>,
+    pub oracle_output_channel: Sender<MessageWrapper<OracleMessage>>,
+    pub oracle_input_channel_sender: Sender<MessageWrapper<OracleMessage>>,
+    pub reveal_input_channel: Receiver<MessageWrapper<RevealMessage>>,
+    pub reveal_output_channel: Sender<MessageWrapper<RevealMessage>>,
+    pub reveal_input_channel_sender: Sender<MessageWrapper<RevealMessage>>,
+    pub shard_input_channel: Receiver<MessageWrapper<ShardMessage>>,
+    pub shard_output_channel: Sender<MessageWrapper<ShardMessage>>,
+    pub shard_input_channel_sender: Sender<MessageWrapper<ShardMessage>>,
+    pub sync_input_channel: Receiver<MessageWrapper<SyncMessage>>,
+    pub sync_output_channel: Sender<MessageWrapper<SyncMessage>>,
+    pub sync_input_channel_sender: Sender<MessageWrapper<SyncMessage>>,
+}
+
+/// Periodic task of the committee, with the last epoch and turn whose tick the node published
+/// (if it led it) and the last epoch it ran.
+struct ScheduledRun {
+    schedule: EpochSchedule,
+    ticked: (u64, u64),
+    run: Option<u64>,
 }
 
 pub struct Node {
@@ -43,11 +71,15 @@ pub struct Node {
     pub protocol_params: NodeProtocolParams,
     pub id: usize,
     pub save_data: SaveData,
+    pub gateway: Option<GatewayState>,
+    /// committees whose objects the node's sync server hands out to their members
+    pub sync_committees: SyncCommittees,
 }
 
 pub struct NodeNetworkParams {
-    pub node_url: String,
-    pub faucet_url: String,
+    pub transport: Arc<dyn Transport>,
+    pub registry: Arc<dyn DidRegistry>,
+    pub http_secrets: HttpSecrets,
 }
 
 pub struct NodeProtocolParams {
@@ -56,6 +88,10 @@ pub struct NodeProtocolParams {
     pub num_participants: usize,
     pub time_resolution: usize,
     pub signature_sleep_time: u64,
+    pub config: CommitteeConfig,
+    /// fetches the objects kept by the peers before serving requests, for a node that
+    /// replaces a member of the committee
+    pub sync: bool,
 }
 
 impl Node {
@@ -72,10 +108,9 @@ impl Node {
             network_params,
             protocol_params,
             id,
-            save_data: SaveData {
-                node_state: None,
-                committee_state: None,
-            },
+            save_data: SaveData::default(),
+            gateway: None,
+            sync_committees: SyncCommittees::default(),
         }
     }
 
@@ -83,6 +118,20 @@ impl Node {
         Self { save_data, ..self }
     }
 
+    pub fn with_gateway(self, gateway: GatewayState) -> Self {
+        Self {
+            gateway: Some(gateway),
+            ..self
+        }
+    }
+
+    pub fn with_sync_committees(self, sync_committees: SyncCommittees) -> Self {
+        Self {
+            sync_committees,
+            ..self
+        }
+    }
+
     pub fn run(mut self, storage: Option<Storage>) -> Result<(), anyhow::Error> {
         let secret = self.keypair.private;
 
@@ -101,6 +150,7 @@ impl Node {
                     did_urls: did_urls.clone(),
                     dist_key,
                     committee_did: None,
+                    config: self.protocol_params.config.clone(),
                 });
                 if let Err(e) = self.save_data.save() {
                     log::error!("failed to save committee data: {}", e);
@@ -120,13 +170,7 @@ impl Node {
             None => Ok({
                 log::debug!("creating and publishing committee's DID document ...");
                 let did = self
-                    .create_did(
-                        dist_pub_key,
-                        &did_urls,
-                        &self.network_params.node_url,
-                        &dkg,
-                        secret,
-                    )
+                    .create_did(dist_pub_key, &did_urls, &dkg, secret)
                     .map_err(|e| {
                         anyhow::Error::msg("could not create and publish DID document").context(e)
                     })?;
@@ -140,12 +184,20 @@ impl Node {
             }),
         }?;
 
+        if let Some(gateway) = &self.gateway {
+            gateway.set_committee(CommitteeInfo {
+                did: did_url.clone(),
+                members: did_urls.clone(),
+                public_key: hex::encode(dist_pub_key.marshal_binary()?),
+            });
+        }
+
         // Create a iota signature logger
         let iota_logger = new_node_signature_logger(
             self.protocol_params.own_did_url.clone(),
             did_url.clone(),
             self.keypair.clone(),
-            self.network_params.node_url.clone(),
+            self.network_params.transport.clone(),
         );
         self.run_api_node(did_url, storage, dkg, iota_logger, did_urls)
             .map_err(|e| anyhow::Error::msg("failed to run api node").context(e))?;
@@ -156,7 +208,6 @@ impl Node {
         &self,
         dist_pub_key: Point,
         dids: &[String],
-        node_url: &str,
         dkg: &DistKeyGenerator<SuiteEd25519>,
         secret: kyber_rs::group::edwards25519::Scalar,
     ) -> Result<String, anyhow::Error> {
@@ -164,46 +215,22 @@ impl Node {
         all_dids.push(self.protocol_params.own_did_url.clone());
         all_dids.sort();
 
-        let client = Client::builder().with_node(node_url)?.finish()?;
-        let rt = tokio::runtime::Runtime::new()?;
+        let registry = &self.network_params.registry;
+        // the first node on the list funds and publishes the committee's DID document
+        let is_publisher = self.protocol_params.own_did_url == all_dids[0];
 
-        let address = get_address(&dist_pub_key.marshal_binary()?);
-        let address_str = address.to_bech32(rt.block_on(client.get_bech32_hrp())?);
+        registry.fund(&dist_pub_key, is_publisher)?;
 
-        let balance = rt.block_on(get_address_balance(&client, &address))?;
-        log::trace!(
-            "committee's address {} balance is: {}",
-            address_str,
-            balance
-        );
-        if balance < 10000000 {
-            log::trace!("waiting for funds on committee's address {}", address_str);
-            if self.protocol_params.own_did_url == all_dids[0] {
-                rt.block_on(request_faucet_funds(
-                    &client,
-                    address,
-                    &self.network_params.faucet_url,
-                ))?
-            } else {
-                loop {
-                    std::thread::sleep(std::time::Duration::from_secs(3));
-                    let balance = rt.block_on(get_address_balance(&client, &address))?;
-                    if balance >= 10000000 {
-                        break;
-                    }
-                }
-            }
-        }
-        let mut document = new_document(
-            &dist_pub_key
-                .marshal_binary()
-                .map_err(|e| anyhow::Error::msg("failed to marshal dist pub key").context(e))?,
-            Some(self.protocol_params.time_resolution as u32),
-            Some(dids.to_vec()),
-            node_url,
-            false,
-        )
-        .map_err(|e| anyhow::Error::msg("failed to create new DID document").context(e))?;
+        let mut document = registry
+            .new_document(
+                &dist_pub_key
+                    .marshal_binary()
+                    .map_err(|e| anyhow::Error::msg("failed to marshal dist pub key").context(e))?,
+                Some(self.protocol_params.time_resolution as u32),
+                Some(dids.to_vec()),
+                false,
+            )
+            .map_err(|e| anyhow::Error::msg("failed to create new DID document").context(e))?;
         log::info!("committee's DID document created");
         log::info!("signing committee's DID document ...");
 
@@ -213,60 +240,32 @@ impl Node {
             .with_sleep_time(self.protocol_params.signature_sleep_time)
             .with_id(DKG_ID.to_string());
 
-        let signer = FsmSigner::new(
+        let mut signer = FsmSigner::new(
             sign_initial_state,
             &self.channels.sign_input_channel,
             self.channels.sign_output_channel.clone(),
         );
 
-        document.sign(signer, &dist_pub_key, node_url)?;
+        registry.sign_document(&mut document, &mut signer, &dist_pub_key)?;
         log::info!("committe's DID document has been signed");
 
-        let mut did = "".to_owned();
-
         // Publish signed DID if the node is the first on the list
-        if self.protocol_params.own_did_url == all_dids[0] {
+        let did = if is_publisher {
             log::info!("publishing committee's DID document ...");
-            did = document.publish(node_url).map_err(|e| {
+            registry.publish_document(&mut document).map_err(|e| {
                 anyhow::Error::msg("failed to publish committee's DID document").context(e)
-            })?;
-            log::info!("committee's DID document has been published");
-            log::info!("committee's DID is: {}", did);
+            })?
         } else {
             log::info!("waiting for committee's DID ...");
-            let c = Client::builder().with_node(node_url)?.finish()?;
-            let rt = tokio::runtime::Runtime::new()?;
-            let mut found = false;
             loop {
                 std::thread::sleep(std::time::Duration::from_secs(5));
-                let alias_ids = rt.block_on(find_alias_ids(
-                    &c,
-                    get_address(&dist_pub_key.marshal_binary()?),
-                ))?;
-                for id in alias_ids {
-                    let did_candidate = IotaDID::from_alias_id(
-                        &id.to_string(),
-                        &NetworkName::try_from("rms")?,
-                    );
-                    let published_doc = resolve_document(did_candidate.to_string(), node_url);
-                    match published_doc {
-                        Ok(doc) => {
-                            if doc.public_key()? == document.public_key()? {
-                                found = true;
-                                did = did_candidate.to_string();
-                                break;
-                            }
-                        }
-                        Err(_) => continue,
-                    }
-                }
-                if found {
-                    break;
+                if let Some(published_did) = registry.find_document(&dist_pub_key)? {
+                    break published_did;
                 }
             }
-            log::info!("committee's DID document has been published");
-            log::info!("committee's DID is: {}", did);
-        }
+        };
+        log::info!("committee's DID document has been published");
+        log::info!("committee's DID is: {}", did);
         Ok(did)
     }
 
@@ -279,7 +278,7 @@ impl Node {
             self.protocol_params.own_did_url.clone(),
             self.protocol_params.did_urls.clone(),
             self.protocol_params.num_participants,
-            self.network_params.node_url.clone(),
+            self.network_params.registry.as_ref(),
         )?;
         let mut dkg_fsm = StateMachine::new(
             Box::new(dkg_initial_state),
@@ -304,26 +303,141 @@ impl Node {
     ) -> Result<(), anyhow::Error> {
         let binding = did_url.clone();
         let api_tag = &binding.split(':').last().unwrap()[2..];
-        let mut api_input = Listener::new(&self.network_params.node_url)?;
-        let api_output = Publisher::new(&self.network_params.node_url)?;
+        let transport = self.network_params.transport.clone();
         let api_params = ApiParams {
-            client: Client::builder()
-                .with_node(&self.network_params.node_url)?
-                .finish()?,
+            transport: transport.clone(),
             dkg,
             secret: self.keypair.private,
             public_key: self.keypair.public,
             id: self.id,
             signature_sender: self.channels.sign_input_channel_sender.clone(),
             signature_sleep_time: self.protocol_params.signature_sleep_time,
+            oracle_sender: self.channels.oracle_input_channel_sender.clone(),
+            reveal_sender: self.channels.reveal_input_channel_sender.clone(),
+            shard_sender: self.channels.shard_input_channel_sender.clone(),
+            sync_sender: self.channels.sync_input_channel_sender.clone(),
+            http_secrets: self.network_params.http_secrets.clone(),
+            quotas: self.protocol_params.config.quotas.clone(),
         };
+        self.sync_committees
+            .set(&did_url, api_params.dkg.participants.clone());
+        let storage = storage.unwrap().for_committee(&did_url);
+        let migrated = storage.migrate_legacy_keys()?;
+        if migrated > 0 {
+            log::info!(
+                "moved {} legacy keys into the committee's storage",
+                migrated
+            );
+        }
         let api_node = ApiNode {
-            storage: storage.unwrap(),
+            storage,
             api_params,
         };
-        let rt = tokio::runtime::Runtime::new()?;
         log::info!("listening for committee requests on tag: {}", api_tag);
-        for (message_data, req_id) in rt.block_on(api_input.start(api_tag.to_owned()))? {
+        let requests = transport.subscribe(api_tag.to_owned())?;
+        if self.protocol_params.sync {
+            self.run_sync(&api_node, &did_url, api_tag);
+        }
+        let mut scheduled: Vec<ScheduledRun> = [
+            (
+                ScheduledTask::Audit,
+                self.protocol_params.config.audit_interval,
+            ),
+            (
+                ScheduledTask::GarbageCollection,
+                self.protocol_params.config.gc_interval,
+            ),
+            (
+                ScheduledTask::Repair,
+                self.protocol_params.config.repair_interval,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, interval)| *interval > 0)
+        .map(|(task, interval)| {
+            let schedule = EpochSchedule::new(task, interval);
+            ScheduledRun {
+                // the first tick is published with the next epoch, once every node is listening
+                ticked: (schedule.current_epoch(), u64::MAX),
+                schedule,
+                run: None,
+            }
+        })
+        .collect();
+        let turns = api_node.api_params.dkg.participants.len() as u64;
+        loop {
+            for scheduled_run in &mut scheduled {
+                let epoch = scheduled_run.schedule.current_epoch();
+                let turn = scheduled_run.schedule.current_turn(turns);
+                if (epoch, turn) <= scheduled_run.ticked {
+                    continue;
+                }
+                scheduled_run.ticked = (epoch, turn);
+                // the leaders of the following turns only start the epoch if no tick did
+                if turn == 0 || scheduled_run.run.is_none_or(|run| run < epoch) {
+                    self.publish_tick(
+                        &api_node,
+                        scheduled_run.schedule.task(),
+                        epoch,
+                        turn,
+                        &did_url,
+                        api_tag,
+                    );
+                }
+            }
+            let timeout = scheduled
+                .iter()
+                .map(|scheduled_run| scheduled_run.schedule.until_next_turn(turns))
+                .min()
+                .unwrap_or(Duration::MAX);
+            let (message_data, req_id) = match requests.recv_timeout(timeout) {
+                Ok(request) => request,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            // ticks are parsed first, a generic request accepts any JSON object
+            if let Ok(tick) = serde_json::from_slice::<EpochTick>(&message_data) {
+                let scheduled_run = match scheduled
+                    .iter_mut()
+                    .find(|scheduled_run| scheduled_run.schedule.task() == tick.task)
+                {
+                    Some(scheduled_run) => scheduled_run,
+                    None => continue,
+                };
+                if tick.committee_did != did_url
+                    || !scheduled_run.schedule.accepts(tick.epoch)
+                    || scheduled_run.run.is_some_and(|run| tick.epoch <= run)
+                {
+                    continue;
+                }
+                if let Err(e) = tick.verify(&api_node.api_params.dkg.participants) {
+                    log::warn!("ignoring tick (block_id: {}): {}", req_id, e);
+                    continue;
+                }
+                scheduled_run.run = Some(tick.epoch);
+                let handler_params = HandlerParams {
+                    signature_logger: logger.clone(),
+                    committee_did: did_url.clone(),
+                    dids: did_urls.clone(),
+                    registry: self.network_params.registry.clone(),
+                };
+                match tick.task {
+                    ScheduledTask::Audit => {
+                        self.run_audit(&api_node, tick.epoch, &req_id, handler_params, api_tag)
+                    }
+                    ScheduledTask::GarbageCollection => self.run_garbage_collection(
+                        &api_node,
+                        &scheduled_run.schedule,
+                        tick.epoch,
+                        handler_params,
+                        api_tag,
+                    ),
+                    ScheduledTask::Repair => {
+                        self.run_repair(&api_node, tick.epoch, &did_url, api_tag)
+                    }
+                }
+                continue;
+            }
             let message: GenericRequest = match serde_json::from_slice(&message_data) {
                 Ok(m) => m,
                 Err(_) => {
@@ -337,24 +451,39 @@ impl Node {
                     continue;
                 }
             };
-            let session_id: String = hex::encode(req_id).chars().take(10).collect();
+            let session_id: String = req_id.trim_start_matches("0x").chars().take(10).collect();
             log::info!(
                 "received a request for the committee (block_id: {})",
                 req_id
             );
             log::info!("handling request [{}]", session_id);
+            self.set_request_status(&req_id, RequestStatus::Processing);
             let handler_params = HandlerParams {
                 signature_logger: logger.clone(),
                 committee_did: did_url.clone(),
                 dids: did_urls.clone(),
-                node_url: self.network_params.node_url.clone(),
+                registry: self.network_params.registry.clone(),
             };
             let response = match api_node
                 .handle_message(
                     request,
                     &self.channels.sign_input_channel,
                     self.channels.sign_output_channel.clone(),
-                    &req_id.to_string(),
+                    ProtocolChannels {
+                        oracle: OracleChannels {
+                            input: &self.channels.oracle_input_channel,
+                            output: self.channels.oracle_output_channel.clone(),
+                        },
+                        reveal: RevealChannels {
+                            input: &self.channels.reveal_input_channel,
+                            output: self.channels.reveal_output_channel.clone(),
+                        },
+                        shards: ShardChannels {
+                            input: &self.channels.shard_input_channel,
+                            output: self.channels.shard_output_channel.clone(),
+                        },
+                    },
+                    &req_id,
                     handler_params,
                 )
                 .map_err(anyhow::Error::from)
@@ -362,11 +491,18 @@ impl Node {
                 Ok(r) => r,
                 Err(e) => {
                     log::error!("could not handle request [{}]: {:?}", session_id, e);
+                    self.set_request_status(
+                        &req_id,
+                        RequestStatus::Failed {
+                            error: e.to_string(),
+                        },
+                    );
                     continue;
                 }
             };
             if let Some((r, working_nodes)) = response {
                 log::info!("request [{}] done", session_id);
+                self.set_request_status(&req_id, RequestStatus::Done { log: r.clone() });
                 let encoded = serde_json::to_vec(&r)?;
                 let mut wn = working_nodes.clone();
                 // Publish signed DID if the node is the first on the list
@@ -376,7 +512,7 @@ impl Node {
                         "publishing committee's task log for request [{}]...",
                         session_id
                     );
-                    match rt.block_on(api_output.publish(&encoded, Some(api_tag.to_owned()))) {
+                    match transport.publish(&encoded, Some(api_tag.to_owned())) {
                         Ok(i) => log::info!(
                             "committee's task log for request [{}] published (block_id: {})",
                             session_id,
@@ -393,24 +529,242 @@ impl Node {
         }
         Ok(())
     }
-}
 
-pub async fn find_alias_ids(
-    client: &Client,
-    address: Address,
-) -> Result<Vec<AliasId>, anyhow::Error> {
-    // Get outputs from node and select inputs
-    let mut alias_ids = Vec::new();
-
-    let alias_output_ids = client
-        .alias_output_ids(vec![QueryParameter::Governor(
-            address.to_bech32(client.get_bech32_hrp().await?),
-        )])
-        .await?;
-
-    for output_id in alias_output_ids {
-        alias_ids.push(AliasId::null().or_from_output_id(&output_id))
+    /// Publishes the tick starting the task's epoch on the committee's tag, if this node is the
+    /// epoch's leader.
+    fn publish_tick(
+        &self,
+        api_node: &ApiNode,
+        task: ScheduledTask,
+        epoch: u64,
+        turn: u64,
+        did_url: &str,
+        api_tag: &str,
+    ) {
+        let leader = EpochTick::leader(&api_node.api_params.dkg.participants, epoch, turn);
+        if leader != Some(self.keypair.public) {
+            return;
+        }
+        let published = EpochTick::new(did_url.to_owned(), task, epoch, turn, &self.keypair)
+            .and_then(|tick| Ok(serde_json::to_vec(&tick)?))
+            .and_then(|encoded| {
+                self.network_params
+                    .transport
+                    .publish(&encoded, Some(api_tag.to_owned()))
+            });
+        match published {
+            Ok(i) => log::info!(
+                "{:?} tick of epoch {} (turn {}) published (block_id: {})",
+                task,
+                epoch,
+                turn,
+                i
+            ),
+            Err(e) => log::error!(
+                "could not publish {:?} tick of epoch {}: {}",
+                task,
+                epoch,
+                e
+            ),
+        }
+    }
+
+    /// Runs the audit of the given epoch and, if this node is the first of the working nodes,
+    /// publishes the audit log on the committee's audit tag.
+    fn run_audit(
+        &self,
+        api_node: &ApiNode,
+        epoch: u64,
+        tick_id: &str,
+        handler_params: HandlerParams,
+        api_tag: &str,
+    ) {
+        log::info!("running conservation audit of epoch {}", epoch);
+        let result = api_node.audit(
+            epoch,
+            tick_id,
+            &self.channels.sign_input_channel,
+            self.channels.sign_output_channel.clone(),
+            handler_params,
+        );
+        let (audit_log, mut working_nodes) = match result {
+            Ok(Some(audit)) => audit,
+            Ok(None) => {
+                log::info!("nothing to audit in epoch {}", epoch);
+                return;
+            }
+            Err(e) => {
+                log::error!("audit of epoch {} failed: {:?}", epoch, e);
+                return;
+            }
+        };
+        log::info!(
+            "audit of epoch {} done, absent nodes: {:?}, bad signers: {:?}",
+            epoch,
+            audit_log.absent_nodes,
+            audit_log.bad_signers
+        );
+        working_nodes.sort();
+        if working_nodes.first() != Some(&self.protocol_params.own_did_url) {
+            return;
+        }
+        let published = serde_json::to_vec(&audit_log)
+            .map_err(anyhow::Error::from)
+            .and_then(|encoded| {
+                self.network_params
+                    .transport
+                    .publish(&encoded, Some(audit_tag(api_tag)))
+            });
+        match published {
+            Ok(i) => log::info!("audit log of epoch {} published (block_id: {})", epoch, i),
+            Err(e) => log::error!("could not publish audit log of epoch {}: {}", epoch, e),
+        }
+    }
+
+    /// Runs the garbage collection of the given epoch and, if this node is the first of the
+    /// working nodes, publishes the deletion log on the committee's deletions tag.
+    fn run_garbage_collection(
+        &self,
+        api_node: &ApiNode,
+        schedule: &EpochSchedule,
+        epoch: u64,
+        handler_params: HandlerParams,
+        api_tag: &str,
+    ) {
+        log::info!("collecting objects expired before epoch {}", epoch);
+        let result = api_node.collect_garbage(
+            schedule,
+            epoch,
+            &self.channels.sign_input_channel,
+            self.channels.sign_output_channel.clone(),
+            handler_params,
+        );
+        let (deletion_log, mut working_nodes) = match result {
+            Ok(Some(collection)) => collection,
+            Ok(None) => {
+                log::info!("no object expired before epoch {}", epoch);
+                return;
+            }
+            Err(e) => {
+                log::error!("garbage collection of epoch {} failed: {:?}", epoch, e);
+                return;
+            }
+        };
+        log::info!(
+            "garbage collection of epoch {} done, absent nodes: {:?}, bad signers: {:?}",
+            epoch,
+            deletion_log.absent_nodes,
+            deletion_log.bad_signers
+        );
+        working_nodes.sort();
+        if working_nodes.first() != Some(&self.protocol_params.own_did_url) {
+            return;
+        }
+        let published = serde_json::to_vec(&deletion_log)
+            .map_err(anyhow::Error::from)
+            .and_then(|encoded| {
+                self.network_params
+                    .transport
+                    .publish(&encoded, Some(deletions_tag(api_tag)))
+            });
+        match published {
+            Ok(i) => log::info!(
+                "deletion log of epoch {} published (block_id: {})",
+                epoch,
+                i
+            ),
+            Err(e) => log::error!("could not publish deletion log of epoch {}: {}", epoch, e),
+        }
+    }
+
+    /// Syncs the node's storage with its peers and publishes the report on the committee's
+    /// sync tag. Requests received in the meantime are handled once the sync is over.
+    fn run_sync(&self, api_node: &ApiNode, did_url: &str, api_tag: &str) {
+        log::info!("syncing the stored objects with the committee's nodes");
+        let result = api_node.sync(
+            &self.protocol_params.own_did_url,
+            did_url,
+            SyncChannels {
+                input: &self.channels.sync_input_channel,
+                output: self.channels.sync_output_channel.clone(),
+            },
+        );
+        let report = match result {
+            Ok(report) => report,
+            Err(e) => {
+                log::error!("sync failed: {:?}", e);
+                return;
+            }
+        };
+        log::info!(
+            "sync done, {} versions synced, {} failed",
+            report.synced().len(),
+            report.failed().len()
+        );
+        let published = serde_json::to_vec(&report)
+            .map_err(anyhow::Error::from)
+            .and_then(|encoded| {
+                self.network_params
+                    .transport
+                    .publish(&encoded, Some(sync_tag(api_tag)))
+            });
+        match published {
+            Ok(i) => log::info!("sync report published (block_id: {})", i),
+            Err(e) => log::error!("could not publish sync report: {}", e),
+        }
     }
 
-    Ok(alias_ids)
+    /// Runs the anti-entropy repair of the given epoch and, if some versions had to be
+    /// fetched, publishes the report on the committee's sync tag.
+    fn run_repair(&self, api_node: &ApiNode, epoch: u64, did_url: &str, api_tag: &str) {
+        log::info!("running anti-entropy repair of epoch {}", epoch);
+        let result = api_node.repair(
+            &self.protocol_params.own_did_url,
+            did_url,
+            epoch,
+            SyncChannels {
+                input: &self.channels.sync_input_channel,
+                output: self.channels.sync_output_channel.clone(),
+            },
+        );
+        let report = match result {
+            Ok(Some(report)) => report,
+            Ok(None) => {
+                log::info!("stored objects match the peers' ones in epoch {}", epoch);
+                return;
+            }
+            Err(e) => {
+                log::error!("repair of epoch {} failed: {:?}", epoch, e);
+                return;
+            }
+        };
+        log::info!(
+            "repair of epoch {} done, {} versions repaired, {} synced, {} failed",
+            epoch,
+            report.repaired().len(),
+            report.synced().len(),
+            report.failed().len()
+        );
+        let published = serde_json::to_vec(&report)
+            .map_err(anyhow::Error::from)
+            .and_then(|encoded| {
+                self.network_params
+                    .transport
+                    .publish(&encoded, Some(sync_tag(api_tag)))
+            });
+        match published {
+            Ok(i) => log::info!(
+                "repair report of epoch {} published (block_id: {})",
+                epoch,
+                i
+            ),
+            Err(e) => log::error!("could not publish repair report of epoch {}: {}", epoch, e),
+        }
+    }
+
+    fn set_request_status(&self, request_id: &str, status: RequestStatus) {
+        if let Some(gateway) = &self.gateway {
+            gateway.set_status(request_id, status);
+        }
+    }
 }
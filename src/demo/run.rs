@@ -8,34 +8,29 @@ use std::{
 
 use clap::Parser;
 
-use iota_client::{
-    block::{
-        address::{Address, Ed25519Address},
-        output::Output,
-    },
-    crypto::hashes::{blake2b::Blake2b256, Digest},
-    node_api::indexer::query_parameters::QueryParameter,
-    Client,
-};
 use kyber_rs::{
     encoding::BinaryMarshaler,
-    group::edwards25519::SuiteEd25519,
+    group::edwards25519::{Point, SuiteEd25519},
     sign::eddsa::EdDSA,
     util::key::{new_key_pair, Pair},
 };
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    api::{
+        gateway::{run_gateway, GatewayState},
+        requests::{EpochSchedule, HttpSecrets, ScheduledTask, SyncCommittees, SyncServer},
+    },
     demo::{
         node::{Node, NodeChannels, NodeNetworkParams, NodeProtocolParams},
-        NodeState, SaveData,
+        CommitteeConfig, NodeState, SaveData,
     },
-    did::new_document,
-    dlt::iota::Listener,
-    net::relay::{IotaBroadcastRelay, IotaListenRelay},
-    store::new_storage,
+    did::{DidRegistry, IotaRegistry},
+    dlt::{iota::IotaTransport, ChunkedTransport, Transport},
+    net::relay::{TransportBroadcastRelay, TransportListenRelay},
+    store::{new_storage, S3Options, Storage, StorageKey},
 };
-use anyhow::{Context, Result};
+use anyhow::Result;
 
 #[derive(Parser)]
 #[command(author, version, about = "node", long_about = None)]
@@ -57,6 +52,34 @@ pub struct NodeArgs {
     #[arg(long = "storage-secret-key", default_value = None)]
     storage_secret_key: Option<String>,
 
+    /// bucket of the s3 and minio-local storages
+    #[arg(long = "storage-bucket", default_value = "dora-node-bucket")]
+    storage_bucket: String,
+
+    /// region of the s3 and minio-local storages
+    #[arg(long = "storage-region", default_value = "eu-south-1")]
+    storage_region: String,
+
+    /// prefix of the node's keys in the bucket of the s3 and minio-local storages
+    #[arg(long = "storage-prefix", default_value = "")]
+    storage_prefix: String,
+
+    /// reaches the endpoint of the s3 storage over plain http
+    #[arg(long = "storage-no-tls")]
+    storage_no_tls: bool,
+
+    /// uses path-style addressing for the s3 storage, as most self-hosted services need
+    #[arg(long = "storage-path-style")]
+    storage_path_style: bool,
+
+    /// encrypts the stored objects with a key derived from the node's private key
+    #[arg(long = "encrypt-storage")]
+    encrypt_storage: bool,
+
+    /// file with the hex encoded key encrypting the stored objects, created if missing
+    #[arg(long = "storage-key-file", default_value = None)]
+    storage_key_file: Option<String>,
+
     #[arg(
         long = "node-url",
         default_value = "https://api.testnet.shimmer.network"
@@ -74,17 +97,39 @@ pub struct NodeArgs {
 
     #[arg(long = "signature-sleep-time", default_value = "5")]
     signature_sleep_time: u64,
+
+    /// fetches the objects kept by the other nodes of the committee before serving requests,
+    /// for a node replacing a member with a new storage
+    #[arg(long = "sync")]
+    sync: bool,
+
+    /// port of the HTTP gateway, the gateway is disabled if not set
+    #[arg(long = "http-port", default_value = None)]
+    http_port: Option<u16>,
+
+    /// address the HTTP gateway binds to, it has no authentication
+    #[arg(long = "http-address", default_value = "127.0.0.1")]
+    http_address: String,
+
+    /// JSON file with the headers added to the HTTP requests, by host
+    #[arg(long = "http-secrets", default_value = None)]
+    http_secrets: Option<String>,
 }
 
 pub fn run_node(args: NodeArgs) -> Result<()> {
     let mut storage = None;
     if let Some(strg) = args.storage {
-        storage = Some(new_storage(
-            &strg,
-            args.storage_access_key,
-            args.storage_secret_key,
-            args.storage_endpoint,
-        )?);
+        let s3_options = S3Options {
+            bucket: args.storage_bucket,
+            region: args.storage_region,
+            endpoint: None,
+            tls: !args.storage_no_tls,
+            path_style: args.storage_path_style,
+            prefix: args.storage_prefix,
+            access_key: args.storage_access_key,
+            secret_key: args.storage_secret_key,
+        };
+        storage = Some(new_storage(&strg, args.storage_endpoint, s3_options)?);
         log::trace!("storage is set");
 
         storage.clone().unwrap().health_check()?;
@@ -98,28 +143,107 @@ pub fn run_node(args: NodeArgs) -> Result<()> {
 
     let keypair = get_keypair(&mut save_data, suite)?;
 
-    let address = get_address(&keypair.public.marshal_binary()?);
-    let rt = tokio::runtime::Runtime::new()?;
-    let client = Client::builder().with_node(&args.node_url)?.finish()?;
-    let address_str = address.to_bech32(rt.block_on(client.get_bech32_hrp())?);
-
-    let balance = rt.block_on(get_address_balance(&client, &address))?;
-    log::trace!("node's address {} balance is: {}", address_str, balance);
-    if balance < 10000000 {
-        log::trace!("waiting for funds on node's address {}", address_str);
-        rt.block_on(request_faucet_funds(&client, address, &args.faucet_url))?
+    let storage_key = match args.storage_key_file {
+        Some(path) => Some(StorageKey::load_or_create(path)?),
+        None if args.encrypt_storage => {
+            Some(StorageKey::derive(&keypair.private.marshal_binary()?))
+        }
+        None => None,
+    };
+    if let Some(key) = storage_key {
+        storage = storage.map(|storage| storage.with_encryption(key));
+        log::info!("storage is encrypted");
     }
 
-    let did_url = get_did(&keypair, &args.node_url, &mut save_data)?;
+    let registry: Arc<dyn DidRegistry> =
+        Arc::new(IotaRegistry::new(&args.node_url).with_faucet(&args.faucet_url));
+    registry.fund(&keypair.public, true)?;
+
+    let did_url = get_did(&keypair, registry.as_ref(), &mut save_data)?;
 
     log::info!("node's DID is: {}", did_url);
 
+    let transport: Arc<dyn Transport> =
+        Arc::new(ChunkedTransport::new(IotaTransport::new(&args.node_url)?));
+
+    let http_secrets = match args.http_secrets {
+        Some(path) => HttpSecrets::load(path)?,
+        None => HttpSecrets::default(),
+    };
+
+    start_node(NodeSetup {
+        keypair,
+        did_url,
+        governor: args.governor,
+        storage,
+        transport,
+        registry,
+        save_data,
+        time_resolution: args.time_resolution,
+        signature_sleep_time: args.signature_sleep_time,
+        sync: args.sync,
+        http_port: args.http_port,
+        http_address: args.http_address,
+        http_secrets,
+    })
+}
+
+/// Everything a node needs to join a committee, once it owns a keypair and a published DID.
+pub(crate) struct NodeSetup {
+    pub keypair: Pair<Point>,
+    pub did_url: String,
+    pub governor: String,
+    pub storage: Option<Storage>,
+    pub transport: Arc<dyn Transport>,
+    pub registry: Arc<dyn DidRegistry>,
+    pub save_data: SaveData,
+    pub time_resolution: usize,
+    pub signature_sleep_time: u64,
+    pub sync: bool,
+    pub http_port: Option<u16>,
+    pub http_address: String,
+    pub http_secrets: HttpSecrets,
+}
+
+/// Waits for the governor's instructions (unless a committee was already saved) and runs the
+/// node over the given transport.
+pub(crate) fn start_node(setup: NodeSetup) -> Result<()> {
+    let NodeSetup {
+        keypair,
+        did_url,
+        governor,
+        storage,
+        transport,
+        registry,
+        save_data,
+        time_resolution,
+        signature_sleep_time,
+        sync,
+        http_port,
+        http_address,
+        http_secrets,
+    } = setup;
+
+    // the gateway is not joined, it serves requests for the whole life of the node
+    let gateway = http_port.map(|port| {
+        let state = GatewayState::new();
+        let gateway_state = state.clone();
+        let gateway_transport = transport.clone();
+        thread::spawn(move || {
+            if let Err(e) = run_gateway(&http_address, port, gateway_state, gateway_transport) {
+                log::error!("HTTP gateway stopped: {:?}", e);
+            }
+        });
+        state
+    });
+
     let is_completed = Arc::new(AtomicBool::new(false));
 
-    let mut all_dids = match save_data.committee_state {
-        Some(cs) => cs.did_urls,
+    let (mut all_dids, config) = match save_data.committee_state {
+        Some(ref cs) => (cs.did_urls.clone(), cs.config.clone()),
         None => {
-            listen_governor_instructions(args.governor, did_url.clone(), args.node_url.clone())?
+            let init = listen_governor_instructions(governor, did_url.clone(), transport.as_ref())?;
+            (init.nodes, init.config)
         }
     };
 
@@ -139,17 +263,17 @@ pub fn run_node(args: NodeArgs) -> Result<()> {
     let (dkg_input_channel_sender, dkg_input_channel) = mpsc::channel();
     let (dkg_output_channel, dkg_output_channel_receiver) = mpsc::channel();
 
-    let dkg_listen_relay = IotaListenRelay::new(
+    let dkg_listen_relay = TransportListenRelay::new(
         dkg_input_channel_sender,
         is_completed.clone(),
         peers_tags.clone(),
-        args.node_url.clone(),
+        transport.clone(),
     );
-    let mut dkg_broadcast_relay = IotaBroadcastRelay::new(
+    let mut dkg_broadcast_relay = TransportBroadcastRelay::new(
         own_tag.clone(),
         dkg_output_channel_receiver,
-        args.node_url.clone(),
-    )?;
+        transport.clone(),
+    );
 
     let dkg_listen_relay_handle = thread::spawn(move || dkg_listen_relay.listen());
     let dkg_broadcast_relay_handle = thread::spawn(move || dkg_broadcast_relay.broadcast());
@@ -157,18 +281,108 @@ pub fn run_node(args: NodeArgs) -> Result<()> {
     let (sign_input_channel_sender, sign_input_channel) = mpsc::channel();
     let (sign_output_channel, sign_input_channel_receiver) = mpsc::channel();
 
-    let sign_listen_relay = IotaListenRelay::new(
+    let sign_listen_relay = TransportListenRelay::new(
         sign_input_channel_sender.clone(),
         is_completed.clone(),
-        peers_tags,
-        args.node_url.clone(),
+        peers_tags.clone(),
+        transport.clone(),
+    );
+    let mut sign_broadcast_relay = TransportBroadcastRelay::new(
+        own_tag.clone(),
+        sign_input_channel_receiver,
+        transport.clone(),
     );
-    let mut sign_broadcast_relay =
-        IotaBroadcastRelay::new(own_tag, sign_input_channel_receiver, args.node_url.clone())?;
 
     let sign_listen_relay_handle = thread::spawn(move || sign_listen_relay.listen());
     let sign_broadcast_relay_handle = thread::spawn(move || sign_broadcast_relay.broadcast());
 
+    let (oracle_input_channel_sender, oracle_input_channel) = mpsc::channel();
+    let (oracle_output_channel, oracle_input_channel_receiver) = mpsc::channel();
+
+    let oracle_listen_relay = TransportListenRelay::new(
+        oracle_input_channel_sender.clone(),
+        is_completed.clone(),
+        peers_tags.clone(),
+        transport.clone(),
+    );
+    let mut oracle_broadcast_relay = TransportBroadcastRelay::new(
+        own_tag.clone(),
+        oracle_input_channel_receiver,
+        transport.clone(),
+    );
+
+    let oracle_listen_relay_handle = thread::spawn(move || oracle_listen_relay.listen());
+    let oracle_broadcast_relay_handle = thread::spawn(move || oracle_broadcast_relay.broadcast());
+
+    let (reveal_input_channel_sender, reveal_input_channel) = mpsc::channel();
+    let (reveal_output_channel, reveal_input_channel_receiver) = mpsc::channel();
+
+    let reveal_listen_relay = TransportListenRelay::new(
+        reveal_input_channel_sender.clone(),
+        is_completed.clone(),
+        peers_tags.clone(),
+        transport.clone(),
+    );
+    let mut reveal_broadcast_relay = TransportBroadcastRelay::new(
+        own_tag.clone(),
+        reveal_input_channel_receiver,
+        transport.clone(),
+    );
+
+    let reveal_listen_relay_handle = thread::spawn(move || reveal_listen_relay.listen());
+    let reveal_broadcast_relay_handle = thread::spawn(move || reveal_broadcast_relay.broadcast());
+
+    let (shard_input_channel_sender, shard_input_channel) = mpsc::channel();
+    let (shard_output_channel, shard_input_channel_receiver) = mpsc::channel();
+
+    let shard_listen_relay = TransportListenRelay::new(
+        shard_input_channel_sender.clone(),
+        is_completed.clone(),
+        peers_tags.clone(),
+        transport.clone(),
+    );
+    let mut shard_broadcast_relay = TransportBroadcastRelay::new(
+        own_tag.clone(),
+        shard_input_channel_receiver,
+        transport.clone(),
+    );
+
+    let shard_listen_relay_handle = thread::spawn(move || shard_listen_relay.listen());
+    let shard_broadcast_relay_handle = thread::spawn(move || shard_broadcast_relay.broadcast());
+
+    // the sync server answers the peers' requests and forwards their answers to the node
+    let (sync_server_sender, sync_server_channel) = mpsc::channel();
+    let (sync_input_channel_sender, sync_input_channel) = mpsc::channel();
+    let (sync_output_channel, sync_input_channel_receiver) = mpsc::channel();
+
+    let sync_listen_relay = TransportListenRelay::new(
+        sync_server_sender,
+        is_completed.clone(),
+        peers_tags,
+        transport.clone(),
+    );
+    let mut sync_broadcast_relay =
+        TransportBroadcastRelay::new(own_tag, sync_input_channel_receiver, transport.clone());
+
+    let sync_listen_relay_handle = thread::spawn(move || sync_listen_relay.listen());
+    let sync_broadcast_relay_handle = thread::spawn(move || sync_broadcast_relay.broadcast());
+
+    // the server is not joined, it answers the peers for the whole life of the node
+    let sync_committees = SyncCommittees::new();
+    if let Some(storage) = &storage {
+        let repair_schedule = (config.repair_interval > 0)
+            .then(|| EpochSchedule::new(ScheduledTask::Repair, config.repair_interval));
+        let mut server = SyncServer::new(
+            storage.clone(),
+            keypair.clone(),
+            sync_committees.clone(),
+            repair_schedule,
+        );
+        let server_output = sync_output_channel.clone();
+        let server_client = sync_input_channel_sender.clone();
+        thread::spawn(move || server.serve(sync_server_channel, server_output, server_client));
+    }
+
     // get node's id in the committee
     all_dids.sort();
     let mut id = 0;
@@ -184,11 +398,24 @@ pub fn run_node(args: NodeArgs) -> Result<()> {
         dkg_output_channel,
         sign_output_channel,
         sign_input_channel_sender,
+        oracle_input_channel,
+        oracle_output_channel,
+        oracle_input_channel_sender,
+        reveal_input_channel,
+        reveal_output_channel,
+        reveal_input_channel_sender,
+        shard_input_channel,
+        shard_output_channel,
+        shard_input_channel_sender,
+        sync_input_channel,
+        sync_output_channel,
+        sync_input_channel_sender,
     };
 
     let network_params = NodeNetworkParams {
-        node_url: args.node_url,
-        faucet_url: args.faucet_url,
+        transport,
+        registry,
+        http_secrets,
     };
 
     peers_dids.sort();
@@ -196,13 +423,18 @@ pub fn run_node(args: NodeArgs) -> Result<()> {
         own_did_url: did_url,
         did_urls: peers_dids,
         num_participants: all_dids.len(),
-        time_resolution: args.time_resolution,
-        signature_sleep_time: args.signature_sleep_time,
+        time_resolution,
+        signature_sleep_time,
+        config,
+        sync,
     };
 
-    let save_data = SaveData::load_or_create();
-    let node =
-        Node::new(keypair, channels, network_params, protocol_params, id).with_save_data(save_data);
+    let mut node = Node::new(keypair, channels, network_params, protocol_params, id)
+        .with_save_data(save_data)
+        .with_sync_committees(sync_committees);
+    if let Some(gateway) = gateway {
+        node = node.with_gateway(gateway);
+    }
 
     node.run(storage)?;
 
@@ -212,6 +444,14 @@ pub fn run_node(args: NodeArgs) -> Result<()> {
     sign_broadcast_relay_handle.join().unwrap()?;
     dkg_listen_relay_handle.join().unwrap()?;
     sign_listen_relay_handle.join().unwrap()?;
+    oracle_broadcast_relay_handle.join().unwrap()?;
+    oracle_listen_relay_handle.join().unwrap()?;
+    reveal_broadcast_relay_handle.join().unwrap()?;
+    reveal_listen_relay_handle.join().unwrap()?;
+    shard_broadcast_relay_handle.join().unwrap()?;
+    shard_listen_relay_handle.join().unwrap()?;
+    sync_broadcast_relay_handle.join().unwrap()?;
+    sync_listen_relay_handle.join().unwrap()?;
 
     Ok(())
 }
@@ -247,7 +487,7 @@ fn get_keypair(
 
 fn get_did(
     keypair: &Pair<kyber_rs::group::edwards25519::Point>,
-    node_url: &str,
+    registry: &dyn DidRegistry,
     save_data: &mut SaveData,
 ) -> Result<String, anyhow::Error> {
     let eddsa = EdDSA::from(keypair.clone());
@@ -262,11 +502,10 @@ fn get_did(
         _ => {
             log::info!("creating node's DID document",);
             let mut document =
-                new_document(&eddsa.public.marshal_binary()?, None, None, node_url, false)?;
-            document.sign(keypair.clone(), &keypair.public, node_url)?;
+                registry.new_document(&eddsa.public.marshal_binary()?, None, None, false)?;
+            registry.sign_document(&mut document, &mut keypair.clone(), &keypair.public)?;
 
-            document.publish(node_url)?;
-            let did = document.did();
+            let did = registry.publish_document(&mut document)?;
             log::info!("node's DID document has been published");
 
             if let Some(ref mut node_state) = save_data.node_state {
@@ -282,21 +521,22 @@ fn get_did(
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-struct DkgInit {
-    nodes: Vec<String>,
+pub(crate) struct DkgInit {
+    pub nodes: Vec<String>,
+    #[serde(default)]
+    pub config: CommitteeConfig,
 }
 
 fn listen_governor_instructions(
     governor_tag: String,
     own_did: String,
-    node_url: String,
-) -> Result<Vec<String>> {
-    let mut init_listener = Listener::new(&node_url)?;
+    transport: &dyn Transport,
+) -> Result<DkgInit> {
     log::info!(
         "listening for instructions on governor tag: {}",
         governor_tag
     );
-    let receiver = tokio::runtime::Runtime::new()?.block_on(init_listener.start(governor_tag))?;
+    let receiver = transport.subscribe(governor_tag)?;
     loop {
         if let Some(data) = receiver.iter().next() {
             let mut deserializer = serde_json::Deserializer::from_slice(&data.0);
@@ -307,69 +547,10 @@ fn listen_governor_instructions(
                             "requested DKG from governor, committe's nodes: {:?}",
                             message.nodes
                         );
-                        return Ok(message.nodes);
+                        return Ok(message);
                     }
                 }
             }
         }
     }
 }
-
-/// Requests funds from the faucet for the given `address`.
-pub async fn request_faucet_funds(
-    client: &Client,
-    address: Address,
-    faucet_endpoint: &str,
-) -> anyhow::Result<()> {
-    let address_bech32 = address.to_bech32(client.get_bech32_hrp().await?);
-
-    iota_client::request_funds_from_faucet(faucet_endpoint, &address_bech32).await?;
-
-    tokio::time::timeout(std::time::Duration::from_secs(300), async {
-        loop {
-            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-
-            let balance = get_address_balance(client, &address)
-                .await
-                .context("failed to get address balance")?;
-            if balance > 0 {
-                break;
-            }
-        }
-        Ok::<(), anyhow::Error>(())
-    })
-    .await
-    .context("maximum timeout exceeded")??;
-
-    Ok(())
-}
-
-/// Returns the balance of the given Bech32-encoded `address`.
-pub async fn get_address_balance(client: &Client, address: &Address) -> anyhow::Result<u64> {
-    let address_bech32 = address.to_bech32(client.get_bech32_hrp().await?);
-    let output_ids = client
-        .basic_output_ids(vec![
-            QueryParameter::Address(address_bech32.to_owned()),
-            QueryParameter::HasExpiration(false),
-            QueryParameter::HasTimelock(false),
-            QueryParameter::HasStorageDepositReturn(false),
-        ])
-        .await?;
-
-    let outputs_responses = client.get_outputs(output_ids).await?;
-
-    let mut total_amount = 0;
-    for output_response in outputs_responses {
-        let output =
-            Output::try_from_dto(&output_response.output, client.get_token_supply().await?)?;
-        total_amount += output.amount();
-    }
-
-    Ok(total_amount)
-}
-
-/// Get an address
-pub fn get_address(public_key: &[u8]) -> Address {
-    Address::Ed25519(Ed25519Address::new(Blake2b256::digest(public_key).into()))
-    // Hash the public key to get the address.
-}
@@ -0,0 +1,785 @@
+use std::{
+    collections::BTreeMap,
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener},
+    sync::{mpsc::Receiver, Arc},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use identity_iota::core::ToJson;
+use kyber_rs::{
+    encoding::BinaryMarshaler,
+    group::edwards25519::{Point, Scalar, SuiteEd25519},
+    util::key::{new_key_pair, Pair},
+};
+use reqwest::header::CONTENT_TYPE;
+use serde::de::DeserializeOwned;
+use url::Url;
+
+use crate::{
+    api::requests::{
+        audit_tag, deletions_tag,
+        messages::{
+            challenge_response, content_key, unix_time, Aggregation, ChallengeResponse,
+            CommitteeLog, DigestAlgorithm, Execution, FailureReason, InputUri, Operation,
+            OracleOptions, OracleResult, OutputUri, ResponseState, StorageLocalUri, StorageUri,
+            MIN_NONCE_SIZE, REQUEST_VERSION,
+        },
+        sync_tag, AuditLog, DeletionLog, GenericRequest, HttpSecrets, Quotas, SealedData,
+        SyncReport,
+    },
+    demo::{
+        run::{start_node, DkgInit, NodeSetup},
+        CommitteeConfig, SaveData,
+    },
+    did::{did_tag, DidRegistry, LocalRegistry},
+    dlt::{memory::MemoryTransport, ChunkedTransport, Transport},
+    store::{new_storage, ObjectMetadata, S3Options, StorageKey},
+};
+
+const SIMULATION_GOVERNOR: &str = "dora-governor-simulation";
+const POLLING_INTERVAL: Duration = Duration::from_millis(100);
+const SIMULATION_CHUNK_SIZE: usize = 1024;
+/// Time [s] between two audits, garbage collections and repairs of the simulated committee.
+const SIMULATION_TASK_INTERVAL: u64 = 10;
+const SIMULATION_MAX_OBJECT_SIZE: u64 = 1024;
+/// Body of every response of the simulation's HTTP server.
+const SIMULATION_HTTP_BODY: &str = r#"{"price": 2.5}"#;
+
+#[derive(Parser)]
+#[command(about = "run a whole committee inside this process", long_about = None)]
+pub struct SimulateArgs {
+    /// number of nodes in the committee
+    #[arg(long, default_value = "3")]
+    nodes: usize,
+
+    #[arg(long = "signature-sleep-time", default_value = "2")]
+    signature_sleep_time: u64,
+
+    /// maximum time [s] to wait for each step of the simulation
+    #[arg(long, default_value = "120")]
+    timeout: u64,
+
+    /// only store, head and get an object once the committee is created, skipping the rest of
+    /// the scenario and the periodic tasks
+    #[arg(long)]
+    smoke: bool,
+}
+
+/// Runs a committee of nodes over an in-memory transport, storage and DID registry. DKG and
+/// the committee's DID creation are driven through the governor tag, then a sequence of
+/// requests is sent to the committee and every resulting log is verified against the
+/// distributed key. The last node syncs its storage when it joins, then loses an object that it
+/// syncs back from its peers, the first one loses the content of an object that its next repair
+/// restores, and the logs of the committee's audits and garbage collections are verified too.
+/// A last request goes through the first node's HTTP gateway. A smoke simulation stops after
+/// its few requests.
+pub fn run_simulation(args: SimulateArgs) -> Result<()> {
+    let memory = MemoryTransport::new();
+    // a small chunk size makes most messages go through chunking
+    let transport =
+        Arc::new(ChunkedTransport::new(memory.clone()).with_chunk_size(SIMULATION_CHUNK_SIZE));
+    let registry = LocalRegistry::new();
+    let timeout = Duration::from_secs(args.timeout);
+    let suite = SuiteEd25519::new_blake3_sha256_ed25519();
+    let source = serve_http(SIMULATION_HTTP_BODY)?;
+    // free port for the first node's gateway
+    let gateway_port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+
+    let mut nodes = Vec::with_capacity(args.nodes);
+    let mut dids = Vec::with_capacity(args.nodes);
+    let mut keys = Vec::with_capacity(args.nodes);
+    let mut storages = Vec::with_capacity(args.nodes);
+    for i in 0..args.nodes {
+        let mut keypair = new_key_pair(&suite)?;
+        let mut document =
+            registry.new_document(&keypair.public.marshal_binary()?, None, None, false)?;
+        let public_key = keypair.public;
+        registry.sign_document(&mut document, &mut keypair, &public_key)?;
+        let did_url = registry.publish_document(&mut document)?;
+        log::info!("simulated node's DID is: {}", did_url);
+
+        let storage = new_storage("memory", None, S3Options::default())?
+            .with_encryption(StorageKey::derive(&keypair.private.marshal_binary()?));
+        storages.push(storage.clone());
+        let setup = NodeSetup {
+            keypair,
+            did_url: did_url.clone(),
+            governor: SIMULATION_GOVERNOR.to_owned(),
+            storage: Some(storage),
+            transport: transport.clone(),
+            registry: Arc::new(registry.clone()),
+            save_data: SaveData::volatile(),
+            time_resolution: 20,
+            signature_sleep_time: args.signature_sleep_time,
+            sync: i == args.nodes - 1,
+            http_port: (i == 0).then_some(gateway_port),
+            http_address: "127.0.0.1".to_owned(),
+            http_secrets: HttpSecrets::default(),
+        };
+        nodes.push(thread::spawn(move || start_node(setup)));
+        dids.push(did_url);
+        keys.push(public_key);
+    }
+
+    wait_for(&mut nodes, timeout, || {
+        Ok((memory.subscribers(SIMULATION_GOVERNOR) == args.nodes).then_some(()))
+    })?;
+    log::info!("requesting DKG from governor");
+    transport.publish(
+        &serde_json::to_vec(&DkgInit {
+            nodes: dids.clone(),
+            // ticks are queued with the requests, so the periodic tasks interleave with the
+            // scenario without changing its results
+            config: CommitteeConfig {
+                audit_interval: SIMULATION_TASK_INTERVAL,
+                gc_interval: SIMULATION_TASK_INTERVAL,
+                repair_interval: SIMULATION_TASK_INTERVAL,
+                quotas: Quotas {
+                    max_object_size: Some(SIMULATION_MAX_OBJECT_SIZE),
+                    ..Default::default()
+                },
+            },
+        })?,
+        Some(SIMULATION_GOVERNOR.to_owned()),
+    )?;
+
+    let committee_did = wait_for(&mut nodes, timeout, || Ok(registry.committee(&dids)))?;
+    let committee_tag = did_tag(&committee_did)
+        .ok_or_else(|| anyhow!("not a valid committee DID: {committee_did}"))?
+        .to_owned();
+    let committee_key = registry.resolve_document(&committee_did)?.public_key()?;
+    log::info!("simulated committee's DID is: {}", committee_did);
+
+    let logs = transport.subscribe(committee_tag.clone())?;
+    let audits = transport.subscribe(audit_tag(&committee_tag))?;
+    let deletions = transport.subscribe(deletions_tag(&committee_tag))?;
+    let reports = transport.subscribe(sync_tag(&committee_tag))?;
+    let syncs = transport.subscribe(sync_tag(&committee_tag))?;
+    wait_for(&mut nodes, timeout, || {
+        Ok((memory.subscribers(&committee_tag) == args.nodes + 1).then_some(()))
+    })?;
+
+    let source = Url::parse(&format!("http://{source}/price"))?;
+    let requester = new_key_pair(&suite)?;
+    // block ids of the store requests of the objects' latest versions, by key
+    let mut stores = BTreeMap::new();
+    let requests = match args.smoke {
+        true => smoke_scenario(),
+        false => scenario(&committee_key, &requester, &source)?,
+    };
+    for (mut request, expected) in requests {
+        let key = match (&request.operation, &request.storage_uri, &expected) {
+            (_, _, Expected::Failure(_)) => None,
+            (Some(Operation::StoreContent { .. }), _, Expected::Data(Some(key))) => {
+                Some(key.clone())
+            }
+            (_, StorageUri::Storage(StorageLocalUri(key)), _) => Some(key.clone()),
+            _ => None,
+        };
+        // signed deletes name the store request of the latest version they delete
+        if request.operation == Some(Operation::Delete) && request.requester.is_some() {
+            request.object_request_id = key.as_ref().and_then(|key| stores.get(key).cloned());
+            request.sign_as_requester(&requester)?;
+        }
+        let stored = matches!(
+            request.operation,
+            Some(Operation::Store | Operation::StoreContent { .. } | Operation::StoreShards)
+        );
+        let request_id =
+            transport.publish(&serde_json::to_vec(&request)?, Some(committee_tag.clone()))?;
+        log::info!("request sent to the committee (id: {})", request_id);
+
+        let log = wait_for(&mut nodes, timeout, || {
+            next_committee_log(&logs, &request_id)
+        })?;
+        verify_committee_log(&log, &committee_key, expected)?;
+        log::info!("committee's log for request {} verified", request_id);
+        if let Some(key) = key.filter(|_| stored) {
+            stores.insert(key, request_id);
+        }
+    }
+    if args.smoke {
+        log::info!("smoke simulation completed");
+        return Ok(());
+    }
+
+    // the first node loses the content of an object, which its next repair fetches from its
+    // peers
+    let storage = storages[0].for_committee(&committee_did);
+    let latest = storage
+        .metadata("repaired")?
+        .ok_or_else(|| anyhow!("repaired object is not stored"))?;
+    storage.repair_version(b"lost data", &latest)?;
+    // the last node loses an object along with its metadata, as a node replacing a member with
+    // a new storage, and its next repair syncs it from its peers
+    let last = args.nodes - 1;
+    storages[last]
+        .for_committee(&committee_did)
+        .delete("retained")?;
+
+    let audit_log = wait_for(&mut nodes, timeout, || {
+        next_matching(&audits, |_: &AuditLog| true)
+    })?;
+    audit_log.verify(&committee_key)?;
+    log::info!("committee's audit log verified");
+
+    let deletion_log = wait_for(&mut nodes, timeout, || {
+        next_matching(&deletions, |log: &DeletionLog| {
+            log.record.deleted.iter().any(|key| key == "ephemeral")
+        })
+    })?;
+    deletion_log.verify(&committee_key)?;
+    log::info!("committee's deletion log verified");
+
+    // the sync of the last node is not awaited, its peers may not serve the committee yet when
+    // it joins
+    let report = wait_for(&mut nodes, timeout, || {
+        next_matching(&reports, |report: &SyncReport| {
+            report.node_did() == dids[0]
+                && report
+                    .repaired()
+                    .iter()
+                    .any(|version| version.key == "repaired")
+        })
+    })?;
+    report.verify(&keys[0])?;
+    log::info!("node's repair report verified");
+
+    let report = wait_for(&mut nodes, timeout, || {
+        next_matching(&syncs, |report: &SyncReport| {
+            report.node_did() == dids[last]
+                && report
+                    .synced()
+                    .iter()
+                    .any(|version| version.key == "retained")
+        })
+    })?;
+    report.verify(&keys[last])?;
+    let synced = storages[last]
+        .for_committee(&committee_did)
+        .get("retained")?;
+    if synced != b"retained data" {
+        bail!("the synced object does not match the stored one");
+    }
+    log::info!("node's sync report verified");
+
+    let gateway = format!("http://127.0.0.1:{gateway_port}");
+    let request_id = post_request(
+        &gateway,
+        &generic_request(
+            InputUri::None,
+            Operation::Exists,
+            StorageUri::Storage(StorageLocalUri("ephemeral".to_owned())),
+        ),
+    )?;
+    log::info!("request sent through the gateway (id: {})", request_id);
+    let log = wait_for(&mut nodes, timeout, || {
+        next_committee_log(&logs, &request_id)
+    })?;
+    verify_committee_log(
+        &log,
+        &committee_key,
+        Expected::Data(Some("false".to_owned())),
+    )?;
+    wait_for(&mut nodes, timeout, || {
+        Ok((request_status(&gateway, &request_id)? == "done").then_some(()))
+    })?;
+    log::info!("committee's log for request {} verified", request_id);
+
+    log::info!("simulation completed");
+    Ok(())
+}
+
+/// Data expected in the log of a request.
+enum Expected {
+    Data(Option<String>),
+    /// data sealed to the public key of the given private key
+    Sealed(Scalar, String),
+    /// metadata of the given version of the object at the key, with an expiration time if
+    /// `expires`
+    Metadata {
+        key: String,
+        version: u64,
+        data: String,
+        expires: bool,
+    },
+    /// value aggregated from the observations of the committee
+    Oracle(f64),
+    Failure(FailureReason),
+}
+
+fn generic_request(
+    input_uri: InputUri,
+    operation: Operation,
+    storage_uri: StorageUri,
+) -> GenericRequest {
+    GenericRequest {
+        version: REQUEST_VERSION,
+        operation: Some(operation),
+        input_uri,
+        output_uri: OutputUri::None,
+        execution: Execution::None,
+        signature: false,
+        storage_uri,
+        oracle: None,
+        json_path: None,
+        http: None,
+        data_encoding: None,
+        content_type: None,
+        ttl: None,
+        requester: None,
+        requester_signature: None,
+        timestamp: Some(unix_time()),
+        object_request_id: None,
+    }
+}
+
+/// Requests of the smoke simulation, along with the data expected in their logs.
+fn smoke_scenario() -> Vec<(GenericRequest, Expected)> {
+    let stored = || StorageUri::Storage(StorageLocalUri("smoke".to_owned()));
+    vec![
+        (
+            generic_request(
+                InputUri::Literal("smoke data".to_owned()),
+                Operation::Store,
+                stored(),
+            ),
+            Expected::Data(None),
+        ),
+        (
+            generic_request(InputUri::None, Operation::Head, stored()),
+            Expected::Metadata {
+                key: "smoke".to_owned(),
+                version: 1,
+                data: "smoke data".to_owned(),
+                expires: false,
+            },
+        ),
+        (
+            generic_request(
+                InputUri::Local(StorageLocalUri("smoke".to_owned())),
+                Operation::Get,
+                StorageUri::None,
+            ),
+            Expected::Data(Some("smoke data".to_owned())),
+        ),
+    ]
+}
+
+/// Requests sent to the committee, along with the data expected in their logs. `source` is
+/// the url of the simulation's HTTP server, and the requests of `requester` are signed.
+fn scenario(
+    committee_key: &Point,
+    requester: &Pair<Point>,
+    source: &Url,
+) -> Result<Vec<(GenericRequest, Expected)>> {
+    let stored = |key: &str| StorageUri::Storage(StorageLocalUri(key.to_owned()));
+    let list = || Operation::List {
+        prefix: "simul".to_owned(),
+    };
+    let mut select = generic_request(
+        InputUri::Literal(r#"{"b": [1, 2], "a": {"y": 2, "x": 1}}"#.to_owned()),
+        Operation::Get,
+        StorageUri::None,
+    );
+    select.json_path = Some("a".to_owned());
+    let algorithm = DigestAlgorithm::Blake2b256;
+    let content = content_key(algorithm, &algorithm.digest(b"simulated data"));
+    let nonce = [7u8; MIN_NONCE_SIZE];
+    let challenge = ChallengeResponse {
+        key: "simulation".to_owned(),
+        version: None,
+        nonce: hex::encode(nonce),
+        response: challenge_response(&nonce, b"simulated data"),
+    };
+    let signed = |mut request: GenericRequest| -> Result<GenericRequest> {
+        request.sign_as_requester(requester)?;
+        Ok(request)
+    };
+    let sealed =
+        SealedData::seal_for(committee_key, Some(&requester.public), b"confidential data")?;
+    let mut reveal = generic_request(InputUri::None, Operation::Reveal, stored("sealed"));
+    reveal.sign_as_requester(requester)?;
+    let mut retained = generic_request(
+        InputUri::Literal("retained data".to_owned()),
+        Operation::Store,
+        stored("retained"),
+    );
+    retained.ttl = Some(3600);
+    // expired before the next garbage collection
+    let mut ephemeral = generic_request(
+        InputUri::Literal("ephemeral data".to_owned()),
+        Operation::Store,
+        stored("ephemeral"),
+    );
+    ephemeral.ttl = Some(1);
+    let mut oracle = generic_request(
+        InputUri::Url(source.clone()),
+        Operation::Oracle,
+        StorageUri::None,
+    );
+    oracle.oracle = Some(OracleOptions {
+        pointer: "/price".to_owned(),
+        aggregation: Aggregation::Median,
+    });
+    Ok(vec![
+        (
+            generic_request(
+                InputUri::Literal("simulated data".to_owned()),
+                Operation::Store,
+                stored("simulation"),
+            ),
+            Expected::Data(None),
+        ),
+        (
+            generic_request(
+                InputUri::Local(StorageLocalUri("simulation".to_owned())),
+                Operation::Get,
+                StorageUri::None,
+            ),
+            Expected::Data(Some("simulated data".to_owned())),
+        ),
+        // a store request of the clients predating operations and timestamps
+        (
+            serde_json::from_value(serde_json::json!({
+                "input_uri": "literal:string:legacy data",
+                "storage_uri": "storage:local:legacy",
+            }))?,
+            Expected::Data(None),
+        ),
+        (
+            generic_request(
+                InputUri::Local(StorageLocalUri("legacy".to_owned())),
+                Operation::Get,
+                StorageUri::None,
+            ),
+            Expected::Data(Some("legacy data".to_owned())),
+        ),
+        (
+            generic_request(
+                InputUri::Literal("literal".to_owned()),
+                Operation::Get,
+                StorageUri::None,
+            ),
+            Expected::Data(Some("literal".to_owned())),
+        ),
+        (select, Expected::Data(Some(r#"{"x":1,"y":2}"#.to_owned()))),
+        (
+            generic_request(InputUri::None, Operation::Exists, stored("simulation")),
+            Expected::Data(Some("true".to_owned())),
+        ),
+        (
+            generic_request(
+                InputUri::None,
+                Operation::Challenge {
+                    nonce: challenge.nonce.clone(),
+                },
+                stored("simulation"),
+            ),
+            Expected::Data(Some(String::from_utf8(challenge.to_jcs()?)?)),
+        ),
+        (
+            signed(generic_request(
+                InputUri::Literal("updated data".to_owned()),
+                Operation::Store,
+                stored("simulation"),
+            ))?,
+            Expected::Data(None),
+        ),
+        (
+            generic_request(
+                InputUri::None,
+                Operation::GetVersion { version: 1 },
+                stored("simulation"),
+            ),
+            Expected::Data(Some("simulated data".to_owned())),
+        ),
+        (
+            generic_request(InputUri::None, Operation::Head, stored("simulation")),
+            Expected::Metadata {
+                key: "simulation".to_owned(),
+                version: 2,
+                data: "updated data".to_owned(),
+                expires: false,
+            },
+        ),
+        (
+            generic_request(InputUri::None, list(), StorageUri::None),
+            Expected::Data(Some("[\"simulation\"]".to_owned())),
+        ),
+        (
+            generic_request(InputUri::None, Operation::Delete, stored("simulation")),
+            Expected::Failure(FailureReason::Unauthorized),
+        ),
+        (
+            signed(generic_request(
+                InputUri::None,
+                Operation::Delete,
+                stored("simulation"),
+            ))?,
+            Expected::Data(None),
+        ),
+        (
+            generic_request(InputUri::None, list(), StorageUri::None),
+            Expected::Data(Some("[]".to_owned())),
+        ),
+        (
+            signed(generic_request(
+                InputUri::Literal("simulated data".to_owned()),
+                Operation::StoreContent { algorithm },
+                StorageUri::None,
+            ))?,
+            Expected::Data(Some(content.clone())),
+        ),
+        (
+            signed(generic_request(
+                InputUri::None,
+                Operation::Delete,
+                StorageUri::Storage(StorageLocalUri(content)),
+            ))?,
+            Expected::Data(None),
+        ),
+        (
+            generic_request(
+                InputUri::Literal(serde_json::to_string(&sealed)?),
+                Operation::Store,
+                stored("sealed"),
+            ),
+            Expected::Data(None),
+        ),
+        (
+            reveal,
+            Expected::Sealed(requester.private, "confidential data".to_owned()),
+        ),
+        (
+            generic_request(
+                InputUri::Literal("erasure-coded data".to_owned()),
+                Operation::StoreShards,
+                stored("sharded"),
+            ),
+            Expected::Data(None),
+        ),
+        (
+            generic_request(InputUri::None, Operation::GetShards, stored("sharded")),
+            Expected::Data(Some("erasure-coded data".to_owned())),
+        ),
+        (retained, Expected::Data(None)),
+        (
+            generic_request(InputUri::None, Operation::Head, stored("retained")),
+            Expected::Metadata {
+                key: "retained".to_owned(),
+                version: 1,
+                data: "retained data".to_owned(),
+                expires: true,
+            },
+        ),
+        (ephemeral, Expected::Data(None)),
+        (
+            generic_request(
+                InputUri::Literal("repaired data".to_owned()),
+                Operation::Store,
+                stored("repaired"),
+            ),
+            Expected::Data(None),
+        ),
+        (
+            generic_request(
+                InputUri::Literal("x".repeat(SIMULATION_MAX_OBJECT_SIZE as usize + 1)),
+                Operation::Store,
+                stored("oversized"),
+            ),
+            Expected::Failure(FailureReason::ObjectTooLarge),
+        ),
+        (
+            generic_request(
+                InputUri::Url(source.clone()),
+                Operation::Get,
+                StorageUri::None,
+            ),
+            Expected::Data(Some(SIMULATION_HTTP_BODY.to_owned())),
+        ),
+        (oracle, Expected::Oracle(2.5)),
+    ])
+}
+
+fn verify_committee_log(
+    log: &CommitteeLog,
+    committee_key: &Point,
+    expected: Expected,
+) -> Result<()> {
+    log.verify(committee_key)?;
+    if let Expected::Failure(reason) = &expected {
+        if log.error.as_ref() != Some(reason) {
+            bail!(
+                "request {} did not fail with {}: {:?}",
+                log.request_id.0,
+                reason,
+                log.error
+            );
+        }
+        return Ok(());
+    }
+    if !matches!(log.result, ResponseState::Success) {
+        bail!("request {} failed: {:?}", log.request_id.0, log.error);
+    }
+    let data = log.data.as_deref().unwrap_or_default();
+    let matches = match expected {
+        Expected::Data(expected_data) => log.data == expected_data,
+        Expected::Sealed(private_key, expected_data) => {
+            let sealed: SealedData = serde_json::from_str(data)?;
+            sealed.open_with_key(&private_key)? == expected_data.as_bytes()
+        }
+        Expected::Metadata {
+            key,
+            version,
+            data: expected_data,
+            expires,
+        } => {
+            let metadata: ObjectMetadata = serde_json::from_str(data)?;
+            metadata.key == key
+                && metadata.version == version
+                && metadata.digest == DigestAlgorithm::Blake2b256.digest(expected_data.as_bytes())
+                && metadata.size == expected_data.len() as u64
+                && metadata.expires_at.is_some() == expires
+        }
+        Expected::Oracle(value) => {
+            let result: OracleResult = serde_json::from_str(data)?;
+            result.value == value && !result.contributors.is_empty()
+        }
+        Expected::Failure(_) => unreachable!("failures are checked first"),
+    };
+    if !matches {
+        bail!(
+            "unexpected data for request {}: {:?}",
+            log.request_id.0,
+            log.data
+        );
+    }
+    Ok(())
+}
+
+fn next_committee_log(
+    logs: &Receiver<(Vec<u8>, String)>,
+    request_id: &str,
+) -> Result<Option<CommitteeLog>> {
+    next_matching(logs, |log: &CommitteeLog| log.request_id.0 == request_id)
+}
+
+/// First payload received as a `T` accepted by `f`, the other payloads are discarded.
+fn next_matching<T: DeserializeOwned>(
+    receiver: &Receiver<(Vec<u8>, String)>,
+    f: impl Fn(&T) -> bool,
+) -> Result<Option<T>> {
+    Ok(receiver
+        .try_iter()
+        .filter_map(|(data, _)| serde_json::from_slice::<T>(&data).ok())
+        .find(|value| f(value)))
+}
+
+/// Serves `body` to every HTTP request, so that url inputs don't need network access.
+fn serve_http(body: &'static str) -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?;
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            // requests have no body, their headers end with an empty line
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                match stream.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buffer[..n]),
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            if let Err(e) = stream.write_all(response.as_bytes()) {
+                log::warn!("simulated HTTP server could not respond: {}", e);
+            }
+        }
+    });
+    Ok(address)
+}
+
+/// Publishes the request through a node's HTTP gateway, returning its id.
+fn post_request(gateway: &str, request: &GenericRequest) -> Result<String> {
+    let response = reqwest::blocking::Client::new()
+        .post(format!("{gateway}/requests"))
+        .header(CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(request)?)
+        .send()?
+        .error_for_status()?;
+    let response: serde_json::Value = serde_json::from_slice(&response.bytes()?)?;
+    match response["id"].as_str() {
+        Some(id) => Ok(id.to_owned()),
+        None => bail!("the gateway did not return the request's id"),
+    }
+}
+
+/// Status of the request reported by a node's HTTP gateway, e.g. `done`.
+fn request_status(gateway: &str, request_id: &str) -> Result<String> {
+    let response =
+        reqwest::blocking::get(format!("{gateway}/requests/{request_id}"))?.error_for_status()?;
+    let response: serde_json::Value = serde_json::from_slice(&response.bytes()?)?;
+    Ok(response["status"].as_str().unwrap_or_default().to_owned())
+}
+
+/// Polls `f` until it returns a value, failing if any node stops or the timeout expires.
+fn wait_for<T>(
+    nodes: &mut Vec<JoinHandle<Result<()>>>,
+    timeout: Duration,
+    mut f: impl FnMut() -> Result<Option<T>>,
+) -> Result<T> {
+    let start = Instant::now();
+    loop {
+        if let Some(value) = f()? {
+            return Ok(value);
+        }
+        if let Some(i) = nodes.iter().position(|node| node.is_finished()) {
+            match nodes.remove(i).join() {
+                Ok(Err(e)) => return Err(e.context("simulated node failed")),
+                _ => bail!("simulated node stopped"),
+            }
+        }
+        if start.elapsed() > timeout {
+            bail!("simulation timed out");
+        }
+        thread::sleep(POLLING_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulated_committee_stores_and_gets_an_object() {
+        run_simulation(SimulateArgs {
+            nodes: 2,
+            signature_sleep_time: 1,
+            timeout: 30,
+            smoke: true,
+        })
+        .unwrap();
+    }
+
+    #[test]
+    #[ignore = "runs a whole committee for tens of seconds"]
+    fn simulated_committee_runs_the_scenario() {
+        run_simulation(SimulateArgs {
+            nodes: 3,
+            signature_sleep_time: 1,
+            timeout: 60,
+            smoke: false,
+        })
+        .unwrap();
+    }
+}
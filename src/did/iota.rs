@@ -0,0 +1,252 @@
+use anyhow::{bail, Context, Result};
+use identity_iota::{iota::NetworkName, prelude::IotaDID};
+use iota_client::{
+    block::{
+        address::{Address, Ed25519Address},
+        output::{AliasId, Output},
+    },
+    crypto::hashes::{blake2b::Blake2b256, Digest},
+    node_api::indexer::query_parameters::QueryParameter,
+    Client,
+};
+use kyber_rs::{encoding::BinaryMarshaler, group::edwards25519::Point};
+
+use crate::dlt::iota::{create_unsigned_did, publish_did, resolve_did, sign_did, Sign};
+
+use super::{DidRegistry, Document};
+
+const MINIMUM_BALANCE: u64 = 10000000;
+
+/// [DidRegistry] publishing DID documents as alias outputs on the Tangle.
+pub struct IotaRegistry {
+    node_url: String,
+    faucet_url: Option<String>,
+}
+
+impl IotaRegistry {
+    pub fn new(node_url: &str) -> Self {
+        Self {
+            node_url: node_url.to_owned(),
+            faucet_url: None,
+        }
+    }
+
+    pub fn with_faucet(self, faucet_url: &str) -> Self {
+        Self {
+            faucet_url: Some(faucet_url.to_owned()),
+            ..self
+        }
+    }
+}
+
+impl DidRegistry for IotaRegistry {
+    fn new_document(
+        &self,
+        public_key_bytes: &[u8],
+        time_resolution: Option<u32>,
+        committee_nodes_dids: Option<Vec<String>>,
+        committee: bool,
+    ) -> Result<Document> {
+        let client = Client::builder().with_node(&self.node_url)?.finish()?;
+        let (address, document, prepared_transaction_data) = create_unsigned_did(
+            public_key_bytes,
+            client,
+            time_resolution,
+            committee_nodes_dids,
+        )?;
+        let document = Document::IotaDocument {
+            address: Some(address),
+            document: Box::new(document),
+            document_transaction: Some(Box::new(prepared_transaction_data)),
+            document_payload: None,
+            committee,
+        };
+        Ok(document)
+    }
+
+    fn sign_document(
+        &self,
+        document: &mut Document,
+        signer: &mut dyn Sign,
+        public_key: &Point,
+    ) -> Result<()> {
+        match document {
+            Document::IotaDocument {
+                document_transaction,
+                document_payload,
+                ..
+            } => {
+                let prepared_data = match document_transaction {
+                    Some(d) => d,
+                    None => return Err(anyhow::Error::msg("No prepared transaction data")),
+                };
+                let r = tokio::runtime::Runtime::new()?;
+                let payload =
+                    r.block_on(sign_did(&self.node_url, prepared_data, signer, public_key))?;
+                *document_payload = Some(payload);
+            }
+            Document::LocalDocument { .. } => bail!("not an IOTA document"),
+        }
+        Ok(())
+    }
+
+    fn publish_document(&self, document: &mut Document) -> Result<String> {
+        match document {
+            Document::IotaDocument {
+                document,
+                document_payload,
+                ..
+            } => {
+                let payload = match document_payload {
+                    Some(p) => p,
+                    None => return Err(anyhow::Error::msg("No payload")),
+                };
+                **document = publish_did(payload.clone(), &self.node_url)?;
+                Ok(document.id().to_string())
+            }
+            Document::LocalDocument { .. } => bail!("not an IOTA document"),
+        }
+    }
+
+    fn resolve_document(&self, did: &str) -> Result<Document> {
+        let doc = resolve_did(did.to_owned(), &self.node_url)?;
+
+        Ok(Document::IotaDocument {
+            document: Box::new(doc),
+            document_transaction: None,
+            document_payload: None,
+            address: None,
+            committee: false,
+        })
+    }
+
+    fn find_document(&self, public_key: &Point) -> Result<Option<String>> {
+        let client = Client::builder().with_node(&self.node_url)?.finish()?;
+        let rt = tokio::runtime::Runtime::new()?;
+        let alias_ids = rt.block_on(find_alias_ids(
+            &client,
+            get_address(&public_key.marshal_binary()?),
+        ))?;
+        for id in alias_ids {
+            let did_candidate =
+                IotaDID::from_alias_id(&id.to_string(), &NetworkName::try_from("rms")?);
+            match self.resolve_document(&did_candidate.to_string()) {
+                Ok(doc) => {
+                    if doc.public_key()? == *public_key {
+                        return Ok(Some(did_candidate.to_string()));
+                    }
+                }
+                Err(_) => continue,
+            }
+        }
+        Ok(None)
+    }
+
+    fn fund(&self, public_key: &Point, requester: bool) -> Result<()> {
+        let client = Client::builder().with_node(&self.node_url)?.finish()?;
+        let rt = tokio::runtime::Runtime::new()?;
+
+        let address = get_address(&public_key.marshal_binary()?);
+        let address_str = address.to_bech32(rt.block_on(client.get_bech32_hrp())?);
+
+        let balance = rt.block_on(get_address_balance(&client, &address))?;
+        log::trace!("address {} balance is: {}", address_str, balance);
+        if balance >= MINIMUM_BALANCE {
+            return Ok(());
+        }
+
+        log::trace!("waiting for funds on address {}", address_str);
+        if requester {
+            let faucet_url = match &self.faucet_url {
+                Some(url) => url,
+                None => bail!("a faucet is needed to fund address {}", address_str),
+            };
+            rt.block_on(request_faucet_funds(&client, address, faucet_url))?
+        } else {
+            loop {
+                std::thread::sleep(std::time::Duration::from_secs(3));
+                let balance = rt.block_on(get_address_balance(&client, &address))?;
+                if balance >= MINIMUM_BALANCE {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn find_alias_ids(client: &Client, address: Address) -> Result<Vec<AliasId>> {
+    // Get outputs from node and select inputs
+    let mut alias_ids = Vec::new();
+
+    let alias_output_ids = client
+        .alias_output_ids(vec![QueryParameter::Governor(
+            address.to_bech32(client.get_bech32_hrp().await?),
+        )])
+        .await?;
+
+    for output_id in alias_output_ids {
+        alias_ids.push(AliasId::null().or_from_output_id(&output_id))
+    }
+
+    Ok(alias_ids)
+}
+
+/// Requests funds from the faucet for the given `address`.
+async fn request_faucet_funds(
+    client: &Client,
+    address: Address,
+    faucet_endpoint: &str,
+) -> anyhow::Result<()> {
+    let address_bech32 = address.to_bech32(client.get_bech32_hrp().await?);
+
+    iota_client::request_funds_from_faucet(faucet_endpoint, &address_bech32).await?;
+
+    tokio::time::timeout(std::time::Duration::from_secs(300), async {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+
+            let balance = get_address_balance(client, &address)
+                .await
+                .context("failed to get address balance")?;
+            if balance > 0 {
+                break;
+            }
+        }
+        Ok::<(), anyhow::Error>(())
+    })
+    .await
+    .context("maximum timeout exceeded")??;
+
+    Ok(())
+}
+
+/// Returns the balance of the given Bech32-encoded `address`.
+async fn get_address_balance(client: &Client, address: &Address) -> anyhow::Result<u64> {
+    let address_bech32 = address.to_bech32(client.get_bech32_hrp().await?);
+    let output_ids = client
+        .basic_output_ids(vec![
+            QueryParameter::Address(address_bech32.to_owned()),
+            QueryParameter::HasExpiration(false),
+            QueryParameter::HasTimelock(false),
+            QueryParameter::HasStorageDepositReturn(false),
+        ])
+        .await?;
+
+    let outputs_responses = client.get_outputs(output_ids).await?;
+
+    let mut total_amount = 0;
+    for output_response in outputs_responses {
+        let output =
+            Output::try_from_dto(&output_response.output, client.get_token_supply().await?)?;
+        total_amount += output.amount();
+    }
+
+    Ok(total_amount)
+}
+
+/// Get an address
+fn get_address(public_key: &[u8]) -> Address {
+    Address::Ed25519(Ed25519Address::new(Blake2b256::digest(public_key).into()))
+    // Hash the public key to get the address.
+}
@@ -0,0 +1,134 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{bail, Result};
+use identity_iota::core::ToJson;
+use iota_client::crypto::hashes::{blake2b::Blake2b256, Digest};
+use kyber_rs::{group::edwards25519::Point, sign::eddsa};
+
+use crate::dlt::iota::Sign;
+
+use super::{DidRegistry, Document};
+
+/// In-process [DidRegistry], DIDs are derived from the public key of their owner. Clones share
+/// the same documents, so it can serve a whole committee running inside a single process.
+#[derive(Clone, Default)]
+pub struct LocalRegistry(Arc<Mutex<HashMap<String, Document>>>);
+
+impl LocalRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the DID of the committee document listing exactly the given members.
+    pub fn committee(&self, members: &[String]) -> Option<String> {
+        let mut members = members.to_vec();
+        members.sort();
+        self.0
+            .lock()
+            .unwrap()
+            .values()
+            .find_map(|document| match document {
+                Document::LocalDocument {
+                    did,
+                    committee_members: Some(committee_members),
+                    ..
+                } if *committee_members == members => Some(did.to_owned()),
+                _ => None,
+            })
+    }
+}
+
+impl DidRegistry for LocalRegistry {
+    fn new_document(
+        &self,
+        public_key_bytes: &[u8],
+        _time_resolution: Option<u32>,
+        committee_nodes_dids: Option<Vec<String>>,
+        _committee: bool,
+    ) -> Result<Document> {
+        let committee_members = committee_nodes_dids.map(|mut urls| {
+            urls.sort();
+            urls
+        });
+        Ok(Document::LocalDocument {
+            did: format!(
+                "did:local:0x{}",
+                hex::encode(Blake2b256::digest(public_key_bytes))
+            ),
+            public_key: public_key_bytes.to_vec(),
+            committee_members,
+            signature_hex: None,
+        })
+    }
+
+    fn sign_document(
+        &self,
+        document: &mut Document,
+        signer: &mut dyn Sign,
+        _public_key: &Point,
+    ) -> Result<()> {
+        let signature = signer.sign(&signing_bytes(document)?)?;
+        match document {
+            Document::LocalDocument { signature_hex, .. } => {
+                *signature_hex = Some(hex::encode(signature))
+            }
+            Document::IotaDocument { .. } => bail!("not a local document"),
+        }
+        Ok(())
+    }
+
+    fn publish_document(&self, document: &mut Document) -> Result<String> {
+        let signature = match document {
+            Document::LocalDocument {
+                signature_hex: Some(signature_hex),
+                ..
+            } => hex::decode(signature_hex)?,
+            _ => bail!("document is not signed"),
+        };
+        eddsa::verify(
+            &document.public_key()?,
+            &signing_bytes(document)?,
+            &signature,
+        )
+        .map_err(|_| anyhow::Error::msg("document signature is not valid"))?;
+
+        let did = document.did();
+        self.0.lock().unwrap().insert(did.clone(), document.clone());
+        Ok(did)
+    }
+
+    fn resolve_document(&self, did: &str) -> Result<Document> {
+        match self.0.lock().unwrap().get(did) {
+            Some(document) => Ok(document.clone()),
+            None => bail!("could not resolve {did}"),
+        }
+    }
+
+    fn find_document(&self, public_key: &Point) -> Result<Option<String>> {
+        for document in self.0.lock().unwrap().values() {
+            if document.public_key()? == *public_key {
+                return Ok(Some(document.did()));
+            }
+        }
+        Ok(None)
+    }
+
+    fn fund(&self, _public_key: &Point, _requester: bool) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn signing_bytes(document: &Document) -> Result<Vec<u8>> {
+    match document {
+        Document::LocalDocument {
+            did,
+            public_key,
+            committee_members,
+            ..
+        } => Ok((did, hex::encode(public_key), committee_members).to_jcs()?),
+        Document::IotaDocument { .. } => bail!("not a local document"),
+    }
+}
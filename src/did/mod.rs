@@ -1,3 +1,16 @@
 mod document;
+mod iota;
+#[cfg(feature = "simulation")]
+mod local;
+mod registry;
 
-pub use document::{new_document, resolve_document, Document};
+pub use document::Document;
+pub use iota::IotaRegistry;
+#[cfg(feature = "simulation")]
+pub use local::LocalRegistry;
+pub use registry::DidRegistry;
+
+/// Tag of the payloads addressed to the owner of a DID, the hex digits of its last segment.
+pub fn did_tag(did: &str) -> Option<&str> {
+    did.rsplit(':').next()?.strip_prefix("0x")
+}
@@ -0,0 +1,40 @@
+use anyhow::Result;
+use kyber_rs::group::edwards25519::Point;
+
+use crate::dlt::iota::Sign;
+
+use super::Document;
+
+/// A [DidRegistry] is where nodes and committees publish their DID documents, and where
+/// anyone can resolve them to retrieve the public keys needed for verification.
+pub trait DidRegistry: Send + Sync {
+    /// Creates a new unsigned document for the given public key.
+    fn new_document(
+        &self,
+        public_key_bytes: &[u8],
+        time_resolution: Option<u32>,
+        committee_nodes_dids: Option<Vec<String>>,
+        committee: bool,
+    ) -> Result<Document>;
+
+    /// Signs the document on behalf of the owner of `public_key`.
+    fn sign_document(
+        &self,
+        document: &mut Document,
+        signer: &mut dyn Sign,
+        public_key: &Point,
+    ) -> Result<()>;
+
+    /// Publishes a signed document and returns its DID.
+    fn publish_document(&self, document: &mut Document) -> Result<String>;
+
+    /// Resolves a published DID to its document.
+    fn resolve_document(&self, did: &str) -> Result<Document>;
+
+    /// Looks for a published document controlled by `public_key`, returning its DID.
+    fn find_document(&self, public_key: &Point) -> Result<Option<String>>;
+
+    /// Makes sure the owner of `public_key` can afford to publish a document. If the owner is
+    /// not the `requester` of the funds it waits for them to be available.
+    fn fund(&self, public_key: &Point, requester: bool) -> Result<()>;
+}
@@ -0,0 +1,388 @@
+use std::{
+    sync::{
+        mpsc::{channel, Receiver},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use iota_client::crypto::hashes::{blake2b::Blake2b256, Digest};
+use serde::{Deserialize, Serialize};
+
+use super::Transport;
+
+/// Largest payload published in a single block, a tagged data block holds at most 32 KiB.
+pub const DEFAULT_CHUNK_SIZE: usize = 24 * 1024;
+/// Largest reassembled payload, manifests announcing more are rejected before fetching any
+/// chunk. A manifest fitting in a block lists a few hundred chunks, so honest payloads are
+/// well below it.
+pub const DEFAULT_MAX_SIZE: u64 = 16 * 1024 * 1024;
+/// Time given to the chunks of a payload received by a subscriber to be fetched, payloads
+/// whose chunks are not fetched in time are discarded so that they don't hold back the next
+/// ones.
+pub const DEFAULT_FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+const MANIFEST_FORMAT: &str = "dora-chunked-v1";
+
+/// Payload published in place of data larger than the chunk size. Chunks are published
+/// without tag, in order, and the manifest is published last under the data's tag.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    format: String,
+    size: u64,
+    /// hex encoded Blake2b256 digest of the whole data
+    digest: String,
+    chunks: Vec<String>,
+}
+
+/// [Transport] decorator splitting large payloads into chunks, subscribers and fetchers get
+/// the reassembled and verified data transparently.
+pub struct ChunkedTransport<T: Transport> {
+    inner: Arc<T>,
+    chunk_size: usize,
+    max_size: u64,
+    fetch_timeout: Duration,
+}
+
+impl<T: Transport + 'static> ChunkedTransport<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            max_size: DEFAULT_MAX_SIZE,
+            fetch_timeout: DEFAULT_FETCH_TIMEOUT,
+        }
+    }
+
+    #[cfg(any(test, feature = "simulation"))]
+    pub fn with_chunk_size(self, chunk_size: usize) -> Self {
+        Self { chunk_size, ..self }
+    }
+}
+
+impl<T: Transport + 'static> Transport for ChunkedTransport<T> {
+    fn subscribe(&self, tag: String) -> Result<Receiver<(Vec<u8>, String)>> {
+        let receiver = self.inner.subscribe(tag)?;
+        let (pending_tx, pending_rx) = channel();
+        let inner = self.inner.clone();
+        let max_size = self.max_size;
+        // manifests are reassembled concurrently, as soon as they are received
+        thread::spawn(move || {
+            for (data, id) in receiver {
+                let (tx, rx) = channel();
+                match parse_manifest(&data) {
+                    Some(manifest) => {
+                        let inner = inner.clone();
+                        thread::spawn(move || {
+                            let _ =
+                                tx.send(reassemble_manifest(inner.as_ref(), manifest, max_size));
+                        });
+                    }
+                    None => {
+                        let _ = tx.send(Ok(data));
+                    }
+                }
+                if pending_tx.send((Instant::now(), id, rx)).is_err() {
+                    break;
+                }
+            }
+        });
+        let (tx, rx) = channel();
+        let fetch_timeout = self.fetch_timeout;
+        // and forwarded in the order they were received
+        thread::spawn(move || {
+            for (received_at, id, reassembled) in pending_rx {
+                let timeout =
+                    (received_at + fetch_timeout).saturating_duration_since(Instant::now());
+                match reassembled.recv_timeout(timeout) {
+                    Ok(Ok(data)) => {
+                        if tx.send((data, id)).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(Err(e)) => log::warn!("discarding chunked payload {}: {}", id, e),
+                    Err(_) => log::warn!(
+                        "discarding chunked payload {}: chunks not fetched in time",
+                        id
+                    ),
+                }
+            }
+        });
+        Ok(rx)
+    }
+
+    fn publish(&self, data: &[u8], tag: Option<String>) -> Result<String> {
+        if data.len() <= self.chunk_size {
+            return self.inner.publish(data, tag);
+        }
+        if data.len() as u64 > self.max_size {
+            bail!("payload of {} bytes is too large", data.len());
+        }
+        let mut chunks = Vec::with_capacity(data.len() / self.chunk_size + 1);
+        for chunk in data.chunks(self.chunk_size) {
+            chunks.push(self.inner.publish(chunk, None)?);
+        }
+        let manifest = serde_json::to_vec(&Manifest {
+            format: MANIFEST_FORMAT.to_owned(),
+            size: data.len() as u64,
+            digest: hex::encode(Blake2b256::digest(data)),
+            chunks,
+        })?;
+        if manifest.len() > self.chunk_size {
+            bail!("payload of {} bytes is too large", data.len());
+        }
+        self.inner.publish(&manifest, tag)
+    }
+
+    fn fetch(&self, id: &str) -> Result<Vec<u8>> {
+        reassemble(self.inner.as_ref(), self.inner.fetch(id)?, self.max_size)
+    }
+}
+
+/// Returns the data referenced by a manifest, or the data itself if it is not a manifest.
+/// Manifests announcing more than `max_size` bytes are rejected.
+fn reassemble(transport: &dyn Transport, data: Vec<u8>, max_size: u64) -> Result<Vec<u8>> {
+    match parse_manifest(&data) {
+        Some(manifest) => reassemble_manifest(transport, manifest, max_size),
+        None => Ok(data),
+    }
+}
+
+fn parse_manifest(data: &[u8]) -> Option<Manifest> {
+    serde_json::from_slice::<Manifest>(data)
+        .ok()
+        .filter(|manifest| manifest.format == MANIFEST_FORMAT)
+}
+
+fn reassemble_manifest(
+    transport: &dyn Transport,
+    manifest: Manifest,
+    max_size: u64,
+) -> Result<Vec<u8>> {
+    if manifest.size > max_size {
+        bail!("manifest of {} bytes is too large", manifest.size);
+    }
+
+    // the size is only trusted once the chunks are fetched
+    let mut reassembled = Vec::with_capacity(DEFAULT_CHUNK_SIZE);
+    for chunk in manifest.chunks.iter() {
+        let chunk = transport.fetch(chunk)?;
+        if (reassembled.len() + chunk.len()) as u64 > manifest.size {
+            bail!("chunks exceed the size in the manifest");
+        }
+        reassembled.extend(chunk);
+    }
+    if reassembled.len() as u64 != manifest.size {
+        bail!("chunks don't match the size in the manifest");
+    }
+    if hex::encode(Blake2b256::digest(&reassembled)) != manifest.digest {
+        bail!("chunks don't match the digest in the manifest");
+    }
+    Ok(reassembled)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::dlt::memory::MemoryTransport;
+
+    use super::*;
+
+    const CHUNK_SIZE: usize = 1024;
+
+    fn data(size: usize) -> Vec<u8> {
+        (0..size).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn manifest(data: &[u8], chunks: Vec<String>) -> Vec<u8> {
+        serde_json::to_vec(&Manifest {
+            format: MANIFEST_FORMAT.to_owned(),
+            size: data.len() as u64,
+            digest: hex::encode(Blake2b256::digest(data)),
+            chunks,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn small_payloads_are_published_as_is() {
+        let memory = MemoryTransport::new();
+        let transport = ChunkedTransport::new(memory.clone()).with_chunk_size(CHUNK_SIZE);
+        let id = transport.publish(&data(CHUNK_SIZE), None).unwrap();
+
+        assert_eq!(memory.fetch(&id).unwrap(), data(CHUNK_SIZE));
+        assert_eq!(transport.fetch(&id).unwrap(), data(CHUNK_SIZE));
+    }
+
+    #[test]
+    fn large_payloads_are_reassembled() {
+        let memory = MemoryTransport::new();
+        let transport = ChunkedTransport::new(memory.clone()).with_chunk_size(CHUNK_SIZE);
+        let receiver = transport.subscribe("tag".to_owned()).unwrap();
+        let data = data(3 * CHUNK_SIZE + 1);
+        let id = transport.publish(&data, Some("tag".to_owned())).unwrap();
+
+        assert_ne!(memory.fetch(&id).unwrap(), data);
+        assert_eq!(transport.fetch(&id).unwrap(), data);
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap(),
+            (data, id)
+        );
+    }
+
+    #[test]
+    fn chunks_are_reassembled_in_the_manifest_order() {
+        let memory = MemoryTransport::new();
+        let data = data(3 * CHUNK_SIZE);
+        let mut chunks: Vec<String> = data
+            .chunks(CHUNK_SIZE)
+            .rev()
+            .map(|chunk| memory.publish(chunk, None).unwrap())
+            .collect();
+        chunks.reverse();
+        let id = memory.publish(&manifest(&data, chunks), None).unwrap();
+
+        let transport = ChunkedTransport::new(memory).with_chunk_size(CHUNK_SIZE);
+        assert_eq!(transport.fetch(&id).unwrap(), data);
+    }
+
+    #[test]
+    fn rejects_missing_and_altered_chunks() {
+        let memory = MemoryTransport::new();
+        let data = data(3 * CHUNK_SIZE);
+        let chunks: Vec<String> = data
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| memory.publish(chunk, None).unwrap())
+            .collect();
+        let transport = ChunkedTransport::new(memory.clone()).with_chunk_size(CHUNK_SIZE);
+
+        let mut missing = chunks.clone();
+        missing[1] = "0x00".to_owned();
+        let mut swapped = chunks.clone();
+        swapped.swap(0, 1);
+        for chunks in [missing, swapped, chunks[..2].to_vec()] {
+            let id = memory.publish(&manifest(&data, chunks), None).unwrap();
+            assert!(transport.fetch(&id).is_err());
+        }
+    }
+
+    #[test]
+    fn subscribers_skip_invalid_manifests() {
+        let memory = MemoryTransport::new();
+        let transport = ChunkedTransport::new(memory.clone()).with_chunk_size(CHUNK_SIZE);
+        let receiver = transport.subscribe("tag".to_owned()).unwrap();
+        let data = data(2 * CHUNK_SIZE);
+        memory
+            .publish(
+                &manifest(&data, vec!["0x00".to_owned()]),
+                Some("tag".to_owned()),
+            )
+            .unwrap();
+        let id = transport.publish(b"next", Some("tag".to_owned())).unwrap();
+
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap(),
+            (b"next".to_vec(), id)
+        );
+    }
+
+    #[test]
+    fn rejects_manifests_above_the_max_size() {
+        let memory = MemoryTransport::new();
+        let transport = ChunkedTransport {
+            max_size: 2 * CHUNK_SIZE as u64,
+            ..ChunkedTransport::new(memory.clone()).with_chunk_size(CHUNK_SIZE)
+        };
+        let receiver = transport.subscribe("tag".to_owned()).unwrap();
+        assert!(transport.publish(&data(3 * CHUNK_SIZE), None).is_err());
+
+        let data = data(3 * CHUNK_SIZE);
+        let chunks: Vec<String> = data
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| memory.publish(chunk, None).unwrap())
+            .collect();
+        let id = memory.publish(&manifest(&data, chunks), None).unwrap();
+        assert!(transport.fetch(&id).is_err());
+
+        let huge = serde_json::to_vec(&Manifest {
+            format: MANIFEST_FORMAT.to_owned(),
+            size: u64::MAX,
+            digest: String::new(),
+            chunks: vec![],
+        })
+        .unwrap();
+        memory.publish(&huge, Some("tag".to_owned())).unwrap();
+        let id = transport.publish(b"next", Some("tag".to_owned())).unwrap();
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap(),
+            (b"next".to_vec(), id)
+        );
+    }
+
+    /// Transport whose fetches of the given id never complete in time.
+    struct Stalled(MemoryTransport, String);
+
+    impl Transport for Stalled {
+        fn subscribe(&self, tag: String) -> Result<Receiver<(Vec<u8>, String)>> {
+            self.0.subscribe(tag)
+        }
+
+        fn publish(&self, data: &[u8], tag: Option<String>) -> Result<String> {
+            self.0.publish(data, tag)
+        }
+
+        fn fetch(&self, id: &str) -> Result<Vec<u8>> {
+            if id == self.1 {
+                thread::sleep(Duration::from_secs(60));
+            }
+            self.0.fetch(id)
+        }
+    }
+
+    #[test]
+    fn payloads_are_forwarded_in_order() {
+        let memory = MemoryTransport::new();
+        let transport = ChunkedTransport::new(memory).with_chunk_size(CHUNK_SIZE);
+        let receiver = transport.subscribe("tag".to_owned()).unwrap();
+        let large = data(8 * CHUNK_SIZE);
+        let first = transport.publish(&large, Some("tag".to_owned())).unwrap();
+        let second = transport.publish(b"next", Some("tag".to_owned())).unwrap();
+
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap(),
+            (large, first)
+        );
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap(),
+            (b"next".to_vec(), second)
+        );
+    }
+
+    #[test]
+    fn stalled_chunks_do_not_hold_back_the_next_payloads() {
+        let memory = MemoryTransport::new();
+        let data = data(2 * CHUNK_SIZE);
+        let chunks: Vec<String> = data
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| memory.publish(chunk, None).unwrap())
+            .collect();
+        let transport = ChunkedTransport {
+            fetch_timeout: Duration::from_millis(100),
+            ..ChunkedTransport::new(Stalled(memory.clone(), chunks[1].clone()))
+                .with_chunk_size(CHUNK_SIZE)
+        };
+        let receiver = transport.subscribe("tag".to_owned()).unwrap();
+        memory
+            .publish(&manifest(&data, chunks), Some("tag".to_owned()))
+            .unwrap();
+        let id = transport.publish(b"next", Some("tag".to_owned())).unwrap();
+
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap(),
+            (b"next".to_vec(), id)
+        );
+    }
+}
@@ -7,10 +7,7 @@ use std::{
 };
 
 use anyhow::Result;
-use iota_client::{
-    block::{payload::Payload, BlockId},
-    Client, MqttEvent, MqttPayload, Topic,
-};
+use iota_client::{block::payload::Payload, Client, MqttEvent, MqttPayload, Topic};
 
 pub struct Listener(Client);
 
@@ -19,7 +16,7 @@ impl Listener {
         Ok(Listener(Client::builder().with_node(node_url)?.finish()?))
     }
 
-    pub async fn start(&mut self, tag: String) -> Result<Receiver<(Vec<u8>, BlockId)>> {
+    pub async fn start(&mut self, tag: String) -> Result<Receiver<(Vec<u8>, String)>> {
         self.listen_tag(tag).await
     }
 
@@ -28,7 +25,7 @@ impl Listener {
         Ok(())
     }
 
-    async fn listen_tag(&mut self, tag: String) -> Result<Receiver<(Vec<u8>, BlockId)>> {
+    async fn listen_tag(&mut self, tag: String) -> Result<Receiver<(Vec<u8>, String)>> {
         let (tx, rx) = channel();
         let tx = Arc::new(Mutex::new(tx));
 
@@ -51,7 +48,7 @@ impl Listener {
                         if tag.as_bytes() == payload.tag() {
                             tx.lock()
                                 .unwrap()
-                                .send((Vec::from(payload.data()), b.id()))
+                                .send((Vec::from(payload.data()), b.id().to_string()))
                                 .unwrap()
                         }
                     };
@@ -134,7 +134,7 @@ pub fn create_unsigned_did(
 pub async fn sign_did(
     node_url: &str,
     prepared_transaction_data: &PreparedTransactionData,
-    signer: &mut impl Sign,
+    signer: &mut dyn Sign,
     public_key: &Point,
 ) -> Result<Payload, anyhow::Error> {
     let hashed_essence = prepared_transaction_data.essence.hash();
@@ -188,7 +188,7 @@ pub async fn sign_did(
                     current_block_index + 1,
                     prepared_transaction_data.inputs_data.len()
                 );
-                let signature = signer.sign(hashed_essence)?;
+                let signature = signer.sign(&hashed_essence)?;
                 log::debug!(
                     "unlock {}/{} signed",
                     current_block_index + 1,
@@ -420,23 +420,23 @@ pub async fn find_inputs(client: &Client, address: String, amount: u64) -> Resul
 }
 
 pub trait Sign {
-    fn sign<T: AsRef<[u8]>>(&mut self, data: T) -> Result<[u8; 64], SignatureError>;
+    fn sign(&mut self, data: &[u8]) -> Result<[u8; 64], SignatureError>;
 }
 
 impl Sign for Pair<Point> {
-    fn sign<T: AsRef<[u8]>>(&mut self, data: T) -> Result<[u8; 64], SignatureError> {
-        EdDSA::from(self.clone()).sign(data.as_ref())
+    fn sign(&mut self, data: &[u8]) -> Result<[u8; 64], SignatureError> {
+        EdDSA::from(self.clone()).sign(data)
     }
 }
 
 impl<R: Receiver<MessageWrapper<SignMessage>> + Clone, S: Sender<MessageWrapper<SignMessage>>> Sign
     for FsmSigner<R, S>
 {
-    fn sign<T: AsRef<[u8]>>(&mut self, data: T) -> Result<[u8; 64], SignatureError> {
+    fn sign(&mut self, data: &[u8]) -> Result<[u8; 64], SignatureError> {
         let init_state = self
             .init_state_builder
             .clone()
-            .with_message(data.as_ref())
+            .with_message(data)
             .with_session_id_nonce(self.round.to_string())
             .build()
             .map_err(|e| SignatureError::InvalidSignature(e.to_string()))?;
@@ -1,5 +1,7 @@
 mod comm;
 mod did;
+mod transport;
 
 pub use comm::{Listener, Publisher};
 pub use did::{create_unsigned_did, publish_did, resolve_did, sign_did, FsmSigner, Sign};
+pub use transport::IotaTransport;
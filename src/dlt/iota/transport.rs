@@ -0,0 +1,55 @@
+use std::{
+    str::FromStr,
+    sync::{mpsc::Receiver, Mutex},
+};
+
+use crate::{dlt::Transport, runtime::BlockingRuntime};
+use anyhow::{bail, Result};
+use iota_client::block::{payload::Payload, BlockId};
+
+use super::{Listener, Publisher};
+
+/// [Transport] backed by an IOTA node, payloads are published as tagged data blocks.
+pub struct IotaTransport {
+    node_url: String,
+    publisher: Publisher,
+    listeners: Mutex<Vec<Listener>>,
+    runtime: BlockingRuntime,
+}
+
+impl IotaTransport {
+    pub fn new(node_url: &str) -> Result<Self> {
+        Ok(IotaTransport {
+            node_url: node_url.to_owned(),
+            publisher: Publisher::new(node_url)?,
+            listeners: Mutex::new(Vec::new()),
+            runtime: BlockingRuntime::new()?,
+        })
+    }
+}
+
+impl Transport for IotaTransport {
+    fn subscribe(&self, tag: String) -> Result<Receiver<(Vec<u8>, String)>> {
+        let mut listener = Listener::new(&self.node_url)?;
+        let receiver = self.runtime.block_on(listener.start(tag))?;
+        // keep the listener alive as long as the transport
+        self.listeners.lock().unwrap().push(listener);
+        Ok(receiver)
+    }
+
+    fn publish(&self, data: &[u8], tag: Option<String>) -> Result<String> {
+        self.runtime.block_on(self.publisher.publish(data, tag))
+    }
+
+    fn fetch(&self, id: &str) -> Result<Vec<u8>> {
+        let block_id = BlockId::from_str(id)?;
+        let block = self
+            .runtime
+            .block_on(self.publisher.0.get_block(&block_id))?;
+        match block.payload() {
+            Some(Payload::TaggedData(tagged_data)) => Ok(tagged_data.data().to_vec()),
+            Some(_) => bail!("payload of block {block_id} is not supported"),
+            None => bail!("missing payload {block_id}"),
+        }
+    }
+}
@@ -0,0 +1,3 @@
+mod transport;
+
+pub use transport::MemoryTransport;
@@ -0,0 +1,140 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::Result;
+use iota_client::crypto::hashes::{blake2b::Blake2b256, Digest};
+
+use crate::dlt::Transport;
+
+/// In-process [Transport], every payload is delivered to the subscribers of its tag. Clones
+/// share the same bus, so a whole committee can communicate inside a single process.
+#[derive(Clone, Default)]
+pub struct MemoryTransport(Arc<Mutex<Bus>>);
+
+/// Payloads are delivered together with their id.
+type Delivery = (Vec<u8>, String);
+
+#[derive(Default)]
+struct Bus {
+    payloads: HashMap<String, Vec<u8>>,
+    subscribers: Vec<(String, Sender<Delivery>)>,
+    published: u64,
+}
+
+impl MemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of live subscriptions to the given tag, so that tests can wait for the nodes.
+    #[cfg(any(test, feature = "simulation"))]
+    pub fn subscribers(&self, tag: &str) -> usize {
+        self.0
+            .lock()
+            .unwrap()
+            .subscribers
+            .iter()
+            .filter(|(t, _)| t == tag)
+            .count()
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn subscribe(&self, tag: String) -> Result<Receiver<Delivery>> {
+        let (tx, rx) = channel();
+        self.0.lock().unwrap().subscribers.push((tag, tx));
+        Ok(rx)
+    }
+
+    fn publish(&self, data: &[u8], tag: Option<String>) -> Result<String> {
+        let mut bus = self.0.lock().unwrap();
+        bus.published += 1;
+
+        // ids look like block ids, so they can be used wherever a block id is expected
+        let mut hasher = Blake2b256::new();
+        hasher.update(bus.published.to_be_bytes());
+        hasher.update(data);
+        let id = format!("0x{}", hex::encode(hasher.finalize()));
+
+        bus.payloads.insert(id.clone(), data.to_vec());
+        if let Some(tag) = tag {
+            // subscribers whose receiver has been dropped are removed
+            bus.subscribers
+                .retain(|(t, tx)| *t != tag || tx.send((data.to_vec(), id.clone())).is_ok());
+        }
+        Ok(id)
+    }
+
+    fn fetch(&self, id: &str) -> Result<Vec<u8>> {
+        self.0
+            .lock()
+            .unwrap()
+            .payloads
+            .get(id)
+            .cloned()
+            .ok_or_else(|| anyhow::Error::msg(format!("missing payload {id}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn payloads_are_delivered_to_the_subscribers_of_their_tag() {
+        let transport = MemoryTransport::new();
+        let tagged = transport.subscribe("tag".to_owned()).unwrap();
+        let other = transport.clone().subscribe("other".to_owned()).unwrap();
+        assert_eq!(transport.subscribers("tag"), 1);
+
+        let id = transport.publish(b"data", Some("tag".to_owned())).unwrap();
+        assert_eq!(
+            tagged.recv_timeout(Duration::from_secs(1)).unwrap(),
+            (b"data".to_vec(), id.clone())
+        );
+        assert!(other.try_recv().is_err());
+        assert_eq!(transport.fetch(&id).unwrap(), b"data");
+    }
+
+    #[test]
+    fn untagged_payloads_are_only_fetched() {
+        let transport = MemoryTransport::new();
+        let receiver = transport.subscribe("tag".to_owned()).unwrap();
+        let id = transport.publish(b"data", None).unwrap();
+
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(transport.fetch(&id).unwrap(), b"data");
+    }
+
+    #[test]
+    fn identical_payloads_get_distinct_ids() {
+        let transport = MemoryTransport::new();
+        let first = transport.publish(b"data", None).unwrap();
+        let second = transport.publish(b"data", None).unwrap();
+
+        assert_ne!(first, second);
+        assert!(first.starts_with("0x") && first.len() == 66);
+    }
+
+    #[test]
+    fn unknown_ids_are_not_fetched() {
+        let transport = MemoryTransport::new();
+        assert!(transport.fetch("0x00").is_err());
+    }
+
+    #[test]
+    fn dropped_subscribers_are_removed() {
+        let transport = MemoryTransport::new();
+        drop(transport.subscribe("tag".to_owned()).unwrap());
+        transport.publish(b"data", Some("tag".to_owned())).unwrap();
+
+        assert_eq!(transport.subscribers("tag"), 0);
+    }
+}
@@ -1 +1,8 @@
+mod chunked;
 pub mod iota;
+#[cfg(any(test, feature = "simulation"))]
+pub mod memory;
+mod transport;
+
+pub use chunked::ChunkedTransport;
+pub use transport::Transport;
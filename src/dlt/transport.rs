@@ -0,0 +1,16 @@
+use std::sync::mpsc::Receiver;
+
+use anyhow::Result;
+
+/// A [Transport] carries tagged payloads between the parties of a committee (nodes, governor
+/// and clients). Payloads are addressed by the id returned when they are published.
+pub trait Transport: Send + Sync {
+    /// Subscribes to every payload published with `tag`, delivered together with its id.
+    fn subscribe(&self, tag: String) -> Result<Receiver<(Vec<u8>, String)>>;
+
+    /// Publishes `data`, optionally under `tag`, returning the id of the payload.
+    fn publish(&self, data: &[u8], tag: Option<String>) -> Result<String>;
+
+    /// Retrieves the payload published with the given `id`.
+    fn fetch(&self, id: &str) -> Result<Vec<u8>>;
+}
@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
 use anyhow::bail;
 use colored::Colorize;
@@ -6,37 +6,37 @@ use identity_iota::core::ToJson;
 use kyber_rs::{group::edwards25519::Point, sign::eddsa::EdDSA, util::key::Pair};
 use serde::{Deserialize, Serialize};
 
-use crate::{did::resolve_document, dlt::iota::Publisher};
+use crate::{did::DidRegistry, dlt::Transport};
 
 #[derive(Clone)]
 pub struct NodeSignatureLogger {
     own_did: String,
     committee_tag: String,
     keypair: Pair<Point>,
-    node_url: String,
+    transport: Arc<dyn Transport>,
 }
 
 pub fn new_node_signature_logger(
     own_did: String,
     committee_did: String,
     keypair: Pair<Point>,
-    node_url: String,
+    transport: Arc<dyn Transport>,
 ) -> NodeSignatureLogger {
     NodeSignatureLogger {
         own_did,
         committee_tag: committee_did.split(':').last().unwrap()[2..].to_string(),
         keypair,
-        node_url,
+        transport,
     }
 }
 
 impl NodeSignatureLogger {
     pub fn publish(&self, log: &mut NodeSignatureLog) -> anyhow::Result<()> {
-        let publisher = Publisher::new(&self.node_url)?;
         self.sign_log(log)?;
 
-        let block_id = tokio::runtime::Runtime::new()?
-            .block_on(publisher.publish(&log.to_jcs()?, Some(self.committee_tag.clone())))?;
+        let block_id = self
+            .transport
+            .publish(&log.to_jcs()?, Some(self.committee_tag.clone()))?;
         log::info!(target: &signature_log_target(&log.session_id),
             "node's signature log published (block_id: {})", block_id);
         Ok(())
@@ -64,12 +64,12 @@ pub fn new_signature_log(
     processed_partial_owners: Vec<Point>,
     bad_signers: Vec<Point>,
     did_urls: Vec<String>,
-    node_url: String,
+    registry: &dyn DidRegistry,
 ) -> anyhow::Result<(NodeSignatureLog, Vec<String>)> {
     // find out who didn't send a partial signature
     let mut processed_partial_owners_dids = vec![];
     for owner in processed_partial_owners {
-        processed_partial_owners_dids.push(public_to_did(&did_urls, owner, &node_url)?);
+        processed_partial_owners_dids.push(public_to_did(&did_urls, owner, registry)?);
     }
 
     let mut absent_nodes = did_urls.clone();
@@ -80,7 +80,7 @@ pub fn new_signature_log(
     // find out who was a bad signer
     let mut bad_signers_nodes = vec![];
     for owner in bad_signers {
-        bad_signers_nodes.push(public_to_did(&did_urls, owner, &node_url)?);
+        bad_signers_nodes.push(public_to_did(&did_urls, owner, registry)?);
     }
 
     let mut working_nodes = vec![];
@@ -134,9 +134,13 @@ pub fn signature_log_target(session_id: &str) -> String {
     )
 }
 
-pub fn public_to_did(dids: &[String], public_key: Point, node_url: &str) -> anyhow::Result<String> {
+pub fn public_to_did(
+    dids: &[String],
+    public_key: Point,
+    registry: &dyn DidRegistry,
+) -> anyhow::Result<String> {
     for did in dids.iter() {
-        if resolve_document(did.to_string(), node_url)?.public_key()? == public_key {
+        if registry.resolve_document(did)?.public_key()? == public_key {
             return Ok(did.to_string());
         }
     }
@@ -7,6 +7,7 @@ mod did;
 mod dlt;
 mod logging;
 mod net;
+mod runtime;
 mod states;
 mod store;
 
@@ -14,22 +15,35 @@ use std::str::FromStr;
 
 use anyhow::{bail, Result};
 
-use api::requests::messages::CommitteeLog;
+use api::requests::{messages::CommitteeLog, Quotas};
 
 use clap::Parser;
-use demo::run::{run_node, NodeArgs};
+#[cfg(feature = "simulation")]
+use demo::simulate::{run_simulation, SimulateArgs};
+use demo::{
+    run::{run_node, DkgInit, NodeArgs},
+    CommitteeConfig, DEFAULT_TASK_INTERVAL,
+};
 
-use did::resolve_document;
-use dlt::iota::Publisher;
-use identity_iota::core::ToJson;
-use kyber_rs::sign::eddsa;
+use did::{DidRegistry, IotaRegistry};
+use dlt::{iota::IotaTransport, ChunkedTransport, Transport};
+use kyber_rs::{
+    encoding::{BinaryMarshaler, BinaryUnmarshaler},
+    group::edwards25519::{Point, Scalar, SuiteEd25519},
+    sign::eddsa,
+    util::key::{new_key_pair, Pair},
+    Point as _,
+};
 use logging::NodeSignatureLog;
 
 use states::dkg;
 
 use crate::api::requests::{
-    messages::{Execution, InputUri, OutputUri, StorageLocalUri, StorageUri},
-    GenericRequest,
+    messages::{
+        unix_time, Aggregation, DataEncoding, DigestAlgorithm, Execution, InputUri, Operation,
+        OracleOptions, OutputUri, StorageLocalUri, StorageUri, REQUEST_VERSION,
+    },
+    AuditLog, DeletionLog, GenericRequest, SealedData, SyncReport,
 };
 
 #[derive(Parser)]
@@ -46,6 +60,12 @@ enum Action {
     NewCommittee(NewCommitteeArgs),
     Verify(VerifyArgs),
     VerifyLog(VerifyLogArgs),
+    VerifyAudit(VerifyAuditArgs),
+    VerifyDeletions(VerifyDeletionsArgs),
+    VerifySync(VerifySyncArgs),
+    Open(OpenArgs),
+    #[cfg(feature = "simulation")]
+    Simulate(SimulateArgs),
 }
 
 #[derive(Parser)]
@@ -60,6 +80,71 @@ struct VerifyLogArgs {
     node_url: String,
 }
 
+#[derive(Parser)]
+struct VerifyAuditArgs {
+    #[arg(
+        required = true,
+        long = "audit-log",
+        help = "audit log of a dora committee"
+    )]
+    audit_log: AuditLog,
+
+    #[arg(
+        long = "node-url",
+        default_value = "https://api.testnet.shimmer.network"
+    )]
+    node_url: String,
+}
+
+#[derive(Parser)]
+struct VerifyDeletionsArgs {
+    #[arg(
+        required = true,
+        long = "deletion-log",
+        help = "deletion log of a dora committee"
+    )]
+    deletion_log: DeletionLog,
+
+    #[arg(
+        long = "node-url",
+        default_value = "https://api.testnet.shimmer.network"
+    )]
+    node_url: String,
+}
+
+#[derive(Parser)]
+struct VerifySyncArgs {
+    #[arg(
+        required = true,
+        long = "sync-report",
+        help = "sync report of a dora node"
+    )]
+    sync_report: SyncReport,
+
+    #[arg(
+        long = "node-url",
+        default_value = "https://api.testnet.shimmer.network"
+    )]
+    node_url: String,
+}
+
+#[derive(Parser)]
+struct OpenArgs {
+    #[arg(
+        required = true,
+        long = "sealed-data",
+        help = "data revealed by a dora committee, sealed to the requester's key"
+    )]
+    sealed_data: SealedData,
+
+    #[arg(
+        required = true,
+        long = "requester-key-file",
+        help = "file with the hex encoded private key of the requester"
+    )]
+    requester_key_file: String,
+}
+
 #[derive(Parser)]
 struct SendArgs {
     #[arg(required = true, long = "message", help = "message to send")]
@@ -80,6 +165,9 @@ struct VerifyArgs {
     #[arg(required = true, long = "committee-log", help = "dora committee log")]
     committee_log: CommitteeLog,
 
+    #[arg(long, help = "file where the decoded data of the log is written", default_value = None)]
+    output: Option<String>,
+
     #[arg(
         long = "node-url",
         default_value = "https://api.testnet.shimmer.network"
@@ -95,6 +183,107 @@ struct RequestArgs {
     #[arg(long, help = "storage id", default_value = None)]
     storage_id: Option<String>,
 
+    #[arg(
+        long,
+        value_enum,
+        help = "operation, inferred from storage id if not set",
+        default_value = None
+    )]
+    operation: Option<OperationArg>,
+
+    #[arg(long, help = "prefix of the keys to list", default_value = "")]
+    prefix: String,
+
+    #[arg(
+        long,
+        help = "JSON pointer of the value observed by oracle requests",
+        default_value = ""
+    )]
+    pointer: String,
+
+    #[arg(
+        long,
+        help = "fraction of values trimmed at each end by oracle requests, the median is used if not set",
+        default_value = None
+    )]
+    trim: Option<f64>,
+
+    #[arg(
+        long = "json-path",
+        help = "JSON pointer or path of the subset of the input selected by get and store requests",
+        default_value = None
+    )]
+    json_path: Option<String>,
+
+    #[arg(
+        long = "http-options",
+        help = "method, headers, body, timeout and max_size of the http request for url inputs, as JSON",
+        default_value = None
+    )]
+    http_options: Option<String>,
+
+    #[arg(
+        long = "data-encoding",
+        help = "encoding of the data in the committee's log: utf8, base64 or hex",
+        default_value = None
+    )]
+    data_encoding: Option<DataEncoding>,
+
+    #[arg(
+        long,
+        help = "digest algorithm of store-content requests: blake2b256 or sha256",
+        default_value = "blake2b256"
+    )]
+    digest: DigestAlgorithm,
+
+    #[arg(
+        long,
+        help = "hex encoded nonce of challenge requests, a random one is used if not set",
+        default_value = None
+    )]
+    nonce: Option<String>,
+
+    #[arg(
+        long = "object-version",
+        help = "version of the object retrieved by get-version requests",
+        default_value = "1"
+    )]
+    object_version: u64,
+
+    #[arg(
+        long = "object-request-id",
+        help = "block id of the store request of the object's latest version, which signed delete requests must name",
+        default_value = None
+    )]
+    object_request_id: Option<String>,
+
+    #[arg(
+        long = "content-type",
+        help = "content type recorded in the metadata of the stored object",
+        default_value = None
+    )]
+    content_type: Option<String>,
+
+    #[arg(
+        long,
+        help = "time [s] after which the stored object expires, it is kept until deleted if not set",
+        default_value = None
+    )]
+    ttl: Option<u64>,
+
+    #[arg(
+        long = "requester-key-file",
+        help = "file with the hex encoded private key signing the request, the stored object counts towards the quota of its public key; created if missing",
+        default_value = None
+    )]
+    requester_key_file: Option<String>,
+
+    #[arg(
+        long,
+        help = "encrypts the literal input to the committee's public key, so that the committee can only reveal it to the key of --requester-key-file"
+    )]
+    seal: bool,
+
     #[arg(long = "committee-tag", long, help = "tag")]
     committee_tag: String,
 
@@ -105,6 +294,24 @@ struct RequestArgs {
     node_url: String,
 }
 
+#[derive(Clone, clap::ValueEnum)]
+enum OperationArg {
+    Get,
+    Store,
+    Delete,
+    List,
+    Head,
+    Exists,
+    Oracle,
+    StoreContent,
+    Prove,
+    Challenge,
+    Reveal,
+    StoreShards,
+    GetShards,
+    GetVersion,
+}
+
 #[derive(Parser)]
 struct NewCommitteeArgs {
     #[arg(
@@ -117,6 +324,38 @@ struct NewCommitteeArgs {
     #[arg(required = true, long, help = "node DIDs")]
     nodes: String,
 
+    /// time [s] between two conservation audits of the stored objects, disabled if 0
+    #[arg(long = "audit-interval", default_value_t = DEFAULT_TASK_INTERVAL)]
+    audit_interval: u64,
+
+    /// time [s] between two garbage collections of the expired objects, disabled if 0
+    #[arg(long = "gc-interval", default_value_t = DEFAULT_TASK_INTERVAL)]
+    gc_interval: u64,
+
+    /// time [s] between two anti-entropy repairs of the stored objects, disabled if 0
+    #[arg(long = "repair-interval", default_value_t = DEFAULT_TASK_INTERVAL)]
+    repair_interval: u64,
+
+    /// maximum size [bytes] of a stored object, not limited if not set
+    #[arg(long = "max-object-size", default_value = None)]
+    max_object_size: Option<u64>,
+
+    /// maximum number of objects stored by the committee, not limited if not set
+    #[arg(long = "max-objects", default_value = None)]
+    max_objects: Option<u64>,
+
+    /// maximum size [bytes] of the objects stored by the committee, not limited if not set
+    #[arg(long = "max-bytes", default_value = None)]
+    max_bytes: Option<u64>,
+
+    /// maximum number of objects stored by a requester, not limited if not set
+    #[arg(long = "requester-max-objects", default_value = None)]
+    requester_max_objects: Option<u64>,
+
+    /// maximum size [bytes] of the objects stored by a requester, not limited if not set
+    #[arg(long = "requester-max-bytes", default_value = None)]
+    requester_max_bytes: Option<u64>,
+
     #[arg(
         long = "node-url",
         default_value = "https://api.testnet.shimmer.network"
@@ -134,43 +373,112 @@ fn main() -> Result<()> {
         Action::NewCommittee(args) => new_committee(args)?,
         Action::Verify(args) => verify(args)?,
         Action::VerifyLog(args) => verify_log(args)?,
+        Action::VerifyAudit(args) => verify_audit(args)?,
+        Action::VerifyDeletions(args) => verify_deletions(args)?,
+        Action::VerifySync(args) => verify_sync(args)?,
+        Action::Open(args) => open_sealed(args)?,
         Action::Send(args) => send_message(args)?,
+        #[cfg(feature = "simulation")]
+        Action::Simulate(args) => run_simulation(args)?,
     }
 
     Ok(())
 }
 
 fn verify(args: VerifyArgs) -> Result<()> {
-    let mut response = args.committee_log;
-    let committee_did_url = response.committee_did.clone();
+    let response = args.committee_log;
+    let registry = IotaRegistry::new(&args.node_url);
 
     println!("Retrieving committee's public key from DID document");
-    let public_key = resolve_document(committee_did_url, &args.node_url)?.public_key()?;
+    let public_key = registry
+        .resolve_document(&response.committee_did)?
+        .public_key()?;
     println!("Public key retrieved");
     println!("Performing signature validation");
 
-    if let Some(signature_hex) = response.signature_hex.clone() {
-        response.signature_hex = None;
-        eddsa::verify(
-            &public_key,
-            &response.to_jcs()?,
-            &hex::decode(signature_hex)?,
-        )
-        .map_err(|_| anyhow::Error::msg("Signature is not valid"))?;
-        println!("Signature is valid")
-    } else {
-        bail!("Missing signature")
+    response.verify(&public_key)?;
+    println!("Signature is valid");
+
+    if let Some(output) = args.output {
+        match response.decoded_data()? {
+            Some(data) => {
+                std::fs::write(&output, data)?;
+                println!("Data written to {output}");
+            }
+            None => bail!("The log has no data"),
+        }
     }
 
     Ok(())
 }
 
+fn verify_audit(args: VerifyAuditArgs) -> Result<()> {
+    let audit_log = args.audit_log;
+    let registry = IotaRegistry::new(&args.node_url);
+
+    println!("Retrieving committee's public key from DID document");
+    let public_key = registry
+        .resolve_document(audit_log.committee_did())?
+        .public_key()?;
+    println!("Public key retrieved");
+    println!("Performing signature validation");
+
+    audit_log.verify(&public_key)?;
+    println!("Signature is valid");
+    Ok(())
+}
+
+fn verify_deletions(args: VerifyDeletionsArgs) -> Result<()> {
+    let deletion_log = args.deletion_log;
+    let registry = IotaRegistry::new(&args.node_url);
+
+    println!("Retrieving committee's public key from DID document");
+    let public_key = registry
+        .resolve_document(deletion_log.committee_did())?
+        .public_key()?;
+    println!("Public key retrieved");
+    println!("Performing signature validation");
+
+    deletion_log.verify(&public_key)?;
+    println!("Signature is valid");
+    Ok(())
+}
+
+fn verify_sync(args: VerifySyncArgs) -> Result<()> {
+    let sync_report = args.sync_report;
+    let registry = IotaRegistry::new(&args.node_url);
+
+    println!("Retrieving node's public key from DID document");
+    let public_key = registry
+        .resolve_document(sync_report.node_did())?
+        .public_key()?;
+    println!("Public key retrieved");
+    println!("Performing signature validation");
+
+    sync_report.verify(&public_key)?;
+    println!("Signature is valid");
+    println!(
+        "{} versions synced, {} repaired, {} failed",
+        sync_report.synced().len(),
+        sync_report.repaired().len(),
+        sync_report.failed().len()
+    );
+    Ok(())
+}
+
+fn open_sealed(args: OpenArgs) -> Result<()> {
+    let requester = load_requester_key(&args.requester_key_file)?;
+    let data = args.sealed_data.open_with_key(&requester.private)?;
+    println!("{}", String::from_utf8_lossy(&data));
+    Ok(())
+}
+
 fn verify_log(args: VerifyLogArgs) -> Result<()> {
     let mut log = args.log;
-    let did_url = log.sender_did.clone();
+    let registry = IotaRegistry::new(&args.node_url);
 
     println!("Retrieving node's public key from DID document");
-    let public_key = resolve_document(did_url, &args.node_url)?.public_key()?;
+    let public_key = registry.resolve_document(&log.sender_did)?.public_key()?;
     println!("Public key retrieved");
     println!("Performing signature validation");
 
@@ -191,38 +499,150 @@ fn send_request(args: RequestArgs) -> Result<()> {
     if let Some(id) = args.storage_id {
         storage_id = StorageUri::Storage(StorageLocalUri(id));
     }
-    let request = GenericRequest {
-        input_uri: InputUri::from_str(&args.input_uri).unwrap(),
+    let operation = match args.operation {
+        Some(OperationArg::Get) => Operation::Get,
+        Some(OperationArg::Store) => Operation::Store,
+        Some(OperationArg::Delete) => Operation::Delete,
+        Some(OperationArg::List) => Operation::List {
+            prefix: args.prefix,
+        },
+        Some(OperationArg::Head) => Operation::Head,
+        Some(OperationArg::Exists) => Operation::Exists,
+        Some(OperationArg::Oracle) => Operation::Oracle,
+        Some(OperationArg::StoreContent) => Operation::StoreContent {
+            algorithm: args.digest,
+        },
+        Some(OperationArg::Prove) => Operation::Prove,
+        Some(OperationArg::Challenge) => Operation::Challenge {
+            nonce: args
+                .nonce
+                .unwrap_or_else(|| hex::encode(rand::random::<[u8; 32]>())),
+        },
+        Some(OperationArg::Reveal) => Operation::Reveal,
+        Some(OperationArg::StoreShards) => Operation::StoreShards,
+        Some(OperationArg::GetShards) => Operation::GetShards,
+        Some(OperationArg::GetVersion) => Operation::GetVersion {
+            version: args.object_version,
+        },
+        None => match storage_id {
+            StorageUri::Storage(_) => Operation::Store,
+            StorageUri::None => Operation::Get,
+        },
+    };
+    let mut oracle = None;
+    if operation == Operation::Oracle {
+        oracle = Some(OracleOptions {
+            pointer: args.pointer,
+            aggregation: match args.trim {
+                Some(trim) => Aggregation::TrimmedMean { trim },
+                None => Aggregation::Median,
+            },
+        });
+    }
+    let requester = args
+        .requester_key_file
+        .as_deref()
+        .map(load_requester_key)
+        .transpose()?;
+    let mut input_uri = InputUri::from_str(&args.input_uri)?;
+    if args.seal {
+        let data = match input_uri {
+            InputUri::Literal(data) => data,
+            _ => bail!("only literal inputs can be sealed"),
+        };
+        let recipient = match &requester {
+            Some(requester) => requester.public,
+            None => bail!("sealed inputs are revealed to the key of --requester-key-file"),
+        };
+        let committee_did = format!("did:iota:rms:0x{}", args.committee_tag);
+        let public_key = IotaRegistry::new(&args.node_url)
+            .resolve_document(&committee_did)?
+            .public_key()?;
+        let sealed = SealedData::seal_for(&public_key, Some(&recipient), data.as_bytes())?;
+        input_uri = InputUri::Literal(serde_json::to_string(&sealed)?);
+    }
+    let mut request = GenericRequest {
+        version: REQUEST_VERSION,
+        operation: Some(operation),
+        input_uri,
         output_uri: OutputUri::None,
         execution: Execution::None,
         signature: false,
         storage_uri: storage_id,
+        oracle,
+        json_path: args.json_path,
+        http: args
+            .http_options
+            .map(|options| serde_json::from_str(&options))
+            .transpose()?,
+        data_encoding: args.data_encoding,
+        content_type: args.content_type,
+        ttl: args.ttl,
+        requester: None,
+        requester_signature: None,
+        timestamp: Some(unix_time()),
+        object_request_id: args.object_request_id,
     };
+    if let Some(requester) = requester {
+        request.sign_as_requester(&requester)?;
+    }
     let request = serde_json::to_vec(&request)?;
 
-    let publisher = Publisher::new(&args.node_url)?;
-    let rt = tokio::runtime::Runtime::new()?;
-    let result = rt.block_on(publisher.publish(&request, Some(args.committee_tag)))?;
+    let transport = ChunkedTransport::new(IotaTransport::new(&args.node_url)?);
+    let result = transport.publish(&request, Some(args.committee_tag))?;
     println!("{result}");
     Ok(())
 }
 
-fn new_committee(args: NewCommitteeArgs) -> Result<()> {
-    let mut nodes = args.nodes;
-
-    let publisher = Publisher::new(&args.node_url)?;
+/// Loads the requester's key pair from the file, or creates it if the file doesn't exist.
+fn load_requester_key(key_file: &str) -> Result<Pair<Point>> {
+    let private = match std::fs::read_to_string(key_file) {
+        Ok(private_hex) => {
+            let mut private = Scalar::default();
+            private.unmarshal_binary(&hex::decode(private_hex.trim())?)?;
+            private
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let pair = new_key_pair(&SuiteEd25519::new_blake3_sha256_ed25519())?;
+            std::fs::write(key_file, hex::encode(pair.private.marshal_binary()?))?;
+            pair.private
+        }
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Pair {
+        private,
+        public: Point::default().mul(&private, None),
+    })
+}
 
-    let rt = tokio::runtime::Runtime::new()?;
+fn new_committee(args: NewCommitteeArgs) -> Result<()> {
+    let transport = ChunkedTransport::new(IotaTransport::new(&args.node_url)?);
 
-    nodes = nodes
+    let nodes = args
+        .nodes
         .split(',')
-        .map(|d| format!("\"did:iota:rms:{d}\""))
-        .collect::<Vec<String>>()
-        .join(",");
-
-    let request = format!("{{\"nodes\": [{nodes}]}}").as_bytes().to_owned();
-
-    let result = rt.block_on(publisher.publish(&request, Some(args.governor_tag)))?;
+        .map(|d| format!("did:iota:rms:{d}"))
+        .collect();
+
+    // the nodes take the committee's configuration from the governor, so that they all use the
+    // same one
+    let request = serde_json::to_vec(&DkgInit {
+        nodes,
+        config: CommitteeConfig {
+            audit_interval: args.audit_interval,
+            gc_interval: args.gc_interval,
+            repair_interval: args.repair_interval,
+            quotas: Quotas {
+                max_object_size: args.max_object_size,
+                max_objects: args.max_objects,
+                max_bytes: args.max_bytes,
+                requester_max_objects: args.requester_max_objects,
+                requester_max_bytes: args.requester_max_bytes,
+            },
+        },
+    })?;
+
+    let result = transport.publish(&request, Some(args.governor_tag))?;
     println!("{result}");
     Ok(())
 }
@@ -230,10 +650,8 @@ fn new_committee(args: NewCommitteeArgs) -> Result<()> {
 fn send_message(args: SendArgs) -> Result<()> {
     let message = args.message.as_bytes().to_owned();
 
-    let publisher = Publisher::new(&args.node_url)?;
-
-    let rt = tokio::runtime::Runtime::new()?;
-    let result = rt.block_on(publisher.publish(&message, Some(args.tag)))?;
+    let transport = ChunkedTransport::new(IotaTransport::new(&args.node_url)?);
+    let result = transport.publish(&message, Some(args.tag))?;
     println!("{result}");
     Ok(())
 }
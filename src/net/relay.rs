@@ -1,5 +1,4 @@
 use anyhow::{Error, Result};
-use iota_client::block::BlockId;
 use std::{
     fmt::Display,
     io::{self, Read, Write},
@@ -15,7 +14,7 @@ use std::{
 
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::dlt::iota::{Listener, Publisher};
+use crate::dlt::Transport;
 
 use super::{
     channel::{Receiver, Sender},
@@ -81,31 +80,35 @@ impl<T: DeserializeOwned + Display, S: Sender<T>> ListenRelay<T, S> {
     }
 }
 
-pub struct IotaListenRelay<T, S: Sender<T>> {
+pub struct TransportListenRelay<T, S: Sender<T>> {
     output: S,
     is_closed: Arc<AtomicBool>,
     tags: Vec<String>,
-    node_url: String,
+    transport: Arc<dyn Transport>,
     _phantom_data: PhantomData<T>,
 }
 
-impl<T: DeserializeOwned + Display, S: Sender<T> + 'static> IotaListenRelay<T, S> {
-    pub fn new(output: S, is_closed: Arc<AtomicBool>, tags: Vec<String>, node_url: String) -> Self {
+impl<T: DeserializeOwned + Display, S: Sender<T> + 'static> TransportListenRelay<T, S> {
+    pub fn new(
+        output: S,
+        is_closed: Arc<AtomicBool>,
+        tags: Vec<String>,
+        transport: Arc<dyn Transport>,
+    ) -> Self {
         Self {
             output,
             is_closed,
             tags,
-            node_url,
+            transport,
             _phantom_data: PhantomData,
         }
     }
 
     pub fn listen(&self) -> Result<()> {
-        let mut listener = Listener::new(&self.node_url)?;
-        let receivers: Vec<std::sync::mpsc::Receiver<(Vec<u8>, BlockId)>> = self
+        let receivers: Vec<std::sync::mpsc::Receiver<(Vec<u8>, String)>> = self
             .tags
             .iter()
-            .map(|i| tokio::runtime::Runtime::new()?.block_on(listener.start(i.to_string())))
+            .map(|i| self.transport.subscribe(i.to_string()))
             .collect::<Result<Vec<_>>>()?;
 
         let mut handles = Vec::new();
@@ -176,22 +179,21 @@ impl<T: Serialize, R: Receiver<T>> BroadcastRelay<T, R> {
     }
 }
 
-pub struct IotaBroadcastRelay<T, R: Receiver<T>> {
+pub struct TransportBroadcastRelay<T, R: Receiver<T>> {
     input: R,
     tag: String,
-    publisher: Publisher,
+    transport: Arc<dyn Transport>,
     _phantom: PhantomData<T>,
 }
 
-impl<T: Serialize, R: Receiver<T>> IotaBroadcastRelay<T, R> {
-    pub fn new(tag: String, input: R, node_url: String) -> Result<Self> {
-        let publisher = Publisher::new(&node_url)?;
-        Ok(IotaBroadcastRelay {
+impl<T: Serialize, R: Receiver<T>> TransportBroadcastRelay<T, R> {
+    pub fn new(tag: String, input: R, transport: Arc<dyn Transport>) -> Self {
+        TransportBroadcastRelay {
             input,
             tag,
-            publisher,
+            transport,
             _phantom: PhantomData,
-        })
+        }
     }
 
     pub fn broadcast(&mut self) -> Result<()> {
@@ -209,9 +211,7 @@ impl<T: Serialize, R: Receiver<T>> IotaBroadcastRelay<T, R> {
             let serialized = serde_json::to_string(&message)?;
 
             let tag = self.tag.clone();
-            tokio::runtime::Runtime::new()
-                .unwrap()
-                .block_on(self.publisher.publish(serialized.as_bytes(), Some(tag)))?;
+            self.transport.publish(serialized.as_bytes(), Some(tag))?;
         }
     }
 }
@@ -0,0 +1,79 @@
+use std::{future::Future, panic, thread};
+
+use anyhow::Result;
+use tokio::{
+    runtime::{Handle, Runtime},
+    task::JoinHandle,
+};
+
+/// Runtime of the async clients called by the node's blocking code. Blocking on (or dropping)
+/// a runtime from an async context panics, e.g. in the handlers of the HTTP gateway, so
+/// futures are then run on the runtime from another thread.
+pub(crate) struct BlockingRuntime(Option<Runtime>);
+
+impl BlockingRuntime {
+    pub fn new() -> Result<Self> {
+        Ok(Self(Some(Runtime::new()?)))
+    }
+
+    pub fn block_on<F>(&self, future: F) -> F::Output
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        let runtime = self.0.as_ref().expect("the runtime is only taken on drop");
+        match Handle::try_current() {
+            Ok(_) => thread::scope(|scope| {
+                scope
+                    .spawn(|| runtime.block_on(future))
+                    .join()
+                    .unwrap_or_else(|e| panic::resume_unwind(e))
+            }),
+            Err(_) => runtime.block_on(future),
+        }
+    }
+
+    /// Runs the future in the background, e.g. a request reading what the caller is sending.
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let runtime = self.0.as_ref().expect("the runtime is only taken on drop");
+        runtime.spawn(future)
+    }
+}
+
+impl Drop for BlockingRuntime {
+    fn drop(&mut self) {
+        if let (Some(runtime), Ok(_)) = (self.0.take(), Handle::try_current()) {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_on_futures_outside_an_async_context() {
+        let runtime = BlockingRuntime::new().unwrap();
+        assert_eq!(runtime.block_on(async { 1 }), 1);
+    }
+
+    #[tokio::test]
+    async fn blocks_on_futures_and_drops_inside_an_async_context() {
+        let runtime = BlockingRuntime::new().unwrap();
+        assert_eq!(runtime.block_on(async { 1 }), 1);
+        assert_eq!(runtime.spawn(async { 2 }).await.unwrap(), 2);
+        drop(runtime);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "failed")]
+    async fn panics_are_propagated_to_the_caller() {
+        let runtime = BlockingRuntime::new().unwrap();
+        runtime.block_on(async { panic!("failed") });
+    }
+}
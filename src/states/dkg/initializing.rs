@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, sync::Arc};
 
 use anyhow::Error;
 use kyber_rs::{
@@ -8,7 +8,7 @@ use kyber_rs::{
 };
 
 use crate::{
-    did::resolve_document,
+    did::DidRegistry,
     states::fsm::{DeliveryStatus, State, Transition},
 };
 
@@ -20,7 +20,7 @@ pub struct Initializing {
     num_participants: usize,
     public_keys: Vec<Point>,
     did_urls: Vec<String>,
-    node_url: String,
+    registry: Arc<dyn DidRegistry>,
 }
 
 impl Display for Initializing {
@@ -34,7 +34,7 @@ impl Initializing {
         key: Pair<Point>,
         did_url: Option<String>,
         num_participants: usize,
-        node_url: String,
+        registry: Arc<dyn DidRegistry>,
     ) -> Initializing {
         let mut public_keys = Vec::with_capacity(num_participants);
         public_keys.push(key.public);
@@ -48,7 +48,7 @@ impl Initializing {
             num_participants,
             public_keys,
             did_urls,
-            node_url,
+            registry,
         }
     }
 }
@@ -72,7 +72,8 @@ impl State<DkgTypes> for Initializing {
             DkgMessage::DIDUrl(did_url) => {
                 self.did_urls.push(did_url.clone());
                 self.public_keys.push(
-                    resolve_document(did_url, &self.node_url)
+                    self.registry
+                        .resolve_document(&did_url)
                         .unwrap()
                         .public_key()
                         .unwrap(),
@@ -1,4 +1,8 @@
 pub mod dkg;
 pub mod feed;
 pub mod fsm;
+pub mod oracle;
+pub mod reveal;
+pub mod shards;
 pub mod sign;
+pub mod sync;
@@ -0,0 +1,204 @@
+use anyhow::Result;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    sync::mpsc::Sender,
+    thread,
+};
+
+use kyber_rs::{
+    group::edwards25519::Point,
+    sign::eddsa::{self, EdDSA},
+    util::key::Pair,
+};
+
+use crate::states::{
+    feed::MessageWrapper,
+    fsm::{DeliveryStatus, State, Transition},
+};
+
+use super::{
+    collecting::{log_target, ObservationParams},
+    messages::{proposal_bytes, Observation, OracleMessage, Proposal},
+    OracleTerminalStates, OracleTypes,
+};
+
+enum WaitingState {
+    Waiting,
+    Done,
+}
+
+/// Broadcasts the observations collected by the node and collects the proposals of its
+/// peers, until every participant proposed or the waiting time is over. Nodes may have
+/// collected different observations before their waiting time was over, so the committee
+/// aggregates the union of the observations of the valid proposals: the agreed observations
+/// only depend on the proposals received, not on who proposed them. A proposal is valid if it
+/// holds observations signed by distinct participants, and participants that signed different
+/// values are left out.
+pub struct Agreeing {
+    session_id: String,
+    proposal: Proposal,
+    participants: Vec<Point>,
+    /// proposals received from the participants, `None` if not valid
+    proposals: HashMap<String, Option<Vec<Observation>>>,
+    threshold: usize,
+    waiting: WaitingState,
+    sender: Sender<MessageWrapper<OracleMessage>>,
+    sleep_time: u64,
+}
+
+impl Agreeing {
+    pub fn new(
+        keypair: Pair<Point>,
+        session_id: String,
+        observations: Vec<Observation>,
+        observation_params: ObservationParams,
+    ) -> Result<Self> {
+        let signature =
+            EdDSA::from(keypair.clone()).sign(&proposal_bytes(&session_id, &observations)?)?;
+        let proposal = Proposal {
+            owner: keypair.public,
+            observations,
+            signature: signature.to_vec(),
+        };
+        let mut agreeing = Self {
+            session_id,
+            proposal: proposal.clone(),
+            participants: observation_params.participants,
+            proposals: HashMap::new(),
+            threshold: observation_params.threshold,
+            waiting: WaitingState::Waiting,
+            sender: observation_params.sender,
+            sleep_time: observation_params.sleep_time,
+        };
+        agreeing.add_proposal(proposal);
+        Ok(agreeing)
+    }
+
+    fn add_proposal(&mut self, proposal: Proposal) {
+        let owner = proposal.owner.to_string();
+        if self.proposals.contains_key(&owner) || !self.participants.contains(&proposal.owner) {
+            return;
+        }
+        let is_signed = proposal_bytes(&self.session_id, &proposal.observations)
+            .map(|bytes| eddsa::verify(&proposal.owner, &bytes, &proposal.signature).is_ok())
+            .unwrap_or(false);
+        if !is_signed {
+            log::warn!(target: &log_target(&self.session_id),
+                "discarding proposal with an invalid signature from {}", proposal.owner.to_string());
+            return;
+        }
+        let observations = self
+            .is_valid(&proposal.observations)
+            .then_some(proposal.observations);
+        if observations.is_none() {
+            log::warn!(target: &log_target(&self.session_id),
+                "discarding invalid proposal from {}", proposal.owner.to_string());
+        }
+        self.proposals.insert(owner, observations);
+    }
+
+    /// Whether the observations are signed by distinct participants.
+    fn is_valid(&self, observations: &[Observation]) -> bool {
+        let mut owners = vec![];
+        for observation in observations {
+            if owners.contains(&observation.owner)
+                || !self.participants.contains(&observation.owner)
+                || !observation.verify(&self.session_id)
+            {
+                return false;
+            }
+            owners.push(observation.owner);
+        }
+        true
+    }
+
+    /// Observations of the valid proposals sorted by owner, `None` while a participant may
+    /// still send its proposal.
+    fn agreed(&self) -> Option<Vec<Observation>> {
+        if let WaitingState::Waiting = self.waiting {
+            if self.proposals.len() < self.participants.len() {
+                return None;
+            }
+        }
+        let mut observations: BTreeMap<String, Option<Observation>> = BTreeMap::new();
+        for observation in self.proposals.values().flatten().flatten() {
+            observations
+                .entry(observation.owner.to_string())
+                .and_modify(|agreed| {
+                    // the owner signed different values
+                    if agreed.as_ref().is_some_and(|o| {
+                        o.value.map(f64::to_bits) != observation.value.map(f64::to_bits)
+                    }) {
+                        *agreed = None;
+                    }
+                })
+                .or_insert_with(|| Some(observation.clone()));
+        }
+        Some(observations.into_values().flatten().collect())
+    }
+}
+
+impl Display for Agreeing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Agreeing on observations")
+    }
+}
+
+impl State<OracleTypes> for Agreeing {
+    fn initialize(&self) -> Vec<OracleMessage> {
+        let sleep_time = self.sleep_time;
+        let session_id = self.session_id.clone();
+        let sender = self.sender.clone();
+
+        log::trace!(target: &log_target(&self.session_id),
+                    "starting proposals countdown, {} seconds", sleep_time);
+        thread::spawn(move || {
+            // sleeps to give time to the missing nodes
+            std::thread::sleep(std::time::Duration::from_secs(sleep_time));
+            // trigger advance messages in the case that no proposal is received in the meantime
+            sender
+                .send(MessageWrapper {
+                    session_id,
+                    message: OracleMessage::WaitingProposalsDone,
+                })
+                .unwrap();
+        });
+
+        vec![OracleMessage::Proposal(self.proposal.clone())]
+    }
+
+    fn deliver(&mut self, message: OracleMessage) -> DeliveryStatus<OracleMessage> {
+        match message {
+            OracleMessage::Proposal(proposal) => {
+                self.add_proposal(proposal);
+                DeliveryStatus::Delivered
+            }
+            OracleMessage::WaitingProposalsDone => {
+                self.waiting = WaitingState::Done;
+                DeliveryStatus::Delivered
+            }
+            // late observations and the countdown of the observations are not needed anymore
+            OracleMessage::Observation(_) | OracleMessage::WaitingDone => DeliveryStatus::Delivered,
+        }
+    }
+
+    fn advance(&mut self) -> Result<Transition<OracleTypes>, anyhow::Error> {
+        let Some(observations) = self.agreed() else {
+            return Ok(Transition::Same);
+        };
+        let values: Vec<_> = observations
+            .iter()
+            .filter_map(|o| o.value.map(|value| (o.owner, value)))
+            .collect();
+        if values.len() < self.threshold {
+            log::info!(target: &log_target(&self.session_id),
+                "{} agreed values, {} needed", values.len(), self.threshold);
+            return Ok(Transition::Terminal(OracleTerminalStates::Failed));
+        }
+        Ok(Transition::Terminal(OracleTerminalStates::Completed(
+            values,
+        )))
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use std::{fmt::Display, sync::mpsc::Sender, thread};
+
+use kyber_rs::{group::edwards25519::Point, sign::eddsa::EdDSA, util::key::Pair};
+
+use crate::states::{
+    feed::MessageWrapper,
+    fsm::{DeliveryStatus, State, Transition},
+};
+
+use super::{
+    agreeing::Agreeing,
+    messages::{observation_bytes, Observation, OracleMessage},
+    OracleTypes,
+};
+
+enum WaitingState {
+    Waiting,
+    Done,
+}
+
+pub struct ObservationParams {
+    pub participants: Vec<Point>,
+    pub threshold: usize,
+    pub sender: Sender<MessageWrapper<OracleMessage>>,
+    pub sleep_time: u64,
+}
+
+/// Broadcasts the node's own observation and collects the ones of its peers, until every
+/// participant answered or the waiting time is over, then proposes the collected
+/// observations to the committee (see [Agreeing]).
+pub struct Collecting {
+    session_id: String,
+    keypair: Pair<Point>,
+    observation: Observation,
+    participants: Vec<Point>,
+    observations: Vec<Observation>,
+    threshold: usize,
+    waiting: WaitingState,
+    sender: Sender<MessageWrapper<OracleMessage>>,
+    sleep_time: u64,
+}
+
+impl Collecting {
+    pub fn new(
+        keypair: Pair<Point>,
+        session_id: String,
+        value: Option<f64>,
+        observation_params: ObservationParams,
+    ) -> Result<Self> {
+        let signature =
+            EdDSA::from(keypair.clone()).sign(&observation_bytes(&session_id, value))?;
+        let observation = Observation {
+            owner: keypair.public,
+            value,
+            signature: signature.to_vec(),
+        };
+        Ok(Self {
+            session_id,
+            keypair,
+            observations: vec![observation.clone()],
+            observation,
+            participants: observation_params.participants,
+            threshold: observation_params.threshold,
+            waiting: WaitingState::Waiting,
+            sender: observation_params.sender,
+            sleep_time: observation_params.sleep_time,
+        })
+    }
+
+    fn is_valid(&self, observation: &Observation) -> bool {
+        self.participants.contains(&observation.owner) && observation.verify(&self.session_id)
+    }
+
+    fn agreeing(&mut self) -> Result<Transition<OracleTypes>> {
+        let mut observations: Vec<Observation> = self.observations.drain(..).collect();
+        observations.sort_by_key(|o| o.owner.to_string());
+        Ok(Transition::Next(Box::new(Agreeing::new(
+            self.keypair.clone(),
+            self.session_id.clone(),
+            observations,
+            ObservationParams {
+                participants: self.participants.clone(),
+                threshold: self.threshold,
+                sender: self.sender.clone(),
+                sleep_time: self.sleep_time,
+            },
+        )?)))
+    }
+}
+
+impl Display for Collecting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Collecting observations")
+    }
+}
+
+impl State<OracleTypes> for Collecting {
+    fn initialize(&self) -> Vec<OracleMessage> {
+        let sleep_time = self.sleep_time;
+        let session_id = self.session_id.clone();
+        let sender = self.sender.clone();
+
+        log::trace!(target: &log_target(&self.session_id),
+                    "starting observations countdown, {} seconds", sleep_time);
+        thread::spawn(move || {
+            // sleeps to give time to the missing nodes
+            std::thread::sleep(std::time::Duration::from_secs(sleep_time));
+            // trigger advance messages in the case that no observation is received in the meantime
+            sender
+                .send(MessageWrapper {
+                    session_id,
+                    message: OracleMessage::WaitingDone,
+                })
+                .unwrap();
+        });
+
+        vec![OracleMessage::Observation(self.observation.clone())]
+    }
+
+    fn deliver(&mut self, message: OracleMessage) -> DeliveryStatus<OracleMessage> {
+        match message {
+            OracleMessage::Observation(observation) => {
+                let is_duplicate = self
+                    .observations
+                    .iter()
+                    .any(|o| o.owner == observation.owner);
+                if is_duplicate {
+                    return DeliveryStatus::Delivered;
+                }
+                if !self.is_valid(&observation) {
+                    log::warn!(target: &log_target(&self.session_id),
+                        "discarding invalid observation from {}", observation.owner.to_string());
+                    return DeliveryStatus::Delivered;
+                }
+                self.observations.push(observation);
+                DeliveryStatus::Delivered
+            }
+            OracleMessage::WaitingDone => {
+                self.waiting = WaitingState::Done;
+                DeliveryStatus::Delivered
+            }
+            m => DeliveryStatus::Unexpected(m),
+        }
+    }
+
+    fn advance(&mut self) -> Result<Transition<OracleTypes>, anyhow::Error> {
+        match self.waiting {
+            WaitingState::Waiting if self.observations.len() < self.participants.len() => {
+                Ok(Transition::Same)
+            }
+            _ => self.agreeing(),
+        }
+    }
+}
+
+pub(super) fn log_target(session_id: &str) -> String {
+    format!(
+        "fsm:{}:oracle",
+        session_id.chars().take(10).collect::<String>().yellow()
+    )
+}
@@ -0,0 +1,77 @@
+use enum_display::EnumDisplay;
+use kyber_rs::{group::edwards25519::Point, sign::eddsa};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, EnumDisplay, Serialize, Deserialize)]
+pub enum OracleMessage {
+    Observation(Observation),
+    Proposal(Proposal),
+    WaitingDone,
+    WaitingProposalsDone,
+}
+
+/// Value observed by a node, `None` if the node could not extract it from the input.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Observation {
+    pub owner: Point,
+    #[serde(with = "value_bits")]
+    pub value: Option<f64>,
+    pub signature: Vec<u8>,
+}
+
+impl Observation {
+    /// Whether the observation is signed by its owner for the session and its value is finite.
+    pub fn verify(&self, session_id: &str) -> bool {
+        self.value.is_none_or(f64::is_finite)
+            && eddsa::verify(
+                &self.owner,
+                &observation_bytes(session_id, self.value),
+                &self.signature,
+            )
+            .is_ok()
+    }
+}
+
+/// Observations collected by a node, proposed as the ones the committee aggregates, signed
+/// by the node.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Proposal {
+    pub owner: Point,
+    pub observations: Vec<Observation>,
+    pub signature: Vec<u8>,
+}
+
+/// Bytes signed by the owner of an observation, bound to the session so that observations
+/// can't be replayed in other requests. The value is signed as the bits of the float, so that
+/// every node checks the exact value that was observed.
+pub(crate) fn observation_bytes(session_id: &str, value: Option<f64>) -> Vec<u8> {
+    match value {
+        Some(value) => format!("{session_id}:{:016x}", value.to_bits()).into_bytes(),
+        None => format!("{session_id}:none").into_bytes(),
+    }
+}
+
+/// Bytes signed by the owner of a proposal, bound to the session.
+pub(crate) fn proposal_bytes(
+    session_id: &str,
+    observations: &[Observation],
+) -> anyhow::Result<Vec<u8>> {
+    let observations = serde_json::to_string(observations)?;
+    Ok(format!("{session_id}:{observations}").into_bytes())
+}
+
+/// Observed values are sent as the bits of the float, decimal numbers may not round-trip
+/// exactly and the nodes would then aggregate different values.
+mod value_bits {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<f64>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.map(f64::to_bits).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<f64>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(f64::from_bits))
+    }
+}
@@ -0,0 +1,229 @@
+use crate::states::fsm::StateMachineTypes;
+
+mod agreeing;
+mod collecting;
+mod messages;
+
+use kyber_rs::group::edwards25519::Point;
+pub use messages::OracleMessage;
+
+pub use collecting::{Collecting, ObservationParams};
+
+pub struct OracleTypes {}
+
+impl StateMachineTypes for OracleTypes {
+    type Message = OracleMessage;
+    type TerminalStates = OracleTerminalStates;
+}
+
+pub enum OracleTerminalStates {
+    /// the agreed observed values along with their owners, sorted by owner
+    Completed(Vec<(Point, f64)>),
+    Failed,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use kyber_rs::{
+        group::edwards25519::SuiteEd25519,
+        sign::eddsa::EdDSA,
+        util::key::{new_key_pair, Pair},
+    };
+
+    use crate::states::fsm::{BoxedState, Transition};
+
+    use super::{
+        messages::{observation_bytes, proposal_bytes, Observation, Proposal},
+        *,
+    };
+
+    const SESSION_ID: &str = "session";
+
+    fn keypairs(n: usize) -> Vec<Pair<Point>> {
+        let suite = SuiteEd25519::new_blake3_sha256_ed25519();
+        (0..n).map(|_| new_key_pair(&suite).unwrap()).collect()
+    }
+
+    fn observation(keypair: &Pair<Point>, value: Option<f64>) -> Observation {
+        let signature = EdDSA::from(keypair.clone())
+            .sign(&observation_bytes(SESSION_ID, value))
+            .unwrap();
+        Observation {
+            owner: keypair.public,
+            value,
+            signature: signature.to_vec(),
+        }
+    }
+
+    fn proposal(keypair: &Pair<Point>, observations: Vec<Observation>) -> Proposal {
+        let signature = EdDSA::from(keypair.clone())
+            .sign(&proposal_bytes(SESSION_ID, &observations).unwrap())
+            .unwrap();
+        Proposal {
+            owner: keypair.public,
+            observations,
+            signature: signature.to_vec(),
+        }
+    }
+
+    /// Runs the oracle of the honest nodes, which receive the messages of each other and the
+    /// messages that `faulty` returns for them in each round (0 for the observations, 1 for the
+    /// proposals). Participants that send nothing are waited for until the waiting time is
+    /// over. Returns the values agreed by each honest node, `None` if it failed.
+    fn run(
+        participants: &[Point],
+        honest: &[(Pair<Point>, Option<f64>)],
+        threshold: usize,
+        faulty: impl Fn(usize, &Point) -> Vec<OracleMessage>,
+    ) -> Vec<Option<Vec<(Point, f64)>>> {
+        // the countdowns are not awaited, the tests deliver the end of the waiting time
+        let (sender, _countdowns) = channel();
+        let mut states: Vec<BoxedState<OracleTypes>> = honest
+            .iter()
+            .map(|(keypair, value)| {
+                let params = ObservationParams {
+                    participants: participants.to_vec(),
+                    threshold,
+                    sender: sender.clone(),
+                    sleep_time: 3600,
+                };
+                Box::new(
+                    Collecting::new(keypair.clone(), SESSION_ID.to_owned(), *value, params)
+                        .unwrap(),
+                ) as BoxedState<OracleTypes>
+            })
+            .collect();
+
+        let exchange = |states: &mut Vec<BoxedState<OracleTypes>>, round: usize| {
+            let sent: Vec<_> = states.iter().map(|state| state.initialize()).collect();
+            let mut transitions = vec![];
+            for (i, state) in states.iter_mut().enumerate() {
+                let received = sent
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .flat_map(|(_, messages)| messages.clone())
+                    .chain(faulty(round, &honest[i].0.public));
+                for message in received {
+                    state.deliver(message);
+                }
+                let transition = match state.advance().unwrap() {
+                    Transition::Same => {
+                        state.deliver(match round {
+                            0 => OracleMessage::WaitingDone,
+                            _ => OracleMessage::WaitingProposalsDone,
+                        });
+                        state.advance().unwrap()
+                    }
+                    transition => transition,
+                };
+                transitions.push(transition);
+            }
+            transitions
+        };
+
+        states = exchange(&mut states, 0)
+            .into_iter()
+            .map(|transition| match transition {
+                Transition::Next(state) => state,
+                _ => panic!("observations are always proposed"),
+            })
+            .collect();
+        exchange(&mut states, 1)
+            .into_iter()
+            .map(|transition| match transition {
+                Transition::Terminal(OracleTerminalStates::Completed(values)) => Some(values),
+                Transition::Terminal(OracleTerminalStates::Failed) => None,
+                _ => panic!("proposals are always agreed on"),
+            })
+            .collect()
+    }
+
+    fn sorted(values: Vec<(Point, f64)>) -> Vec<(Point, f64)> {
+        let mut values = values;
+        values.sort_by_key(|(owner, _)| owner.to_string());
+        values
+    }
+
+    #[test]
+    fn identical_observations_are_agreed() {
+        let keypairs = keypairs(3);
+        let participants: Vec<_> = keypairs.iter().map(|keypair| keypair.public).collect();
+        let honest: Vec<_> = keypairs.into_iter().map(|k| (k, Some(1.5))).collect();
+
+        let expected = sorted(participants.iter().map(|p| (*p, 1.5)).collect());
+        for agreed in run(&participants, &honest, 2, |_, _| vec![]) {
+            assert_eq!(agreed, Some(expected.clone()));
+        }
+    }
+
+    #[test]
+    fn observations_of_an_equivocating_node_are_left_out() {
+        let mut keypairs = keypairs(4);
+        let faulty = keypairs.pop().unwrap();
+        let participants: Vec<_> = keypairs
+            .iter()
+            .chain([&faulty])
+            .map(|keypair| keypair.public)
+            .collect();
+        let honest: Vec<_> = keypairs
+            .into_iter()
+            .zip([1.0, 2.0, 3.0])
+            .map(|(keypair, value)| (keypair, Some(value)))
+            .collect();
+        let first = honest[0].0.public;
+
+        // the faulty node sends different values to its peers, and proposes its own one only
+        let results = run(&participants, &honest, 3, |round, recipient| {
+            let value = if *recipient == first { 100.0 } else { 200.0 };
+            match round {
+                0 => vec![OracleMessage::Observation(observation(
+                    &faulty,
+                    Some(value),
+                ))],
+                _ => vec![OracleMessage::Proposal(proposal(
+                    &faulty,
+                    vec![observation(&faulty, Some(value))],
+                ))],
+            }
+        });
+
+        let expected = sorted(
+            honest
+                .iter()
+                .map(|(keypair, value)| (keypair.public, value.unwrap()))
+                .collect(),
+        );
+        for agreed in results {
+            assert_eq!(agreed, Some(expected.clone()));
+        }
+    }
+
+    #[test]
+    fn missing_observers_are_waited_for_until_the_waiting_time_is_over() {
+        let mut keypairs = keypairs(3);
+        let missing = keypairs.pop().unwrap();
+        let participants: Vec<_> = keypairs
+            .iter()
+            .chain([&missing])
+            .map(|keypair| keypair.public)
+            .collect();
+        let honest: Vec<_> = keypairs.into_iter().zip([Some(1.0), Some(2.0)]).collect();
+
+        let expected = sorted(
+            honest
+                .iter()
+                .map(|(keypair, value)| (keypair.public, value.unwrap()))
+                .collect(),
+        );
+        for agreed in run(&participants, &honest, 2, |_, _| vec![]) {
+            assert_eq!(agreed, Some(expected.clone()));
+        }
+        // less than a threshold of values
+        for agreed in run(&participants, &honest, 3, |_, _| vec![]) {
+            assert_eq!(agreed, None);
+        }
+    }
+}
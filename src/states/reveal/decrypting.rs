@@ -0,0 +1,249 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use std::{fmt::Display, sync::mpsc::Sender, thread};
+
+use kyber_rs::{
+    group::edwards25519::{Point, Scalar},
+    share::poly::PriShare,
+    sign::eddsa::{self, EdDSA},
+    util::key::Pair,
+    Point as _,
+};
+
+use crate::{
+    api::requests::{DleqProof, SealedData},
+    states::{
+        feed::MessageWrapper,
+        fsm::{DeliveryStatus, State, Transition},
+    },
+};
+
+use super::{
+    messages::{partial_piece_bytes, PartialDecryption, PartialPiece, RevealMessage},
+    RevealTerminalStates, RevealTypes,
+};
+
+enum WaitingState {
+    Waiting,
+    Done,
+}
+
+pub struct DecryptionParams {
+    /// the participants of the DKG, in the DKG's order
+    pub participants: Vec<Point>,
+    /// the public shares `x_i G` of the committee's private key, in the DKG's order
+    pub public_shares: Vec<Point>,
+    pub threshold: usize,
+    pub sender: Sender<MessageWrapper<RevealMessage>>,
+    pub sleep_time: u64,
+}
+
+/// Sends the node's own partial decryption to each of its peers, encrypted to the peer's key,
+/// and collects the ones of its peers, until enough partial decryptions proven against the
+/// public shares of the committee's key are collected or the waiting time is over.
+pub struct Decrypting {
+    session_id: String,
+    keypair: Pair<Point>,
+    ephemeral: Point,
+    pieces: Vec<PartialPiece>,
+    participants: Vec<Point>,
+    public_shares: Vec<Point>,
+    answered: Vec<Point>,
+    partials: Vec<PartialDecryption>,
+    threshold: usize,
+    waiting: WaitingState,
+    sender: Sender<MessageWrapper<RevealMessage>>,
+    sleep_time: u64,
+}
+
+impl Decrypting {
+    pub fn new(
+        keypair: Pair<Point>,
+        session_id: String,
+        share: PriShare<Scalar>,
+        ephemeral: Point,
+        decryption_params: DecryptionParams,
+    ) -> Result<Self> {
+        let partial = PartialDecryption {
+            owner: keypair.public,
+            index: share.i,
+            value: Point::default().mul(&share.v, Some(&ephemeral)),
+            proof: DleqProof::new(&share.v, &ephemeral, session_id.as_bytes())?,
+        };
+        let partial_bytes = serde_json::to_vec(&partial)?;
+        let mut pieces = vec![];
+        for recipient in &decryption_params.participants {
+            if *recipient == keypair.public {
+                continue;
+            }
+            let sealed = SealedData::seal(recipient, &partial_bytes)?;
+            let signature = EdDSA::from(keypair.clone()).sign(&partial_piece_bytes(
+                &session_id,
+                recipient,
+                share.i,
+                &sealed,
+            )?)?;
+            pieces.push(PartialPiece {
+                owner: keypair.public,
+                recipient: *recipient,
+                index: share.i,
+                partial: sealed,
+                signature: signature.to_vec(),
+            });
+        }
+        Ok(Self {
+            session_id,
+            answered: vec![keypair.public],
+            keypair,
+            ephemeral,
+            pieces,
+            participants: decryption_params.participants,
+            public_shares: decryption_params.public_shares,
+            partials: vec![partial],
+            threshold: decryption_params.threshold,
+            waiting: WaitingState::Waiting,
+            sender: decryption_params.sender,
+            sleep_time: decryption_params.sleep_time,
+        })
+    }
+
+    fn collect(&mut self, piece: PartialPiece) {
+        // pieces are broadcast, only the ones encrypted to this node can be opened
+        if piece.recipient != self.keypair.public
+            || self.answered.contains(&piece.owner)
+            // shares are indexed as the participants of the DKG
+            || self.participants.get(piece.index) != Some(&piece.owner)
+        {
+            return;
+        }
+        let bytes = match partial_piece_bytes(
+            &self.session_id,
+            &piece.recipient,
+            piece.index,
+            &piece.partial,
+        ) {
+            Ok(bytes) => bytes,
+            Err(_) => return,
+        };
+        if eddsa::verify(&piece.owner, &bytes, &piece.signature).is_err() {
+            log::warn!(target: &log_target(&self.session_id),
+                "discarding partial decryption with an invalid signature from {}", piece.owner.to_string());
+            return;
+        }
+        self.answered.push(piece.owner);
+        let partial = match piece
+            .partial
+            .open_with_key(&self.keypair.private)
+            .ok()
+            .and_then(|partial| serde_json::from_slice::<PartialDecryption>(&partial).ok())
+        {
+            Some(partial) => partial,
+            None => return,
+        };
+        if !self.is_valid(&piece, &partial) {
+            log::warn!(target: &log_target(&self.session_id),
+                "discarding invalid partial decryption from {}", piece.owner.to_string());
+            return;
+        }
+        self.partials.push(partial);
+    }
+
+    /// Whether the partial decryption is the piece's one and was computed with the share of
+    /// the committee's key of its owner.
+    fn is_valid(&self, piece: &PartialPiece, partial: &PartialDecryption) -> bool {
+        let public_share = match self.public_shares.get(partial.index) {
+            Some(public_share) => public_share,
+            None => return false,
+        };
+        partial.owner == piece.owner
+            && partial.index == piece.index
+            && partial
+                .proof
+                .verify(
+                    public_share,
+                    &self.ephemeral,
+                    &partial.value,
+                    self.session_id.as_bytes(),
+                )
+                .is_ok()
+    }
+
+    fn completed(&self) -> RevealTerminalStates {
+        let mut partials = self.partials.clone();
+        partials.sort_by_key(|p| p.index);
+        RevealTerminalStates::Completed(partials)
+    }
+}
+
+impl Display for Decrypting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Collecting partial decryptions")
+    }
+}
+
+impl State<RevealTypes> for Decrypting {
+    fn initialize(&self) -> Vec<RevealMessage> {
+        let sleep_time = self.sleep_time;
+        let session_id = self.session_id.clone();
+        let sender = self.sender.clone();
+
+        log::trace!(target: &log_target(&self.session_id),
+                    "starting partial decryptions countdown, {} seconds", sleep_time);
+        thread::spawn(move || {
+            // sleeps to give time to the missing nodes
+            std::thread::sleep(std::time::Duration::from_secs(sleep_time));
+            // trigger advance messages in the case that no partial decryption is received in the meantime
+            sender
+                .send(MessageWrapper {
+                    session_id,
+                    message: RevealMessage::WaitingDone,
+                })
+                .unwrap();
+        });
+
+        self.pieces
+            .iter()
+            .cloned()
+            .map(|piece| RevealMessage::PartialPiece(Box::new(piece)))
+            .collect()
+    }
+
+    fn deliver(&mut self, message: RevealMessage) -> DeliveryStatus<RevealMessage> {
+        match message {
+            RevealMessage::PartialPiece(piece) => {
+                self.collect(*piece);
+                DeliveryStatus::Delivered
+            }
+            RevealMessage::WaitingDone => {
+                self.waiting = WaitingState::Done;
+                DeliveryStatus::Delivered
+            }
+        }
+    }
+
+    fn advance(&mut self) -> Result<Transition<RevealTypes>, anyhow::Error> {
+        // the own partial decryption was already sent, peers don't need this node to keep
+        // listening
+        if self.partials.len() >= self.threshold {
+            return Ok(Transition::Terminal(self.completed()));
+        }
+        match self.waiting {
+            WaitingState::Waiting if self.answered.len() < self.participants.len() => {
+                Ok(Transition::Same)
+            }
+            _ => {
+                log::info!(target: &log_target(&self.session_id),
+                    "partial decryptions timeout, {} valid ones received", self.partials.len());
+                Ok(Transition::Terminal(RevealTerminalStates::Failed))
+            }
+        }
+    }
+}
+
+fn log_target(session_id: &str) -> String {
+    format!(
+        "fsm:{}:reveal",
+        session_id.chars().take(10).collect::<String>().yellow()
+    )
+}
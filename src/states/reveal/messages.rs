@@ -0,0 +1,46 @@
+use enum_display::EnumDisplay;
+use kyber_rs::{encoding::BinaryMarshaler, group::edwards25519::Point};
+use serde::{Deserialize, Serialize};
+
+use crate::api::requests::{DleqProof, SealedData};
+
+#[derive(Clone, EnumDisplay, Serialize, Deserialize)]
+pub enum RevealMessage {
+    PartialPiece(Box<PartialPiece>),
+    WaitingDone,
+}
+
+/// Share `x_i R` of the shared point of some sealed data, computed by the owner of the `i`-th
+/// share of the committee's private key, along with the proof that it was computed with the
+/// share.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartialDecryption {
+    pub owner: Point,
+    pub index: usize,
+    pub value: Point,
+    pub proof: DleqProof,
+}
+
+/// Partial decryption of a node, encrypted to one of its peers, so that only the committee
+/// can combine the partial decryptions, and signed by the node.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartialPiece {
+    pub owner: Point,
+    pub recipient: Point,
+    pub index: usize,
+    pub partial: SealedData,
+    pub signature: Vec<u8>,
+}
+
+/// Bytes signed by the owner of a partial piece, bound to the session so that partial
+/// decryptions can't be replayed in other requests.
+pub(crate) fn partial_piece_bytes(
+    session_id: &str,
+    recipient: &Point,
+    index: usize,
+    partial: &SealedData,
+) -> anyhow::Result<Vec<u8>> {
+    let recipient = hex::encode(recipient.marshal_binary()?);
+    let partial = serde_json::to_string(partial)?;
+    Ok(format!("{session_id}:{recipient}:{index}:{partial}").into_bytes())
+}
@@ -0,0 +1,21 @@
+use crate::states::fsm::StateMachineTypes;
+
+mod decrypting;
+mod messages;
+
+pub use messages::{PartialDecryption, RevealMessage};
+
+pub use decrypting::{Decrypting, DecryptionParams};
+
+pub struct RevealTypes {}
+
+impl StateMachineTypes for RevealTypes {
+    type Message = RevealMessage;
+    type TerminalStates = RevealTerminalStates;
+}
+
+pub enum RevealTerminalStates {
+    /// the proven partial decryptions along with their owners, sorted by share index
+    Completed(Vec<PartialDecryption>),
+    Failed,
+}
@@ -0,0 +1,233 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use std::{collections::BTreeMap, fmt::Display, sync::mpsc::Sender, thread};
+
+use kyber_rs::{
+    group::edwards25519::Point,
+    sign::eddsa::{self, EdDSA},
+    util::key::Pair,
+};
+
+use crate::{
+    api::requests::{messages::DigestAlgorithm, SealedData},
+    states::{
+        feed::MessageWrapper,
+        fsm::{DeliveryStatus, State, Transition},
+    },
+};
+
+use super::{
+    messages::{shard_piece_bytes, ShardMessage, ShardPiece},
+    ShardTerminalStates, ShardTypes,
+};
+
+enum WaitingState {
+    Waiting,
+    Done,
+}
+
+pub struct GatheringParams {
+    pub participants: Vec<Point>,
+    /// number of shards needed to reconstruct the object
+    pub threshold: usize,
+    /// Blake2b-256 digests of the shards of the object, by shard index
+    pub shard_digests: Vec<String>,
+    pub sender: Sender<MessageWrapper<ShardMessage>>,
+    pub sleep_time: u64,
+}
+
+/// Sends the node's own shard to each of its peers, encrypted to the peer's key, and gathers
+/// the ones of its peers, until enough valid shards to reconstruct the object are gathered or
+/// the waiting time is over. Shards are checked against the digests recorded when the object
+/// was stored.
+pub struct Gathering {
+    session_id: String,
+    keypair: Pair<Point>,
+    pieces: Vec<ShardPiece>,
+    participants: Vec<Point>,
+    answered: Vec<Point>,
+    shards: BTreeMap<usize, Vec<u8>>,
+    threshold: usize,
+    shard_digests: Vec<String>,
+    waiting: WaitingState,
+    sender: Sender<MessageWrapper<ShardMessage>>,
+    sleep_time: u64,
+}
+
+impl Gathering {
+    pub fn new(
+        keypair: Pair<Point>,
+        session_id: String,
+        index: usize,
+        shard: Option<Vec<u8>>,
+        gathering_params: GatheringParams,
+    ) -> Result<Self> {
+        let mut pieces = vec![];
+        for recipient in &gathering_params.participants {
+            if *recipient == keypair.public {
+                continue;
+            }
+            let sealed = shard
+                .as_ref()
+                .map(|shard| SealedData::seal(recipient, shard))
+                .transpose()?;
+            let signature = EdDSA::from(keypair.clone()).sign(&shard_piece_bytes(
+                &session_id,
+                recipient,
+                index,
+                &sealed,
+            )?)?;
+            pieces.push(ShardPiece {
+                owner: keypair.public,
+                recipient: *recipient,
+                index,
+                shard: sealed,
+                signature: signature.to_vec(),
+            });
+        }
+        let mut gathering = Self {
+            session_id,
+            keypair,
+            pieces,
+            participants: gathering_params.participants,
+            answered: vec![],
+            shards: BTreeMap::new(),
+            threshold: gathering_params.threshold,
+            shard_digests: gathering_params.shard_digests,
+            waiting: WaitingState::Waiting,
+            sender: gathering_params.sender,
+            sleep_time: gathering_params.sleep_time,
+        };
+        gathering.answered.push(gathering.keypair.public);
+        if let Some(shard) = shard {
+            gathering.add_shard(index, shard);
+        }
+        Ok(gathering)
+    }
+
+    fn gather(&mut self, piece: ShardPiece) {
+        // pieces are broadcast, only the ones encrypted to this node can be opened
+        if piece.recipient != self.keypair.public
+            || self.answered.contains(&piece.owner)
+            || !self.participants.contains(&piece.owner)
+        {
+            return;
+        }
+        let bytes = match shard_piece_bytes(
+            &self.session_id,
+            &piece.recipient,
+            piece.index,
+            &piece.shard,
+        ) {
+            Ok(bytes) => bytes,
+            Err(_) => return,
+        };
+        if eddsa::verify(&piece.owner, &bytes, &piece.signature).is_err() {
+            log::warn!(target: &log_target(&self.session_id),
+                "discarding shard with an invalid signature from {}", piece.owner.to_string());
+            return;
+        }
+        self.answered.push(piece.owner);
+        let shard = match piece
+            .shard
+            .map(|sealed| sealed.open_with_key(&self.keypair.private))
+        {
+            Some(Ok(shard)) => shard,
+            Some(Err(_)) | None => return,
+        };
+        if !self.add_shard(piece.index, shard) {
+            log::warn!(target: &log_target(&self.session_id),
+                "discarding invalid shard from {}", piece.owner.to_string());
+        }
+    }
+
+    /// Keeps the shard if it matches the digest recorded for its index.
+    fn add_shard(&mut self, index: usize, shard: Vec<u8>) -> bool {
+        if self.shard_digests.get(index) != Some(&DigestAlgorithm::Blake2b256.digest(&shard)) {
+            return false;
+        }
+        self.shards.insert(index, shard);
+        true
+    }
+
+    fn completed(&self) -> ShardTerminalStates {
+        ShardTerminalStates::Completed(
+            self.shards
+                .iter()
+                .map(|(index, shard)| (*index, shard.clone()))
+                .collect(),
+        )
+    }
+}
+
+impl Display for Gathering {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Gathering shards")
+    }
+}
+
+impl State<ShardTypes> for Gathering {
+    fn initialize(&self) -> Vec<ShardMessage> {
+        let sleep_time = self.sleep_time;
+        let session_id = self.session_id.clone();
+        let sender = self.sender.clone();
+
+        log::trace!(target: &log_target(&self.session_id),
+                    "starting shards countdown, {} seconds", sleep_time);
+        thread::spawn(move || {
+            // sleeps to give time to the missing nodes
+            std::thread::sleep(std::time::Duration::from_secs(sleep_time));
+            // trigger advance messages in the case that no shard is received in the meantime
+            sender
+                .send(MessageWrapper {
+                    session_id,
+                    message: ShardMessage::WaitingDone,
+                })
+                .unwrap();
+        });
+
+        self.pieces
+            .iter()
+            .cloned()
+            .map(|piece| ShardMessage::ShardPiece(Box::new(piece)))
+            .collect()
+    }
+
+    fn deliver(&mut self, message: ShardMessage) -> DeliveryStatus<ShardMessage> {
+        match message {
+            ShardMessage::ShardPiece(piece) => {
+                self.gather(*piece);
+                DeliveryStatus::Delivered
+            }
+            ShardMessage::WaitingDone => {
+                self.waiting = WaitingState::Done;
+                DeliveryStatus::Delivered
+            }
+        }
+    }
+
+    fn advance(&mut self) -> Result<Transition<ShardTypes>, anyhow::Error> {
+        // the own shard was already sent, peers don't need this node to keep listening
+        if self.shards.len() >= self.threshold {
+            return Ok(Transition::Terminal(self.completed()));
+        }
+        match self.waiting {
+            WaitingState::Waiting if self.answered.len() < self.participants.len() => {
+                Ok(Transition::Same)
+            }
+            _ => {
+                log::info!(target: &log_target(&self.session_id),
+                    "shards timeout, {} valid shards gathered", self.shards.len());
+                Ok(Transition::Terminal(ShardTerminalStates::Failed))
+            }
+        }
+    }
+}
+
+fn log_target(session_id: &str) -> String {
+    format!(
+        "fsm:{}:shards",
+        session_id.chars().take(10).collect::<String>().yellow()
+    )
+}
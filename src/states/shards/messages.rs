@@ -0,0 +1,36 @@
+use enum_display::EnumDisplay;
+use kyber_rs::{encoding::BinaryMarshaler, group::edwards25519::Point};
+use serde::{Deserialize, Serialize};
+
+use crate::api::requests::SealedData;
+
+#[derive(Clone, EnumDisplay, Serialize, Deserialize)]
+pub enum ShardMessage {
+    ShardPiece(Box<ShardPiece>),
+    WaitingDone,
+}
+
+/// Shard of an object kept by a node, encrypted to one of its peers, `None` if the node lost
+/// it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShardPiece {
+    pub owner: Point,
+    /// node the shard is encrypted to
+    pub recipient: Point,
+    pub index: usize,
+    pub shard: Option<SealedData>,
+    pub signature: Vec<u8>,
+}
+
+/// Bytes signed by the owner of a shard piece, bound to the session and to the recipient so
+/// that pieces can't be replayed in other requests nor claimed by other nodes.
+pub(crate) fn shard_piece_bytes(
+    session_id: &str,
+    recipient: &Point,
+    index: usize,
+    shard: &Option<SealedData>,
+) -> anyhow::Result<Vec<u8>> {
+    let recipient = hex::encode(recipient.marshal_binary()?);
+    let shard = serde_json::to_string(shard)?;
+    Ok(format!("{session_id}:{recipient}:{index}:{shard}").into_bytes())
+}
@@ -0,0 +1,21 @@
+use crate::states::fsm::StateMachineTypes;
+
+mod gathering;
+mod messages;
+
+pub use messages::ShardMessage;
+
+pub use gathering::{Gathering, GatheringParams};
+
+pub struct ShardTypes {}
+
+impl StateMachineTypes for ShardTypes {
+    type Message = ShardMessage;
+    type TerminalStates = ShardTerminalStates;
+}
+
+pub enum ShardTerminalStates {
+    /// the valid shards gathered from the committee, by shard index
+    Completed(Vec<(usize, Vec<u8>)>),
+    Failed,
+}
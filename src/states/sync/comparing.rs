@@ -0,0 +1,113 @@
+use std::{fmt::Display, sync::mpsc::Sender, thread};
+
+use kyber_rs::{group::edwards25519::Point, util::key::Pair};
+
+use crate::states::{
+    feed::MessageWrapper,
+    fsm::{DeliveryStatus, State, Transition},
+};
+
+use super::{
+    indexing::{log_target, SyncParams},
+    messages::{RootPiece, RootRequest, SyncMessage},
+    SyncTerminalStates, SyncTypes,
+};
+
+enum WaitingState {
+    Waiting,
+    Done,
+}
+
+/// Requests the Merkle root of the objects kept by the peers at a repair epoch and collects
+/// their signed answers, until every peer answered or the waiting time is over.
+pub struct Comparing {
+    session_id: String,
+    request: RootRequest,
+    peers: Vec<Point>,
+    pieces: Vec<RootPiece>,
+    waiting: WaitingState,
+    sender: Sender<MessageWrapper<SyncMessage>>,
+    sleep_time: u64,
+}
+
+impl Comparing {
+    pub fn new(
+        keypair: &Pair<Point>,
+        session_id: String,
+        committee_did: String,
+        epoch: u64,
+        sync_params: SyncParams,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            request: RootRequest::new(keypair, &session_id, committee_did, epoch)?,
+            session_id,
+            peers: sync_params.peers,
+            pieces: vec![],
+            waiting: WaitingState::Waiting,
+            sender: sync_params.sender,
+            sleep_time: sync_params.sleep_time,
+        })
+    }
+}
+
+impl Display for Comparing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Comparing Merkle roots")
+    }
+}
+
+impl State<SyncTypes> for Comparing {
+    fn initialize(&self) -> Vec<SyncMessage> {
+        let sleep_time = self.sleep_time;
+        let session_id = self.session_id.clone();
+        let sender = self.sender.clone();
+
+        log::trace!(target: &log_target(&self.session_id),
+                    "starting roots countdown, {} seconds", sleep_time);
+        thread::spawn(move || {
+            // sleeps to give time to the slow peers
+            std::thread::sleep(std::time::Duration::from_secs(sleep_time));
+            // trigger advance messages in the case that no root is received in the meantime
+            sender
+                .send(MessageWrapper {
+                    session_id,
+                    message: SyncMessage::WaitingDone,
+                })
+                .unwrap();
+        });
+
+        vec![SyncMessage::RootRequest(self.request.clone())]
+    }
+
+    fn deliver(&mut self, message: SyncMessage) -> DeliveryStatus<SyncMessage> {
+        match message {
+            SyncMessage::RootPiece(piece) => {
+                let is_duplicate = self.pieces.iter().any(|p| p.owner == piece.owner);
+                if !is_duplicate
+                    && self.peers.contains(&piece.owner)
+                    && piece.verify(&self.session_id)
+                {
+                    self.pieces.push(piece);
+                }
+                DeliveryStatus::Delivered
+            }
+            SyncMessage::WaitingDone => {
+                self.waiting = WaitingState::Done;
+                DeliveryStatus::Delivered
+            }
+            m => DeliveryStatus::Unexpected(m),
+        }
+    }
+
+    fn advance(&mut self) -> Result<Transition<SyncTypes>, anyhow::Error> {
+        match self.waiting {
+            WaitingState::Waiting if self.pieces.len() < self.peers.len() => Ok(Transition::Same),
+            _ => Ok(Transition::Terminal(SyncTerminalStates::Compared(
+                self.pieces
+                    .drain(..)
+                    .filter_map(|piece| piece.root)
+                    .collect(),
+            ))),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::{fmt::Display, sync::mpsc::Sender, thread};
+
+use kyber_rs::group::edwards25519::{Point, Scalar};
+
+use crate::{
+    api::requests::messages::DigestAlgorithm,
+    states::{
+        feed::MessageWrapper,
+        fsm::{DeliveryStatus, State, Transition},
+    },
+};
+
+use super::{
+    indexing::{log_target, SyncParams},
+    messages::{ObjectRequest, SyncMessage},
+    SyncTerminalStates, SyncTypes,
+};
+
+enum WaitingState {
+    Waiting,
+    Done,
+}
+
+/// Requests a version of an object from the peers and collects their copies, encrypted to
+/// the node's key. When the Blake2b-256 digest of the version is known, the first matching
+/// copy completes the fetching; otherwise copies are collected until every peer answered or
+/// the waiting time is over.
+pub struct Fetching {
+    session_id: String,
+    private_key: Scalar,
+    request: ObjectRequest,
+    digest: Option<String>,
+    peers: Vec<Point>,
+    answered: Vec<Point>,
+    copies: Vec<Vec<u8>>,
+    waiting: WaitingState,
+    sender: Sender<MessageWrapper<SyncMessage>>,
+    sleep_time: u64,
+}
+
+impl Fetching {
+    /// The request must be signed by the node owning the private key (see [ObjectRequest::new]).
+    pub fn new(
+        session_id: String,
+        private_key: Scalar,
+        request: ObjectRequest,
+        digest: Option<String>,
+        sync_params: SyncParams,
+    ) -> Self {
+        Self {
+            session_id,
+            private_key,
+            request,
+            digest,
+            peers: sync_params.peers,
+            answered: vec![],
+            copies: vec![],
+            waiting: WaitingState::Waiting,
+            sender: sync_params.sender,
+            sleep_time: sync_params.sleep_time,
+        }
+    }
+
+    fn matching_copy(&self) -> Option<&Vec<u8>> {
+        let digest = self.digest.as_ref()?;
+        self.copies
+            .iter()
+            .find(|copy| &DigestAlgorithm::Blake2b256.digest(copy) == digest)
+    }
+}
+
+impl Display for Fetching {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Fetching object")
+    }
+}
+
+impl State<SyncTypes> for Fetching {
+    fn initialize(&self) -> Vec<SyncMessage> {
+        let sleep_time = self.sleep_time;
+        let session_id = self.session_id.clone();
+        let sender = self.sender.clone();
+
+        log::trace!(target: &log_target(&self.session_id),
+                    "starting fetching countdown, {} seconds", sleep_time);
+        thread::spawn(move || {
+            // sleeps to give time to the slow peers
+            std::thread::sleep(std::time::Duration::from_secs(sleep_time));
+            // trigger advance messages in the case that no copy is received in the meantime
+            sender
+                .send(MessageWrapper {
+                    session_id,
+                    message: SyncMessage::WaitingDone,
+                })
+                .unwrap();
+        });
+
+        vec![SyncMessage::ObjectRequest(self.request.clone())]
+    }
+
+    fn deliver(&mut self, message: SyncMessage) -> DeliveryStatus<SyncMessage> {
+        match message {
+            SyncMessage::ObjectPiece(piece) => {
+                if self.answered.contains(&piece.owner)
+                    || !self.peers.contains(&piece.owner)
+                    || !piece.verify(&self.session_id)
+                {
+                    return DeliveryStatus::Delivered;
+                }
+                self.answered.push(piece.owner);
+                match piece
+                    .content
+                    .map(|content| content.open_with_key(&self.private_key))
+                {
+                    Some(Ok(copy)) => self.copies.push(copy),
+                    Some(Err(e)) => {
+                        log::warn!(target: &log_target(&self.session_id),
+                            "could not decrypt the copy of {}: {}", piece.owner.to_string(), e)
+                    }
+                    None => {}
+                }
+                DeliveryStatus::Delivered
+            }
+            SyncMessage::WaitingDone => {
+                self.waiting = WaitingState::Done;
+                DeliveryStatus::Delivered
+            }
+            m => DeliveryStatus::Unexpected(m),
+        }
+    }
+
+    fn advance(&mut self) -> Result<Transition<SyncTypes>, anyhow::Error> {
+        if let Some(copy) = self.matching_copy() {
+            return Ok(Transition::Terminal(SyncTerminalStates::Fetched(vec![
+                copy.clone(),
+            ])));
+        }
+        match self.waiting {
+            WaitingState::Waiting if self.answered.len() < self.peers.len() => Ok(Transition::Same),
+            _ if self.digest.is_some() || self.copies.is_empty() => {
+                log::info!(target: &log_target(&self.session_id),
+                    "fetching timeout, {} copies received", self.copies.len());
+                Ok(Transition::Terminal(SyncTerminalStates::Failed))
+            }
+            _ => Ok(Transition::Terminal(SyncTerminalStates::Fetched(
+                self.copies.drain(..).collect(),
+            ))),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use colored::Colorize;
+
+use std::{fmt::Display, sync::mpsc::Sender, thread};
+
+use kyber_rs::{group::edwards25519::Point, util::key::Pair};
+
+use crate::states::{
+    feed::MessageWrapper,
+    fsm::{DeliveryStatus, State, Transition},
+};
+
+use super::{
+    messages::{IndexPiece, IndexRequest, SyncMessage},
+    SyncTerminalStates, SyncTypes,
+};
+
+enum WaitingState {
+    Waiting,
+    Done,
+}
+
+pub struct SyncParams {
+    /// the other nodes of the committee
+    pub peers: Vec<Point>,
+    pub sender: Sender<MessageWrapper<SyncMessage>>,
+    pub sleep_time: u64,
+}
+
+/// Requests the index of the objects kept by the peers and collects their signed answers,
+/// until every peer answered or the waiting time is over.
+pub struct Indexing {
+    session_id: String,
+    request: IndexRequest,
+    peers: Vec<Point>,
+    pieces: Vec<IndexPiece>,
+    waiting: WaitingState,
+    sender: Sender<MessageWrapper<SyncMessage>>,
+    sleep_time: u64,
+}
+
+impl Indexing {
+    pub fn new(
+        keypair: &Pair<Point>,
+        session_id: String,
+        committee_did: String,
+        sync_params: SyncParams,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            request: IndexRequest::new(keypair, &session_id, committee_did)?,
+            session_id,
+            peers: sync_params.peers,
+            pieces: vec![],
+            waiting: WaitingState::Waiting,
+            sender: sync_params.sender,
+            sleep_time: sync_params.sleep_time,
+        })
+    }
+}
+
+impl Display for Indexing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Indexing peers' objects")
+    }
+}
+
+impl State<SyncTypes> for Indexing {
+    fn initialize(&self) -> Vec<SyncMessage> {
+        let sleep_time = self.sleep_time;
+        let session_id = self.session_id.clone();
+        let sender = self.sender.clone();
+
+        log::trace!(target: &log_target(&self.session_id),
+                    "starting index countdown, {} seconds", sleep_time);
+        thread::spawn(move || {
+            // sleeps to give time to the slow peers
+            std::thread::sleep(std::time::Duration::from_secs(sleep_time));
+            // trigger advance messages in the case that no index is received in the meantime
+            sender
+                .send(MessageWrapper {
+                    session_id,
+                    message: SyncMessage::WaitingDone,
+                })
+                .unwrap();
+        });
+
+        vec![SyncMessage::IndexRequest(self.request.clone())]
+    }
+
+    fn deliver(&mut self, message: SyncMessage) -> DeliveryStatus<SyncMessage> {
+        match message {
+            SyncMessage::IndexPiece(piece) => {
+                let is_duplicate = self.pieces.iter().any(|p| p.owner == piece.owner);
+                if !is_duplicate
+                    && self.peers.contains(&piece.owner)
+                    && piece.verify(&self.session_id)
+                {
+                    self.pieces.push(piece);
+                }
+                DeliveryStatus::Delivered
+            }
+            SyncMessage::WaitingDone => {
+                self.waiting = WaitingState::Done;
+                DeliveryStatus::Delivered
+            }
+            m => DeliveryStatus::Unexpected(m),
+        }
+    }
+
+    fn advance(&mut self) -> Result<Transition<SyncTypes>, anyhow::Error> {
+        match self.waiting {
+            WaitingState::Waiting if self.pieces.len() < self.peers.len() => Ok(Transition::Same),
+            _ if self.pieces.is_empty() => {
+                log::info!(target: &log_target(&self.session_id),
+                    "index timeout, no peer answered");
+                Ok(Transition::Terminal(SyncTerminalStates::Failed))
+            }
+            _ => Ok(Transition::Terminal(SyncTerminalStates::Indexed(
+                self.pieces.drain(..).collect(),
+            ))),
+        }
+    }
+}
+
+pub(super) fn log_target(session_id: &str) -> String {
+    format!(
+        "fsm:{}:sync",
+        session_id.chars().take(10).collect::<String>().yellow()
+    )
+}
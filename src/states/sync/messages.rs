@@ -0,0 +1,246 @@
+use enum_display::EnumDisplay;
+use kyber_rs::{
+    group::edwards25519::Point,
+    sign::eddsa::{self, EdDSA},
+    util::key::Pair,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::requests::{messages::CommitteeLog, SealedData},
+    store::ObjectMetadata,
+};
+
+#[derive(Clone, EnumDisplay, Serialize, Deserialize)]
+pub enum SyncMessage {
+    RootRequest(RootRequest),
+    RootPiece(RootPiece),
+    IndexRequest(IndexRequest),
+    IndexPiece(IndexPiece),
+    ObjectRequest(ObjectRequest),
+    ObjectPiece(ObjectPiece),
+    WaitingDone,
+}
+
+/// Request of the Merkle root of the intact objects a node keeps for the committee, at the
+/// given repair epoch, signed by a member of the committee.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RootRequest {
+    pub owner: Point,
+    pub committee_did: String,
+    pub epoch: u64,
+    pub signature: Vec<u8>,
+}
+
+impl RootRequest {
+    /// Request of the node owning the keypair, signed for the session.
+    pub fn new(
+        keypair: &Pair<Point>,
+        session_id: &str,
+        committee_did: String,
+        epoch: u64,
+    ) -> anyhow::Result<Self> {
+        let signature = sign(keypair, session_id, &(&committee_did, epoch))?;
+        Ok(Self {
+            owner: keypair.public,
+            committee_did,
+            epoch,
+            signature,
+        })
+    }
+
+    pub fn verify(&self, session_id: &str) -> bool {
+        verify(
+            &self.owner,
+            session_id,
+            &(&self.committee_did, self.epoch),
+            &self.signature,
+        )
+    }
+}
+
+/// Hex encoded Merkle root of the intact objects of a node, `None` if the node could not
+/// check its objects, signed by the node.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RootPiece {
+    pub owner: Point,
+    pub root: Option<String>,
+    pub signature: Vec<u8>,
+}
+
+impl RootPiece {
+    pub fn new(
+        keypair: &Pair<Point>,
+        session_id: &str,
+        root: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let signature = sign(keypair, session_id, &root)?;
+        Ok(Self {
+            owner: keypair.public,
+            root,
+            signature,
+        })
+    }
+
+    pub fn verify(&self, session_id: &str) -> bool {
+        verify(&self.owner, session_id, &self.root, &self.signature)
+    }
+}
+
+/// Request of the index of the objects a node keeps for the committee, signed by a member of
+/// the committee.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IndexRequest {
+    pub owner: Point,
+    pub committee_did: String,
+    pub signature: Vec<u8>,
+}
+
+impl IndexRequest {
+    /// Request of the node owning the keypair, signed for the session.
+    pub fn new(
+        keypair: &Pair<Point>,
+        session_id: &str,
+        committee_did: String,
+    ) -> anyhow::Result<Self> {
+        let signature = sign(keypair, session_id, &committee_did)?;
+        Ok(Self {
+            owner: keypair.public,
+            committee_did,
+            signature,
+        })
+    }
+
+    pub fn verify(&self, session_id: &str) -> bool {
+        verify(
+            &self.owner,
+            session_id,
+            &self.committee_did,
+            &self.signature,
+        )
+    }
+}
+
+/// Version of an object along with the committee's signed log of the request that stored it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub metadata: ObjectMetadata,
+    pub log: CommitteeLog,
+}
+
+/// Index of the objects kept by a node, every version of every object, signed by the node.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IndexPiece {
+    pub owner: Point,
+    pub entries: Vec<IndexEntry>,
+    pub signature: Vec<u8>,
+}
+
+impl IndexPiece {
+    pub fn new(
+        keypair: &Pair<Point>,
+        session_id: &str,
+        entries: Vec<IndexEntry>,
+    ) -> anyhow::Result<Self> {
+        let signature = sign(keypair, session_id, &entries)?;
+        Ok(Self {
+            owner: keypair.public,
+            entries,
+            signature,
+        })
+    }
+
+    pub fn verify(&self, session_id: &str) -> bool {
+        verify(&self.owner, session_id, &self.entries, &self.signature)
+    }
+}
+
+/// Request of a version of an object, as it is kept by the nodes, signed by a member of the
+/// committee.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ObjectRequest {
+    pub owner: Point,
+    pub committee_did: String,
+    pub key: String,
+    pub version: u64,
+    pub signature: Vec<u8>,
+}
+
+impl ObjectRequest {
+    /// Request of the node owning the keypair, signed for the session.
+    pub fn new(
+        keypair: &Pair<Point>,
+        session_id: &str,
+        committee_did: String,
+        key: String,
+        version: u64,
+    ) -> anyhow::Result<Self> {
+        let signature = sign(keypair, session_id, &(&committee_did, &key, version))?;
+        Ok(Self {
+            owner: keypair.public,
+            committee_did,
+            key,
+            version,
+            signature,
+        })
+    }
+
+    pub fn verify(&self, session_id: &str) -> bool {
+        verify(
+            &self.owner,
+            session_id,
+            &(&self.committee_did, &self.key, self.version),
+            &self.signature,
+        )
+    }
+}
+
+/// Version of an object kept by a node, encrypted to the node that requested it, `None` if
+/// the node doesn't have it, signed by the node.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ObjectPiece {
+    pub owner: Point,
+    pub content: Option<SealedData>,
+    pub signature: Vec<u8>,
+}
+
+impl ObjectPiece {
+    pub fn new(
+        keypair: &Pair<Point>,
+        session_id: &str,
+        content: Option<SealedData>,
+    ) -> anyhow::Result<Self> {
+        let signature = sign(keypair, session_id, &content)?;
+        Ok(Self {
+            owner: keypair.public,
+            content,
+            signature,
+        })
+    }
+
+    pub fn verify(&self, session_id: &str) -> bool {
+        verify(&self.owner, session_id, &self.content, &self.signature)
+    }
+}
+
+/// Bytes signed by the owner of a sync message: the session, so that messages can't be
+/// replayed in other sessions, and the message's fields.
+fn signed_bytes(session_id: &str, fields: &impl Serialize) -> anyhow::Result<Vec<u8>> {
+    Ok(serde_json::to_vec(&(session_id, fields))?)
+}
+
+fn sign(
+    keypair: &Pair<Point>,
+    session_id: &str,
+    fields: &impl Serialize,
+) -> anyhow::Result<Vec<u8>> {
+    Ok(EdDSA::from(keypair.clone())
+        .sign(&signed_bytes(session_id, fields)?)?
+        .to_vec())
+}
+
+fn verify(owner: &Point, session_id: &str, fields: &impl Serialize, signature: &[u8]) -> bool {
+    signed_bytes(session_id, fields)
+        .map(|bytes| eddsa::verify(owner, &bytes, signature).is_ok())
+        .unwrap_or(false)
+}
@@ -0,0 +1,29 @@
+use crate::states::fsm::StateMachineTypes;
+
+mod comparing;
+mod fetching;
+mod indexing;
+mod messages;
+
+pub use messages::{IndexEntry, IndexPiece, ObjectPiece, ObjectRequest, RootPiece, SyncMessage};
+
+pub use comparing::Comparing;
+pub use fetching::Fetching;
+pub use indexing::{Indexing, SyncParams};
+
+pub struct SyncTypes {}
+
+impl StateMachineTypes for SyncTypes {
+    type Message = SyncMessage;
+    type TerminalStates = SyncTerminalStates;
+}
+
+pub enum SyncTerminalStates {
+    /// the Merkle roots of the peers that answered
+    Compared(Vec<String>),
+    /// the indexes of the peers that answered
+    Indexed(Vec<IndexPiece>),
+    /// the copies of the object received from the peers
+    Fetched(Vec<Vec<u8>>),
+    Failed,
+}
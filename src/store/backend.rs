@@ -0,0 +1,44 @@
+use std::io::{Read, Write};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// A [StorageBackend] is a flat key-value object store where a node keeps the committee data.
+pub trait StorageBackend: Send + Sync {
+    fn put(&self, key: &str, content: &[u8]) -> Result<()>;
+
+    fn get(&self, key: &str) -> Result<Vec<u8>>;
+
+    fn delete(&self, key: &str) -> Result<()>;
+
+    /// Lists the keys starting with `prefix`, sorted.
+    fn list(&self, prefix: &str) -> Result<Vec<String>>;
+
+    fn exists(&self, key: &str) -> Result<bool>;
+
+    /// Retrieves the information about an object without its content.
+    fn head(&self, key: &str) -> Result<ObjectInfo>;
+
+    /// Stores the object read from `reader`, returning its size. Backends that can't stream
+    /// objects read it entirely in memory.
+    fn put_stream(&self, key: &str, reader: &mut dyn Read) -> Result<u64> {
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+        self.put(key, &content)?;
+        Ok(content.len() as u64)
+    }
+
+    /// Writes the object to `writer`, returning its size. Backends that can't stream objects
+    /// retrieve it entirely in memory.
+    fn get_stream(&self, key: &str, writer: &mut dyn Write) -> Result<u64> {
+        let content = self.get(key)?;
+        writer.write_all(&content)?;
+        Ok(content.len() as u64)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectInfo {
+    pub key: String,
+    pub size: u64,
+}
@@ -0,0 +1,483 @@
+use std::{
+    fs,
+    io::{self, ErrorKind, Read, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::Result;
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use thiserror::Error;
+
+use super::{ObjectInfo, StorageBackend};
+
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 24;
+const TAG_SIZE: usize = 16;
+const ENVELOPE_VERSION: u8 = 1;
+/// Size [bytes] of the chunks the content is encrypted in, so that objects can be streamed.
+const CHUNK_SIZE: usize = 64 * 1024;
+const ENCRYPTED_CHUNK_SIZE: usize = CHUNK_SIZE + TAG_SIZE;
+/// the nonce of a chunk is the prefix followed by the chunk's counter and the last chunk flag
+const PREFIX_SIZE: usize = NONCE_SIZE - 5;
+/// version, then the nonce and the data key wrapped by the storage key, then the nonce prefix
+/// of the content's chunks, followed by the encrypted chunks and their tags
+const HEADER_SIZE: usize = 1 + NONCE_SIZE + KEY_SIZE + TAG_SIZE + PREFIX_SIZE;
+const KEY_DERIVATION_INFO: &[u8] = b"dora-storage-encryption";
+
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error("could not encrypt the object")]
+    Encryption,
+    #[error("could not decrypt the object, the key is wrong or the object was tampered with")]
+    Decryption,
+    #[error("not an encrypted object")]
+    InvalidEnvelope,
+    #[error("not a valid storage key")]
+    InvalidKey,
+    #[error("object is too large to be encrypted")]
+    TooLarge,
+}
+
+/// Key wrapping the data keys of the objects of an [EncryptedBackend].
+#[derive(Clone)]
+pub struct StorageKey(Key);
+
+impl StorageKey {
+    /// Derives the key from the node's private key, so that no additional secret has to be kept.
+    pub fn derive(secret: &[u8]) -> Self {
+        let mut key = Key::default();
+        Hkdf::<Sha256>::new(None, secret)
+            .expand(KEY_DERIVATION_INFO, &mut key[..])
+            .expect("the key size is a valid hkdf output size");
+        Self(key)
+    }
+
+    /// Loads the hex encoded key sealed in the file at `path`, creating the file with a random
+    /// key if it does not exist.
+    pub fn load_or_create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            let key = hex::decode(fs::read_to_string(path)?.trim())?;
+            if key.len() != KEY_SIZE {
+                return Err(EncryptionError::InvalidKey.into());
+            }
+            return Ok(Self(*Key::from_slice(&key)));
+        }
+        let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(path)?.write_all(hex::encode(key).as_bytes())?;
+        log::info!("storage key created at {}", path.display());
+        Ok(Self(key))
+    }
+}
+
+/// Envelope encryption of the objects of another backend: every object is encrypted with its
+/// own random data key, which is stored next to it wrapped by the [StorageKey]. Both are bound
+/// to the object's key, so that objects can't be swapped in the underlying backend. The
+/// content is encrypted in chunks, so that objects are streamed without being held in memory.
+pub struct EncryptedBackend {
+    inner: Arc<dyn StorageBackend>,
+    cipher: XChaCha20Poly1305,
+}
+
+impl EncryptedBackend {
+    pub fn new(inner: Arc<dyn StorageBackend>, key: StorageKey) -> Self {
+        Self {
+            inner,
+            cipher: XChaCha20Poly1305::new(&key.0),
+        }
+    }
+
+    /// Creates the header of a new object and the cipher of its chunks.
+    fn seal_header<'a>(&self, key: &'a str) -> Result<(Vec<u8>, ChunkCipher<'a>), EncryptionError> {
+        let data_key = XChaCha20Poly1305::generate_key(&mut OsRng);
+        let key_nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let wrapped_key = self
+            .cipher
+            .encrypt(&key_nonce, payload(&data_key, key))
+            .map_err(|_| EncryptionError::Encryption)?;
+        let prefix = rand::random::<[u8; PREFIX_SIZE]>();
+
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.push(ENVELOPE_VERSION);
+        header.extend_from_slice(&key_nonce);
+        header.extend_from_slice(&wrapped_key);
+        header.extend_from_slice(&prefix);
+        Ok((header, ChunkCipher::new(&data_key, prefix, key)))
+    }
+
+    /// Unwraps the data key of the object's header, returning the cipher of its chunks.
+    fn open_header<'a>(
+        &self,
+        key: &'a str,
+        header: &[u8],
+    ) -> Result<ChunkCipher<'a>, EncryptionError> {
+        if header.len() != HEADER_SIZE || header[0] != ENVELOPE_VERSION {
+            return Err(EncryptionError::InvalidEnvelope);
+        }
+        let (key_nonce, rest) = header[1..].split_at(NONCE_SIZE);
+        let (wrapped_key, prefix) = rest.split_at(KEY_SIZE + TAG_SIZE);
+
+        let data_key = self
+            .cipher
+            .decrypt(XNonce::from_slice(key_nonce), payload(wrapped_key, key))
+            .map_err(|_| EncryptionError::Decryption)?;
+        let prefix = prefix
+            .try_into()
+            .map_err(|_| EncryptionError::InvalidEnvelope)?;
+        Ok(ChunkCipher::new(Key::from_slice(&data_key), prefix, key))
+    }
+
+    fn seal(&self, key: &str, content: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let (mut envelope, mut chunks) = self.seal_header(key)?;
+        let count = chunk_count(content.len(), CHUNK_SIZE);
+        envelope.reserve(content.len() + count * TAG_SIZE);
+        for i in 0..count {
+            let chunk = &content[i * CHUNK_SIZE..content.len().min((i + 1) * CHUNK_SIZE)];
+            envelope.extend_from_slice(&chunks.encrypt(chunk, i + 1 == count)?);
+        }
+        Ok(envelope)
+    }
+
+    fn open(&self, key: &str, envelope: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        if envelope.len() < HEADER_SIZE + TAG_SIZE {
+            return Err(EncryptionError::InvalidEnvelope);
+        }
+        let (header, encrypted) = envelope.split_at(HEADER_SIZE);
+        let mut chunks = self.open_header(key, header)?;
+        let count = chunk_count(encrypted.len(), ENCRYPTED_CHUNK_SIZE);
+        let mut content = Vec::with_capacity(encrypted.len());
+        for (i, chunk) in encrypted.chunks(ENCRYPTED_CHUNK_SIZE).enumerate() {
+            content.extend_from_slice(&chunks.decrypt(chunk, i + 1 == count)?);
+        }
+        Ok(content)
+    }
+}
+
+/// Number of chunks of the given size holding `len` bytes, an empty content being encrypted
+/// in a single empty chunk.
+fn chunk_count(len: usize, chunk_size: usize) -> usize {
+    len.div_ceil(chunk_size).max(1)
+}
+
+fn payload<'a>(msg: &'a [u8], key: &'a str) -> Payload<'a, 'a> {
+    Payload {
+        msg,
+        aad: key.as_bytes(),
+    }
+}
+
+/// Encryption of the chunks of an object following the STREAM construction: the nonce of a
+/// chunk is made of the object's random prefix, the chunk's counter and a flag set on the
+/// last chunk, so that chunks can't be reordered, dropped or truncated without failing the
+/// decryption.
+struct ChunkCipher<'a> {
+    cipher: XChaCha20Poly1305,
+    prefix: [u8; PREFIX_SIZE],
+    counter: u32,
+    key: &'a str,
+}
+
+impl<'a> ChunkCipher<'a> {
+    fn new(data_key: &Key, prefix: [u8; PREFIX_SIZE], key: &'a str) -> Self {
+        Self {
+            cipher: XChaCha20Poly1305::new(data_key),
+            prefix,
+            counter: 0,
+            key,
+        }
+    }
+
+    fn next_nonce(&mut self, last: bool) -> Result<XNonce, EncryptionError> {
+        let mut nonce = XNonce::default();
+        nonce[..PREFIX_SIZE].copy_from_slice(&self.prefix);
+        nonce[PREFIX_SIZE..NONCE_SIZE - 1].copy_from_slice(&self.counter.to_be_bytes());
+        nonce[NONCE_SIZE - 1] = last as u8;
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or(EncryptionError::TooLarge)?;
+        Ok(nonce)
+    }
+
+    fn encrypt(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>, EncryptionError> {
+        let nonce = self.next_nonce(last)?;
+        self.cipher
+            .encrypt(&nonce, payload(chunk, self.key))
+            .map_err(|_| EncryptionError::Encryption)
+    }
+
+    fn decrypt(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>, EncryptionError> {
+        let nonce = self.next_nonce(last)?;
+        self.cipher
+            .decrypt(&nonce, payload(chunk, self.key))
+            .map_err(|_| EncryptionError::Decryption)
+    }
+}
+
+fn io_error(e: EncryptionError) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, e)
+}
+
+/// Reader of the encrypted object, encrypting the content read from `source` one chunk at a
+/// time.
+struct EncryptingReader<'a> {
+    source: &'a mut dyn Read,
+    chunks: ChunkCipher<'a>,
+    /// content read ahead of the encrypted chunks, so that the last chunk is known
+    pending: Vec<u8>,
+    output: Vec<u8>,
+    position: usize,
+    done: bool,
+    size: u64,
+}
+
+impl<'a> EncryptingReader<'a> {
+    fn new(source: &'a mut dyn Read, header: Vec<u8>, chunks: ChunkCipher<'a>) -> Self {
+        Self {
+            source,
+            chunks,
+            pending: Vec::with_capacity(CHUNK_SIZE + 1),
+            output: header,
+            position: 0,
+            done: false,
+            size: 0,
+        }
+    }
+
+    /// Encrypts the next chunk, reading one byte past it to know whether it is the last one.
+    fn next_chunk(&mut self) -> io::Result<()> {
+        let wanted = CHUNK_SIZE + 1 - self.pending.len();
+        let read = Read::take(&mut self.source, wanted as u64).read_to_end(&mut self.pending)?;
+        let last = read < wanted;
+        let chunk: Vec<u8> = match last {
+            true => std::mem::take(&mut self.pending),
+            false => self.pending.drain(..CHUNK_SIZE).collect(),
+        };
+        self.output = self.chunks.encrypt(&chunk, last).map_err(io_error)?;
+        self.position = 0;
+        self.done = last;
+        self.size += chunk.len() as u64;
+        Ok(())
+    }
+}
+
+impl Read for EncryptingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.output.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.next_chunk()?;
+        }
+        let len = buf.len().min(self.output.len() - self.position);
+        buf[..len].copy_from_slice(&self.output[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+/// Writer of the encrypted object, writing its decrypted content to `sink` one chunk at a
+/// time. [DecryptingWriter::finish] must be called once the whole object was written, to
+/// decrypt its last chunk.
+struct DecryptingWriter<'a> {
+    backend: &'a EncryptedBackend,
+    key: &'a str,
+    sink: &'a mut dyn Write,
+    chunks: Option<ChunkCipher<'a>>,
+    pending: Vec<u8>,
+    size: u64,
+}
+
+impl<'a> DecryptingWriter<'a> {
+    fn new(backend: &'a EncryptedBackend, key: &'a str, sink: &'a mut dyn Write) -> Self {
+        Self {
+            backend,
+            key,
+            sink,
+            chunks: None,
+            pending: Vec::with_capacity(2 * ENCRYPTED_CHUNK_SIZE),
+            size: 0,
+        }
+    }
+
+    fn write_chunk(&mut self, chunk: &[u8], last: bool) -> io::Result<()> {
+        let chunks = self
+            .chunks
+            .as_mut()
+            .ok_or_else(|| io_error(EncryptionError::InvalidEnvelope))?;
+        let content = chunks.decrypt(chunk, last).map_err(io_error)?;
+        self.sink.write_all(&content)?;
+        self.size += content.len() as u64;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<u64> {
+        let chunk = std::mem::take(&mut self.pending);
+        self.write_chunk(&chunk, true)?;
+        self.sink.flush()?;
+        Ok(self.size)
+    }
+}
+
+impl Write for DecryptingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        if self.chunks.is_none() {
+            if self.pending.len() < HEADER_SIZE {
+                return Ok(buf.len());
+            }
+            let header: Vec<u8> = self.pending.drain(..HEADER_SIZE).collect();
+            let chunks = self
+                .backend
+                .open_header(self.key, &header)
+                .map_err(io_error)?;
+            self.chunks = Some(chunks);
+        }
+        // a full chunk is only decrypted once more data follows it, as the last one is flagged
+        while self.pending.len() > ENCRYPTED_CHUNK_SIZE {
+            let chunk: Vec<u8> = self.pending.drain(..ENCRYPTED_CHUNK_SIZE).collect();
+            self.write_chunk(&chunk, false)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+}
+
+impl StorageBackend for EncryptedBackend {
+    fn put(&self, key: &str, content: &[u8]) -> Result<()> {
+        self.inner.put(key, &self.seal(key, content)?)
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        Ok(self.open(key, &self.inner.get(key)?)?)
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        self.inner.delete(key)
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        self.inner.list(prefix)
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        self.inner.exists(key)
+    }
+
+    fn head(&self, key: &str) -> Result<ObjectInfo> {
+        let info = self.inner.head(key)?;
+        let encrypted = info.size.saturating_sub(HEADER_SIZE as u64);
+        let count = chunk_count(encrypted as usize, ENCRYPTED_CHUNK_SIZE) as u64;
+        Ok(ObjectInfo {
+            size: encrypted.saturating_sub(count * TAG_SIZE as u64),
+            ..info
+        })
+    }
+
+    fn put_stream(&self, key: &str, reader: &mut dyn Read) -> Result<u64> {
+        let (header, chunks) = self.seal_header(key)?;
+        let mut encrypting = EncryptingReader::new(reader, header, chunks);
+        self.inner.put_stream(key, &mut encrypting)?;
+        Ok(encrypting.size)
+    }
+
+    fn get_stream(&self, key: &str, writer: &mut dyn Write) -> Result<u64> {
+        let mut decrypting = DecryptingWriter::new(self, key, writer);
+        self.inner.get_stream(key, &mut decrypting)?;
+        decrypting.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::storages::memory::MemoryStorage;
+
+    fn backends() -> (MemoryStorage, EncryptedBackend) {
+        let inner = MemoryStorage::new();
+        let encrypted = EncryptedBackend::new(Arc::new(inner.clone()), StorageKey::derive(b"key"));
+        (inner, encrypted)
+    }
+
+    fn content(size: usize) -> Vec<u8> {
+        (0..size).map(|i| (i % 251) as u8).collect()
+    }
+
+    const SIZES: [usize; 5] = [0, 1, CHUNK_SIZE, CHUNK_SIZE + 1, 2 * CHUNK_SIZE + 5];
+
+    #[test]
+    fn seal_and_open() {
+        let (inner, encrypted) = backends();
+        for size in SIZES {
+            let content = content(size);
+            encrypted.put("object", &content).unwrap();
+            assert_ne!(inner.get("object").unwrap(), content);
+            assert_eq!(encrypted.get("object").unwrap(), content);
+            assert_eq!(encrypted.head("object").unwrap().size, size as u64);
+        }
+    }
+
+    #[test]
+    fn streams() {
+        let (_, encrypted) = backends();
+        for size in SIZES {
+            let content = content(size);
+            let stored = encrypted
+                .put_stream("object", &mut content.as_slice())
+                .unwrap();
+            assert_eq!(stored, size as u64);
+            assert_eq!(encrypted.get("object").unwrap(), content);
+
+            encrypted.put("object", &content).unwrap();
+            let mut read = vec![];
+            let retrieved = encrypted.get_stream("object", &mut read).unwrap();
+            assert_eq!(retrieved, size as u64);
+            assert_eq!(read, content);
+        }
+    }
+
+    #[test]
+    fn rejects_tampered_objects() {
+        let (inner, encrypted) = backends();
+        let content = content(2 * CHUNK_SIZE + 5);
+        encrypted.put("object", &content).unwrap();
+        let envelope = inner.get("object").unwrap();
+
+        let mut flipped = envelope.clone();
+        flipped[HEADER_SIZE + CHUNK_SIZE] ^= 1;
+        inner.put("object", &flipped).unwrap();
+        assert!(encrypted.get("object").is_err());
+        assert!(encrypted.get_stream("object", &mut vec![]).is_err());
+
+        // dropping the last chunk leaves a full chunk which is not flagged as the last one
+        let truncated = &envelope[..HEADER_SIZE + 2 * ENCRYPTED_CHUNK_SIZE];
+        inner.put("object", truncated).unwrap();
+        assert!(encrypted.get("object").is_err());
+        assert!(encrypted.get_stream("object", &mut vec![]).is_err());
+
+        // the object is bound to its key
+        inner.put("other", &envelope).unwrap();
+        assert!(encrypted.get("other").is_err());
+    }
+
+    #[test]
+    fn rejects_other_storage_keys() {
+        let (inner, encrypted) = backends();
+        encrypted.put("object", b"content").unwrap();
+        let other = EncryptedBackend::new(Arc::new(inner), StorageKey::derive(b"other"));
+        assert!(other.get("object").is_err());
+    }
+}
@@ -1,4 +1,13 @@
+mod backend;
+mod encrypted;
 mod storage;
 mod storages;
+mod usage;
+mod versioned;
 
+pub use backend::{ObjectInfo, StorageBackend};
+pub use encrypted::{EncryptedBackend, StorageKey};
 pub use storage::{new_storage, Storage};
+pub use storages::s3::S3Options;
+pub use usage::Usage;
+pub use versioned::ObjectMetadata;
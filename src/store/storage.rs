@@ -1,87 +1,637 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Seek, SeekFrom, Write},
+    sync::{Arc, Mutex},
+};
+
 use anyhow::{bail, Result};
-use s3::Bucket;
 
-use super::storages::minio::setup_minio_local_storage;
+use super::{
+    storages::{
+        filesystem::FilesystemStorage,
+        memory::MemoryStorage,
+        s3::{S3Options, S3Storage},
+    },
+    usage::UsageIndex,
+    EncryptedBackend, ObjectInfo, ObjectMetadata, StorageBackend, StorageKey, Usage,
+};
+
+/// Prefix of the node's internal keys, outside of every namespace.
+const INTERNAL_PREFIX: &str = "_dora/";
+/// Prefix of the keys written by the health check.
+const HEALTH_CHECK_PREFIX: &str = "_dora/health-";
+/// Prefix of the namespaces of the committees.
+const COMMITTEES_PREFIX: &str = "committees/";
 
+/// Handle to the [StorageBackend] of a node, cheap to clone. Every key is namespaced: the
+/// versions of the clients' objects are kept under `{namespace}versions/`, their metadata
+/// under `{namespace}metadata/`, the objects stored before versioning under
+/// `{namespace}objects/`, the committee's signed
+/// logs of their store requests under `{namespace}logs/`, so that they can never collide with
+/// each other nor with the node's internal keys under `_dora/`. The clients' keys are escaped
+/// (see [escape_key]), so that a key is never the parent directory of another one in the
+/// filesystem storage.
 #[derive(Clone)]
-pub enum Storage {
-    MinioLocal { bucket: Bucket },
+pub struct Storage {
+    backend: Arc<dyn StorageBackend>,
+    /// prefix of the keys, empty or ending with `/`
+    namespace: String,
+    /// usage indexes of the namespaces built so far, shared by every handle to the backend
+    usage_indexes: Arc<Mutex<HashMap<String, UsageIndex>>>,
 }
 
+/// Creates the storage of the given type, the options only apply to the S3 storages.
 pub fn new_storage(
     storage: &str,
-    access_key: Option<String>,
-    secret_key: Option<String>,
     endpoint: Option<String>,
+    s3_options: S3Options,
 ) -> Result<Storage> {
-    let storage: Storage = match storage {
-        "minio-local" => setup_minio_local_storage(
-            endpoint.expect("minio storage needs an endpoint"),
-            access_key,
-            secret_key,
-        )?,
-        _ => panic!("{storage} storage is not supported"),
+    let storage = match storage {
+        "s3" => Storage::new(S3Storage::new(S3Options {
+            endpoint,
+            ..s3_options
+        })?),
+        // a MinIO instance reached over plain http, e.g. next to the node in docker compose
+        "minio-local" => match endpoint {
+            Some(endpoint) => Storage::new(S3Storage::new(S3Options {
+                endpoint: Some(endpoint),
+                tls: false,
+                path_style: true,
+                ..s3_options
+            })?),
+            None => bail!("minio storage needs an endpoint"),
+        },
+        "filesystem" => match endpoint {
+            Some(directory) => Storage::new(FilesystemStorage::new(directory)?),
+            None => bail!("filesystem storage needs a directory as endpoint"),
+        },
+        "memory" => Storage::new(MemoryStorage::new()),
+        _ => bail!("{storage} storage is not supported"),
     };
     Ok(storage)
 }
 
 impl Storage {
-    pub fn put(&self, path: String, content: &[u8]) -> Result<()> {
-        let r = tokio::runtime::Runtime::new()?;
-        match self {
-            Storage::MinioLocal { bucket } => {
-                let response_code = r.block_on(bucket.put_object(path, content))?.status_code();
-                if response_code != 200 {
-                    bail!("could not put object, error: {}", response_code)
-                };
+    pub fn new(backend: impl StorageBackend + 'static) -> Self {
+        Self {
+            backend: Arc::new(backend),
+            namespace: String::new(),
+            usage_indexes: Default::default(),
+        }
+    }
+
+    /// Encrypts the objects at rest with the given key, see [EncryptedBackend].
+    pub fn with_encryption(self, key: StorageKey) -> Self {
+        Self {
+            backend: Arc::new(EncryptedBackend::new(self.backend, key)),
+            ..self
+        }
+    }
+
+    /// Storage of the committee with the given DID, isolated from the other committees of the
+    /// node.
+    pub fn for_committee(&self, committee_did: &str) -> Self {
+        let tag = committee_did.rsplit(':').next().unwrap_or(committee_did);
+        Self {
+            backend: self.backend.clone(),
+            namespace: format!("{COMMITTEES_PREFIX}{tag}/"),
+            usage_indexes: self.usage_indexes.clone(),
+        }
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        format!("{}objects/{}", self.namespace, escape_key(key))
+    }
+
+    fn version_key(&self, key: &str, version: u64) -> String {
+        format!("{}versions/{}/{}", self.namespace, escape_key(key), version)
+    }
+
+    fn metadata_key(&self, key: &str) -> String {
+        format!("{}metadata/{}", self.namespace, escape_key(key))
+    }
+
+    fn log_key(&self, request_id: &str) -> String {
+        format!("{}logs/{}", self.namespace, request_id)
+    }
+
+    /// Backend key of the latest version of the object, or of the object itself if it was
+    /// stored before versioning.
+    fn latest_key(&self, key: &str) -> Result<String> {
+        Ok(match self.metadata(key)? {
+            Some(latest) => self.version_key(key, latest.version),
+            None => self.object_key(key),
+        })
+    }
+
+    /// Latest version of the object.
+    pub fn get(&self, key: &str) -> Result<Vec<u8>> {
+        self.backend.get(&self.latest_key(key)?)
+    }
+
+    /// Deletes the object along with its versions, metadata and logs.
+    pub fn delete(&self, key: &str) -> Result<()> {
+        self.change_usage(
+            || self.delete_object(key),
+            |index| {
+                index.remove(key);
                 Ok(())
+            },
+        )
+    }
+
+    fn delete_object(&self, key: &str) -> Result<()> {
+        let versions = self.versions(key)?;
+        for metadata in &versions {
+            self.backend
+                .delete(&self.version_key(key, metadata.version))?;
+            let log_key = self.log_key(&metadata.request_id);
+            if self.backend.exists(&log_key)? {
+                self.backend.delete(&log_key)?;
             }
         }
+        let metadata_key = self.metadata_key(key);
+        if self.backend.exists(&metadata_key)? {
+            self.backend.delete(&metadata_key)?;
+        }
+        let object_key = self.object_key(key);
+        if versions.is_empty() || self.backend.exists(&object_key)? {
+            self.backend.delete(&object_key)?;
+        }
+        Ok(())
+    }
+
+    /// Lists the keys of the objects starting with `prefix`, sorted.
+    pub fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = self.list_keys(&self.metadata_key(""), prefix)?;
+        keys.extend(self.list_keys(&self.object_key(""), prefix)?);
+        keys.sort();
+        keys.dedup();
+        Ok(keys)
+    }
+
+    /// Keys of the objects whose metadata is kept, even if the objects themselves were lost.
+    pub fn versioned_keys(&self) -> Result<Vec<String>> {
+        self.list_keys(&self.metadata_key(""), "")
+    }
+
+    /// Unescaped keys starting with `prefix` under the backend prefix `base`, sorted. Escaping
+    /// keeps the prefixes of the keys, but not their order.
+    fn list_keys(&self, base: &str, prefix: &str) -> Result<Vec<String>> {
+        let mut keys: Vec<String> = self
+            .backend
+            .list(&format!("{base}{}", escape_key(prefix)))?
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(base).map(unescape_key))
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    pub fn exists(&self, key: &str) -> Result<bool> {
+        self.backend.exists(&self.latest_key(key)?)
+    }
+
+    pub fn head(&self, key: &str) -> Result<ObjectInfo> {
+        let info = self.backend.head(&self.latest_key(key)?)?;
+        Ok(ObjectInfo {
+            key: key.to_owned(),
+            ..info
+        })
+    }
+
+    /// Writes the latest version of the object to `writer`, see [StorageBackend::get_stream].
+    pub fn get_stream(&self, key: &str, writer: &mut dyn Write) -> Result<u64> {
+        self.backend.get_stream(&self.latest_key(key)?, writer)
+    }
+
+    /// Stores a new version of the object, keeping the previous ones. The version of
+    /// `metadata` is replaced by the next version of the object, and the recorded metadata is
+    /// returned. The object is written once, as its new version, and it is only served once its
+    /// metadata is recorded: a failed store leaves the previous version in place.
+    pub fn put_version(
+        &self,
+        key: &str,
+        content: &[u8],
+        mut metadata: ObjectMetadata,
+    ) -> Result<ObjectMetadata> {
+        let versions = self.live_versions(key, metadata.timestamp)?;
+        metadata.key = key.to_owned();
+        metadata.version = versions.last().map_or(1, |latest| latest.version + 1);
+
+        self.backend
+            .put(&self.version_key(key, metadata.version), content)?;
+        self.record_version(versions, &metadata)?;
+        Ok(metadata)
+    }
+
+    /// Streaming version of [Storage::put_version], the source is read from its start.
+    pub fn put_version_stream(
+        &self,
+        key: &str,
+        source: &mut (impl Read + Seek),
+        mut metadata: ObjectMetadata,
+    ) -> Result<ObjectMetadata> {
+        let versions = self.live_versions(key, metadata.timestamp)?;
+        metadata.key = key.to_owned();
+        metadata.version = versions.last().map_or(1, |latest| latest.version + 1);
+
+        source.seek(SeekFrom::Start(0))?;
+        self.backend
+            .put_stream(&self.version_key(key, metadata.version), source)?;
+        self.record_version(versions, &metadata)?;
+        Ok(metadata)
+    }
+
+    /// Versions a new version stored at unix time `now` [s] follows. When the latest version
+    /// expired at `now`, the object is deleted as the garbage collection would have, so that
+    /// the new version is the first one whether or not the garbage collection ran. Versions
+    /// stored without a time follow the stored ones.
+    fn live_versions(&self, key: &str, now: Option<u64>) -> Result<Vec<ObjectMetadata>> {
+        let versions = self.versions(key)?;
+        let expired = versions
+            .last()
+            .zip(now)
+            .is_some_and(|(latest, now)| latest.expired(now));
+        if expired {
+            self.delete(key)?;
+            return Ok(vec![]);
+        }
+        Ok(versions)
+    }
+
+    /// Stores a version of the object as recorded by another node of the committee, keeping
+    /// its metadata. Versions must be imported in order, after the ones already stored.
+    pub fn import_version(&self, content: &[u8], metadata: ObjectMetadata) -> Result<()> {
+        let versions = self.versions(&metadata.key)?;
+        let next = versions.last().map_or(1, |latest| latest.version + 1);
+        if metadata.version != next {
+            bail!(
+                "version {} of {} can't follow version {}",
+                metadata.version,
+                metadata.key,
+                next - 1
+            );
+        }
+
+        self.backend
+            .put(&self.version_key(&metadata.key, metadata.version), content)?;
+        self.record_version(versions, &metadata)
+    }
+
+    /// Replaces the content of a stored version, e.g. with a copy fetched from another node
+    /// of the committee after it was corrupted. Its metadata is left untouched.
+    pub fn repair_version(&self, content: &[u8], metadata: &ObjectMetadata) -> Result<()> {
+        let versions = self.versions(&metadata.key)?;
+        if !versions.contains(metadata) {
+            bail!(
+                "version {} of {} is not stored",
+                metadata.version,
+                metadata.key
+            );
+        }
+
+        self.backend
+            .put(&self.version_key(&metadata.key, metadata.version), content)
     }
 
-    pub fn get(&self, path: String) -> Result<Vec<u8>> {
-        let r = tokio::runtime::Runtime::new()?;
-        match self {
-            Storage::MinioLocal { bucket } => {
-                let response = r.block_on(bucket.get_object(path))?;
-                if response.status_code() != 200 {
-                    bail!("could not get object, error: {}", response.status_code())
-                };
-                Ok(response.bytes().to_vec())
+    /// Records the metadata of a new version.
+    fn record_version(
+        &self,
+        mut versions: Vec<ObjectMetadata>,
+        metadata: &ObjectMetadata,
+    ) -> Result<()> {
+        versions.push(metadata.clone());
+        self.change_usage(
+            || {
+                self.backend.put(
+                    &self.metadata_key(&metadata.key),
+                    &serde_json::to_vec(&versions)?,
+                )
+            },
+            |index| {
+                index.record(metadata, self.has_log(&metadata.request_id)?);
+                Ok(())
+            },
+        )
+    }
+
+    /// Makes a change counted by the usage, then updates the namespace's usage index if it
+    /// was built. The index is dropped if either fails, so that a partial change is counted as
+    /// the metadata records it when the index is built again.
+    fn change_usage(
+        &self,
+        change: impl FnOnce() -> Result<()>,
+        update: impl FnOnce(&mut UsageIndex) -> Result<()>,
+    ) -> Result<()> {
+        let mut usage_indexes = self.usage_indexes.lock().unwrap();
+        let changed = change().and_then(|()| match usage_indexes.get_mut(&self.namespace) {
+            Some(index) => update(index),
+            None => Ok(()),
+        });
+        if changed.is_err() {
+            usage_indexes.remove(&self.namespace);
+        }
+        changed
+    }
+
+    /// Objects and bytes stored in the namespace at unix time `now` [s], computed from the
+    /// recorded metadata of the versions whose store the committee signed, see [Usage].
+    /// Like for the garbage collection, an object whose latest version expired at `now` doesn't
+    /// count, even if it was not deleted yet, nor do the versions without a kept signed log, so
+    /// that the nodes that missed a garbage collection or a store agree on the usage. The
+    /// metadata is only read the first time, after that the usage is kept up to date as
+    /// versions are stored, logged and deleted.
+    pub fn usage(&self, now: u64) -> Result<Usage> {
+        let mut usage_indexes = self.usage_indexes.lock().unwrap();
+        if let Some(index) = usage_indexes.get(&self.namespace) {
+            return Ok(index.usage(now));
+        }
+        let mut index = UsageIndex::default();
+        for key in self.versioned_keys()? {
+            for metadata in self.versions(&key)? {
+                index.record(&metadata, self.has_log(&metadata.request_id)?);
             }
         }
+        let usage = index.usage(now);
+        usage_indexes.insert(self.namespace.clone(), index);
+        Ok(usage)
     }
 
-    pub fn delete(&self, path: String) -> Result<()> {
-        let r = tokio::runtime::Runtime::new()?;
-        match self {
-            Storage::MinioLocal { bucket } => {
-                let response_code = r.block_on(bucket.delete_object(path))?.status_code();
-                if response_code != 204 {
-                    bail!("could not delete object, error: {}", response_code)
-                };
+    pub fn get_version(&self, key: &str, version: u64) -> Result<Vec<u8>> {
+        self.backend.get(&self.version_key(key, version))
+    }
+
+    /// Writes a version of the object to `writer`, see [StorageBackend::get_stream].
+    pub fn get_version_stream(
+        &self,
+        key: &str,
+        version: u64,
+        writer: &mut dyn Write,
+    ) -> Result<u64> {
+        self.backend
+            .get_stream(&self.version_key(key, version), writer)
+    }
+
+    /// Keeps the committee's signed log of the request that stored a version.
+    pub fn put_log(&self, request_id: &str, log: &[u8]) -> Result<()> {
+        self.change_usage(
+            || self.backend.put(&self.log_key(request_id), log),
+            |index| {
+                index.log(request_id);
                 Ok(())
+            },
+        )
+    }
+
+    /// Whether the committee's signed log of the request is kept.
+    pub fn has_log(&self, request_id: &str) -> Result<bool> {
+        self.backend.exists(&self.log_key(request_id))
+    }
+
+    /// Committee's signed log of the request that stored a version, `None` if the version was
+    /// stored before logs were kept.
+    pub fn get_log(&self, request_id: &str) -> Result<Option<Vec<u8>>> {
+        let log_key = self.log_key(request_id);
+        if !self.backend.exists(&log_key)? {
+            return Ok(None);
+        }
+        Ok(Some(self.backend.get(&log_key)?))
+    }
+
+    /// Metadata of the latest version of the object, `None` if the object was stored without
+    /// versioning.
+    pub fn metadata(&self, key: &str) -> Result<Option<ObjectMetadata>> {
+        Ok(self.versions(key)?.pop())
+    }
+
+    /// Metadata of every version of the object, oldest first.
+    pub fn versions(&self, key: &str) -> Result<Vec<ObjectMetadata>> {
+        let metadata_key = self.metadata_key(key);
+        if !self.backend.exists(&metadata_key)? {
+            return Ok(vec![]);
+        }
+        Ok(serde_json::from_slice(&self.backend.get(&metadata_key)?)?)
+    }
+
+    /// Moves the objects written by nodes that did not namespace their keys, at the root of the
+    /// backend, into this namespace. Keys already present in the namespace are left where they
+    /// are. Returns the number of moved objects.
+    pub fn migrate_legacy_keys(&self) -> Result<usize> {
+        let mut moved = 0;
+        for legacy_key in self.backend.list("")? {
+            if legacy_key.starts_with(COMMITTEES_PREFIX) || legacy_key.starts_with(INTERNAL_PREFIX)
+            {
+                continue;
             }
+            let key = self.object_key(&legacy_key);
+            if self.backend.exists(&key)? {
+                log::warn!("{legacy_key} not migrated, {key} already exists");
+                continue;
+            }
+            // objects are encrypted with their key as associated data, so they are decrypted
+            // and encrypted again by the backend
+            self.backend.put(&key, &self.backend.get(&legacy_key)?)?;
+            self.backend.delete(&legacy_key)?;
+            moved += 1;
         }
+        Ok(moved)
     }
 
+    /// Writes, reads and deletes a test object under a random internal key, which can't
+    /// collide with the objects of any committee nor with concurrent health checks.
     pub fn health_check(&self) -> Result<()> {
-        match self {
-            Storage::MinioLocal { .. } => {
-                let test_path = "test.file".to_owned();
-                let test_data = b"test_data";
-                // PUT test
-                self.put(test_path.clone(), test_data)?;
+        let test_path = format!(
+            "{HEALTH_CHECK_PREFIX}{}",
+            hex::encode(rand::random::<[u8; 8]>())
+        );
+        let test_data = b"test_data";
+        // PUT test
+        self.backend.put(&test_path, test_data)?;
 
-                // GET test
-                let data = self.get(test_path.clone())?;
-                assert_eq!(data, test_data);
+        // GET test
+        let data = self.backend.get(&test_path)?;
 
-                // DEL test
-                self.delete(test_path)?;
+        // DEL test
+        self.backend.delete(&test_path)?;
+        if data != test_data {
+            bail!("storage returned corrupted data for the health check object");
+        }
 
-                Ok(())
+        Ok(())
+    }
+}
+
+/// Escapes `%` and `/` in a client's key, so that it is a single segment of the backend key.
+/// Escaping maps every character on its own, so it keeps the prefixes of the keys.
+fn escape_key(key: &str) -> String {
+    key.replace('%', "%25").replace('/', "%2F")
+}
+
+fn unescape_key(key: &str) -> String {
+    key.replace("%2F", "/").replace("%25", "%")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaped_keys_round_trip() {
+        for key in ["a", "a/b", "a%2Fb", "%/", "%252F", ""] {
+            assert_eq!(unescape_key(&escape_key(key)), key);
+        }
+        assert!(escape_key("a/b/c").starts_with(&escape_key("a/b")));
+    }
+
+    #[test]
+    fn nested_keys_do_not_collide() {
+        let dir = std::env::temp_dir().join(format!(
+            "dora-storage-{}",
+            hex::encode(rand::random::<[u8; 8]>())
+        ));
+        let storage =
+            Storage::new(FilesystemStorage::new(&dir).unwrap()).for_committee("did:iota:rms:0x01");
+        let metadata = ObjectMetadata::default();
+        storage.put_version("a", b"a", metadata.clone()).unwrap();
+        storage.put_version("a/1", b"a/1", metadata).unwrap();
+
+        assert_eq!(storage.list("a").unwrap(), vec!["a", "a/1"]);
+        assert_eq!(storage.list("a/").unwrap(), vec!["a/1"]);
+        assert_eq!(storage.get_version("a", 1).unwrap(), b"a");
+        assert_eq!(storage.get_version("a/1", 1).unwrap(), b"a/1");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn latest_version_is_served_without_a_copy() {
+        let storage = Storage::new(MemoryStorage::new()).for_committee("did:iota:rms:0x01");
+        let metadata = ObjectMetadata::default();
+        storage
+            .put_version("a", b"first", metadata.clone())
+            .unwrap();
+        let latest = storage
+            .put_version_stream("a", &mut std::io::Cursor::new(b"second"), metadata)
+            .unwrap();
+
+        assert_eq!(latest.version, 2);
+        assert_eq!(storage.metadata("a").unwrap(), Some(latest));
+        assert_eq!(storage.get("a").unwrap(), b"second");
+        assert_eq!(storage.get_version("a", 1).unwrap(), b"first");
+        assert_eq!(storage.head("a").unwrap().size, 6);
+        assert!(storage.exists("a").unwrap());
+        assert_eq!(storage.list("").unwrap(), vec!["a"]);
+        assert!(!storage.backend.exists(&storage.object_key("a")).unwrap());
+
+        storage.delete("a").unwrap();
+        assert!(!storage.exists("a").unwrap());
+        assert!(storage.backend.list("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn usage_counts_the_signed_versions_not_expired() {
+        let storage = Storage::new(MemoryStorage::new()).for_committee("did:iota:rms:0x01");
+        let version = |request_id: &str, timestamp, expires_at| ObjectMetadata {
+            request_id: request_id.to_owned(),
+            size: 10,
+            timestamp: Some(timestamp),
+            expires_at,
+            ..Default::default()
+        };
+        storage
+            .put_version("a", b"a", version("1", 0, None))
+            .unwrap();
+        storage
+            .put_version("a", b"a", version("2", 0, None))
+            .unwrap();
+        storage
+            .put_version("b", b"b", version("3", 0, Some(100)))
+            .unwrap();
+        for request_id in ["1", "3"] {
+            storage.put_log(request_id, b"{}").unwrap();
+        }
+
+        let usage = storage.usage(99).unwrap();
+        assert_eq!((usage.objects, usage.bytes), (2, 20));
+        let usage = storage.usage(100).unwrap();
+        assert_eq!((usage.objects, usage.bytes), (1, 10));
+
+        // storing an expired object restarts its versions, as if it was collected
+        let stored = storage
+            .put_version("b", b"b", version("4", 100, None))
+            .unwrap();
+        assert_eq!(stored.version, 1);
+        assert_eq!(storage.versions("b").unwrap(), vec![stored]);
+        assert!(!storage.has_log("3").unwrap());
+    }
+
+    #[test]
+    fn usage_index_follows_the_stored_versions() {
+        let storage = Storage::new(MemoryStorage::new()).for_committee("did:iota:rms:0x01");
+        // usage read from the metadata, without the index of the storage
+        let scanned = |now| {
+            Storage {
+                usage_indexes: Default::default(),
+                ..storage.clone()
             }
+            .usage(now)
+            .unwrap()
+        };
+        let version = |request_id: &str, requester: &str, expires_at| ObjectMetadata {
+            request_id: request_id.to_owned(),
+            size: 10,
+            timestamp: Some(0),
+            expires_at,
+            requester: Some(requester.to_owned()),
+            ..Default::default()
+        };
+        storage
+            .put_version("a", b"a", version("1", "alice", None))
+            .unwrap();
+        storage.put_log("1", b"{}").unwrap();
+        assert_eq!(storage.usage(0).unwrap(), scanned(0));
+
+        storage
+            .put_version("a", b"a", version("2", "bob", Some(100)))
+            .unwrap();
+        storage
+            .put_version("b", b"b", version("3", "bob", None))
+            .unwrap();
+        // the version of a request whose log is not kept doesn't count
+        storage
+            .put_version("c", b"c", version("4", "carol", None))
+            .unwrap();
+        for request_id in ["2", "3"] {
+            storage.put_log(request_id, b"{}").unwrap();
+        }
+        for now in [99, 100] {
+            assert_eq!(storage.usage(now).unwrap(), scanned(now));
         }
+        assert_eq!(storage.usage(99).unwrap().requester("bob").bytes, 20);
+        assert_eq!(storage.usage(100).unwrap().requester("bob").bytes, 10);
+        assert_eq!(storage.usage(99).unwrap().requester("carol").bytes, 0);
+
+        storage.delete("b").unwrap();
+        storage.put_log("4", b"{}").unwrap();
+        assert_eq!(storage.usage(99).unwrap(), scanned(99));
+        let usage = storage.usage(99).unwrap();
+        assert_eq!((usage.objects, usage.bytes), (2, 30));
+        assert_eq!(usage.requester("bob").bytes, 10);
+    }
+
+    #[test]
+    fn legacy_keys_are_moved_into_the_namespace() {
+        let storage = Storage::new(MemoryStorage::new());
+        storage.backend.put("a/b", b"object").unwrap();
+        storage.backend.put("c", b"other").unwrap();
+        storage.backend.put("_dora/health-00", b"test").unwrap();
+        let committee = storage.for_committee("did:iota:rms:0x01");
+
+        assert_eq!(committee.migrate_legacy_keys().unwrap(), 2);
+        assert_eq!(committee.get("a/b").unwrap(), b"object");
+        assert_eq!(committee.list("").unwrap(), vec!["a/b", "c"]);
+        assert_eq!(committee.metadata("a/b").unwrap(), None);
+        assert_eq!(storage.backend.list("").unwrap().len(), 3);
+        assert_eq!(committee.migrate_legacy_keys().unwrap(), 0);
     }
 }
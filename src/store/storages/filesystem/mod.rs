@@ -0,0 +1,3 @@
+mod storage;
+
+pub(crate) use storage::FilesystemStorage;
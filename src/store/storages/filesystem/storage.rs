@@ -0,0 +1,191 @@
+use std::{
+    fs,
+    io::{self, ErrorKind, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+
+use crate::store::{ObjectInfo, StorageBackend};
+
+/// Directory of the storage where objects are written before being moved to their path,
+/// outside of the keys.
+const TEMPORARY_DIRECTORY: &str = ".tmp";
+
+/// Storage keeping every object as a file inside a directory, keys containing `/` are stored
+/// in subdirectories.
+pub struct FilesystemStorage {
+    root: PathBuf,
+}
+
+impl FilesystemStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(root.join(TEMPORARY_DIRECTORY))?;
+        Ok(Self { root })
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        let relative = Path::new(key);
+        // keys must not be able to escape the storage directory, nor to reach temporary files
+        if key.is_empty()
+            || !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+            || relative.starts_with(TEMPORARY_DIRECTORY)
+        {
+            bail!("{} is not a valid key", key)
+        }
+        Ok(self.root.join(relative))
+    }
+
+    /// Unique path of a temporary file, in the same filesystem as the objects so that it can be
+    /// renamed to their path.
+    fn temporary_path(&self) -> PathBuf {
+        self.root
+            .join(TEMPORARY_DIRECTORY)
+            .join(hex::encode(rand::random::<[u8; 16]>()))
+    }
+
+    fn collect_keys(&self, directory: &Path, keys: &mut Vec<String>) -> Result<()> {
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path == self.root.join(TEMPORARY_DIRECTORY) {
+                continue;
+            } else if path.is_dir() {
+                self.collect_keys(&path, keys)?;
+            } else if let Ok(relative) = path.strip_prefix(&self.root) {
+                let key = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                keys.push(key);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StorageBackend for FilesystemStorage {
+    fn put(&self, key: &str, content: &[u8]) -> Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // write to a temporary file first, so that readers never see partial objects
+        let temporary = self.temporary_path();
+        fs::write(&temporary, content)?;
+        fs::rename(temporary, path)?;
+        Ok(())
+    }
+
+    fn put_stream(&self, key: &str, reader: &mut dyn Read) -> Result<u64> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temporary = self.temporary_path();
+        let size = match io::copy(reader, &mut fs::File::create(&temporary)?) {
+            Ok(size) => size,
+            Err(e) => {
+                fs::remove_file(&temporary).ok();
+                return Err(e.into());
+            }
+        };
+        fs::rename(temporary, path)?;
+        Ok(size)
+    }
+
+    fn get_stream(&self, key: &str, writer: &mut dyn Write) -> Result<u64> {
+        Ok(io::copy(&mut fs::File::open(self.path(key)?)?, writer)?)
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        Ok(fs::read(self.path(key)?)?)
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        Ok(fs::remove_file(self.path(key)?)?)
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        self.collect_keys(&self.root, &mut keys)?;
+        keys.retain(|key| key.starts_with(prefix));
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        match fs::metadata(self.path(key)?) {
+            Ok(metadata) => Ok(metadata.is_file()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn head(&self, key: &str) -> Result<ObjectInfo> {
+        let metadata = fs::metadata(self.path(key)?)?;
+        Ok(ObjectInfo {
+            key: key.to_owned(),
+            size: metadata.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage() -> (FilesystemStorage, PathBuf) {
+        let dir = std::env::temp_dir().join(format!(
+            "dora-storage-{}",
+            hex::encode(rand::random::<[u8; 8]>())
+        ));
+        (FilesystemStorage::new(&dir).unwrap(), dir)
+    }
+
+    #[test]
+    fn stores_objects_as_files() {
+        let (storage, dir) = storage();
+        storage.put("a/b", b"content").unwrap();
+        assert_eq!(storage.put_stream("c", &mut &b"streamed"[..]).unwrap(), 8);
+
+        assert_eq!(storage.get("a/b").unwrap(), b"content");
+        assert_eq!(fs::read(dir.join("a").join("b")).unwrap(), b"content");
+        let mut streamed = vec![];
+        storage.get_stream("c", &mut streamed).unwrap();
+        assert_eq!(streamed, b"streamed");
+        assert_eq!(storage.head("a/b").unwrap().size, 7);
+        assert_eq!(storage.list("").unwrap(), vec!["a/b", "c"]);
+        assert_eq!(storage.list("a/").unwrap(), vec!["a/b"]);
+
+        storage.delete("a/b").unwrap();
+        assert!(!storage.exists("a/b").unwrap());
+        assert!(storage.exists("c").unwrap());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keys_like_temporary_files_are_kept() {
+        let (storage, dir) = storage();
+        storage.put(".x.tmp", b"client").unwrap();
+        storage.put("x", b"object").unwrap();
+
+        assert_eq!(storage.get(".x.tmp").unwrap(), b"client");
+        assert_eq!(storage.list("").unwrap(), vec![".x.tmp", "x"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keys_stay_inside_the_storage() {
+        let (storage, dir) = storage();
+        for key in ["", "../a", "a/../../b", "/a", "a//b/..", ".tmp/a", ".tmp"] {
+            assert!(storage.put(key, b"content").is_err(), "{key}");
+            assert!(storage.get(key).is_err(), "{key}");
+        }
+        assert!(storage.list("").unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,69 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{bail, Result};
+
+use crate::store::{ObjectInfo, StorageBackend};
+
+/// Volatile storage, objects are lost when the node stops.
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    objects: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StorageBackend for MemoryStorage {
+    fn put(&self, key: &str, content: &[u8]) -> Result<()> {
+        self.objects
+            .lock()
+            .unwrap()
+            .insert(key.to_owned(), content.to_vec());
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        match self.objects.lock().unwrap().get(key) {
+            Some(content) => Ok(content.clone()),
+            None => bail!("could not get object, {} not found", key),
+        }
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        match self.objects.lock().unwrap().remove(key) {
+            Some(_) => Ok(()),
+            None => bail!("could not delete object, {} not found", key),
+        }
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        Ok(self
+            .objects
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        Ok(self.objects.lock().unwrap().contains_key(key))
+    }
+
+    fn head(&self, key: &str) -> Result<ObjectInfo> {
+        match self.objects.lock().unwrap().get(key) {
+            Some(content) => Ok(ObjectInfo {
+                key: key.to_owned(),
+                size: content.len() as u64,
+            }),
+            None => bail!("could not head object, {} not found", key),
+        }
+    }
+}
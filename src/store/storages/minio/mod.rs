@@ -1,3 +0,0 @@
-mod storage;
-
-pub(crate) use storage::setup_minio_local_storage;
@@ -1 +1,3 @@
-pub(crate) mod minio;
+pub(crate) mod filesystem;
+pub(crate) mod memory;
+pub(crate) mod s3;
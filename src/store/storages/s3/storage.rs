@@ -0,0 +1,384 @@
+use std::{
+    io::{self, Read, Write},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{
+    runtime::BlockingRuntime,
+    store::{ObjectInfo, StorageBackend},
+};
+use anyhow::{bail, Result};
+use s3::{creds::Credentials, error::S3Error, Bucket, BucketConfiguration, Region};
+use tokio::{
+    io::{AsyncRead, ReadBuf},
+    sync::mpsc,
+};
+
+/// Size [bytes] of the parts of multipart uploads and of the ranges of streamed downloads,
+/// the bucket uploads parts of 8 MiB.
+const CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+const CONTENT_TYPE: &str = "application/octet-stream";
+
+pub struct S3Storage {
+    bucket: Bucket,
+    /// prefix of the node's keys in the bucket, empty or ending with `/`
+    prefix: String,
+    /// runtime of the bucket's requests, shared by every call
+    runtime: BlockingRuntime,
+}
+
+/// Configuration of an S3-compatible storage.
+#[derive(Clone, Debug)]
+pub struct S3Options {
+    pub bucket: String,
+    pub region: String,
+    /// host (and port) of S3-compatible services other than AWS, e.g. `localhost:9000`
+    pub endpoint: Option<String>,
+    /// whether the endpoint is reached over https, AWS is always reached over https
+    pub tls: bool,
+    /// path-style (`{endpoint}/{bucket}/{key}`) instead of virtual-host style
+    /// (`{bucket}.{endpoint}/{key}`) addressing
+    pub path_style: bool,
+    /// prefix of the node's keys, so that the committees of a node can share a bucket
+    pub prefix: String,
+    pub access_key: Option<String>,
+    pub secret_key: Option<String>,
+}
+
+impl Default for S3Options {
+    fn default() -> Self {
+        Self {
+            bucket: "dora-node-bucket".to_owned(),
+            region: "eu-south-1".to_owned(),
+            endpoint: None,
+            tls: true,
+            path_style: false,
+            prefix: String::new(),
+            access_key: None,
+            secret_key: None,
+        }
+    }
+}
+
+impl S3Storage {
+    /// Connects to the bucket, creating it if it does not exist.
+    pub fn new(options: S3Options) -> Result<Self> {
+        let region = match options.endpoint {
+            Some(endpoint) => Region::Custom {
+                region: options.region,
+                endpoint: match options.tls {
+                    true => format!("https://{endpoint}"),
+                    false => format!("http://{endpoint}"),
+                },
+            },
+            None => options.region.parse()?,
+        };
+        let credentials = Credentials {
+            access_key: options.access_key,
+            secret_key: options.secret_key,
+            security_token: None,
+            session_token: None,
+            expiration: None,
+        };
+
+        let runtime = BlockingRuntime::new()?;
+        let response = match options.path_style {
+            true => runtime.block_on(Bucket::create_with_path_style(
+                &options.bucket,
+                region.clone(),
+                credentials.clone(),
+                BucketConfiguration::default(),
+            )),
+            false => runtime.block_on(Bucket::create(
+                &options.bucket,
+                region.clone(),
+                credentials.clone(),
+                BucketConfiguration::default(),
+            )),
+        };
+        let bucket = match response {
+            Ok(r) => r.bucket,
+            // the bucket exists, or the node may only use it
+            Err(S3Error::Http(409 | 403, ..)) => {
+                let bucket = Bucket::new(&options.bucket, region, credentials)?;
+                match options.path_style {
+                    true => bucket.with_path_style(),
+                    false => bucket,
+                }
+            }
+            Err(e) => bail!("{}", e),
+        };
+
+        let prefix = match options.prefix.trim_matches('/') {
+            "" => String::new(),
+            prefix => format!("{prefix}/"),
+        };
+        Ok(Self {
+            bucket,
+            prefix,
+            runtime,
+        })
+    }
+
+    /// Key of the object in the bucket.
+    fn object_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+}
+
+impl StorageBackend for S3Storage {
+    fn put(&self, key: &str, content: &[u8]) -> Result<()> {
+        let object_key = self.object_key(key);
+        let response_code = self
+            .runtime
+            .block_on(self.bucket.put_object(&object_key, content))?
+            .status_code();
+        if response_code != 200 {
+            bail!("could not put object, error: {}", response_code)
+        };
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let object_key = self.object_key(key);
+        let response = self.runtime.block_on(self.bucket.get_object(&object_key))?;
+        if response.status_code() != 200 {
+            bail!("could not get object, error: {}", response.status_code())
+        };
+        Ok(response.bytes().to_vec())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        let object_key = self.object_key(key);
+        let response_code = self
+            .runtime
+            .block_on(self.bucket.delete_object(&object_key))?
+            .status_code();
+        if response_code != 204 {
+            bail!("could not delete object, error: {}", response_code)
+        };
+        Ok(())
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let results = self
+            .runtime
+            .block_on(self.bucket.list(self.object_key(prefix), None))?;
+        let mut keys: Vec<String> = results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .filter_map(|object| object.key.strip_prefix(&self.prefix).map(str::to_owned))
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        let object_key = self.object_key(key);
+        match self.runtime.block_on(self.bucket.head_object(&object_key)) {
+            Ok((_, 200)) => Ok(true),
+            Ok((_, 404)) | Err(S3Error::Http(404, ..)) => Ok(false),
+            Ok((_, code)) => bail!("could not head object, error: {}", code),
+            Err(e) => bail!("{}", e),
+        }
+    }
+
+    fn head(&self, key: &str) -> Result<ObjectInfo> {
+        let object_key = self.object_key(key);
+        let (head, code) = self
+            .runtime
+            .block_on(self.bucket.head_object(&object_key))?;
+        if code != 200 {
+            bail!("could not head object, error: {}", code)
+        };
+        Ok(ObjectInfo {
+            key: key.to_owned(),
+            size: head.content_length.unwrap_or_default() as u64,
+        })
+    }
+
+    /// Uploads the object in parts of [CHUNK_SIZE], objects smaller than a part are uploaded
+    /// in a single request. The upload runs in the background and reads the chunks sent to it,
+    /// so that only a few chunks are buffered at a time.
+    fn put_stream(&self, key: &str, reader: &mut dyn Read) -> Result<u64> {
+        let (sender, receiver) = mpsc::channel(1);
+        let bucket = self.bucket.clone();
+        let object_key = self.object_key(key);
+        let upload = self.runtime.spawn(async move {
+            let mut reader = ChunkReader {
+                receiver,
+                chunk: Vec::new(),
+                position: 0,
+            };
+            bucket
+                .put_object_stream_with_content_type(&mut reader, object_key, CONTENT_TYPE)
+                .await
+        });
+
+        let mut size = 0;
+        let mut read_error = None;
+        loop {
+            let chunk = match read_chunk(reader) {
+                Ok(chunk) if chunk.is_empty() => break,
+                Ok(chunk) => Ok(chunk),
+                Err(e) => {
+                    // the upload fails with a copy of the error, the reader's one is returned
+                    let copy = io::Error::new(e.kind(), e.to_string());
+                    read_error = Some(e);
+                    Err(copy)
+                }
+            };
+            size += chunk.as_ref().map_or(0, |chunk| chunk.len() as u64);
+            let failed = chunk.is_err();
+            // the upload stops receiving when its request fails
+            if self.runtime.block_on(sender.send(chunk)).is_err() || failed {
+                break;
+            }
+        }
+        drop(sender);
+
+        let response_code = self.runtime.block_on(upload)?;
+        if let Some(e) = read_error {
+            return Err(e.into());
+        }
+        let response_code = response_code?;
+        if response_code != 200 {
+            bail!("could not put object, error: {}", response_code)
+        };
+        Ok(size)
+    }
+
+    /// Downloads the object in ranges of [CHUNK_SIZE].
+    fn get_stream(&self, key: &str, writer: &mut dyn Write) -> Result<u64> {
+        let object_key = self.object_key(key);
+        let size = self.head(key)?.size;
+        let mut start = 0;
+        while start < size {
+            let end = (start + CHUNK_SIZE).min(size) - 1;
+            let response = self.runtime.block_on(self.bucket.get_object_range(
+                &object_key,
+                start,
+                Some(end),
+            ))?;
+            if !matches!(response.status_code(), 200 | 206) {
+                bail!("could not get object, error: {}", response.status_code())
+            };
+            writer.write_all(response.bytes())?;
+            start = end + 1;
+        }
+        Ok(size)
+    }
+}
+
+fn read_chunk(reader: &mut dyn Read) -> io::Result<Vec<u8>> {
+    let mut chunk = Vec::with_capacity(CHUNK_SIZE as usize);
+    reader.take(CHUNK_SIZE).read_to_end(&mut chunk)?;
+    Ok(chunk)
+}
+
+/// Reader of a streamed upload, reading the chunks sent by [S3Storage::put_stream] until the
+/// sender is dropped.
+struct ChunkReader {
+    receiver: mpsc::Receiver<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    position: usize,
+}
+
+impl AsyncRead for ChunkReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        while this.position == this.chunk.len() {
+            match this.receiver.poll_recv(cx) {
+                Poll::Ready(Some(chunk)) => {
+                    this.chunk = chunk?;
+                    this.position = 0;
+                }
+                // the sender is dropped at the end of the object
+                Poll::Ready(None) => return Poll::Ready(Ok(())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        let read = buf.remaining().min(this.chunk.len() - this.position);
+        buf.put_slice(&this.chunk[this.position..this.position + read]);
+        this.position += read;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Tests against a running MinIO instance, e.g.
+/// `docker run -p 9000:9000 minio/minio server /data`, run with `cargo test -- --ignored`.
+/// The endpoint and credentials are read from `DORA_TEST_S3_ENDPOINT`,
+/// `DORA_TEST_S3_ACCESS_KEY` and `DORA_TEST_S3_SECRET_KEY`.
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn storage(prefix: &str) -> S3Storage {
+        let env_or = |name: &str, default: &str| env::var(name).unwrap_or(default.to_owned());
+        S3Storage::new(S3Options {
+            bucket: "dora-test-bucket".to_owned(),
+            endpoint: Some(env_or("DORA_TEST_S3_ENDPOINT", "localhost:9000")),
+            tls: false,
+            path_style: true,
+            prefix: format!("{prefix}-{}", hex::encode(rand::random::<[u8; 4]>())),
+            access_key: Some(env_or("DORA_TEST_S3_ACCESS_KEY", "minioadmin")),
+            secret_key: Some(env_or("DORA_TEST_S3_SECRET_KEY", "minioadmin")),
+            ..S3Options::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    #[ignore = "needs a MinIO instance"]
+    fn stores_objects() {
+        let storage = storage("objects");
+        storage.put("a/1", b"first").unwrap();
+        storage.put("a/2", b"second").unwrap();
+        storage.put("b", b"third").unwrap();
+
+        assert_eq!(storage.get("a/1").unwrap(), b"first");
+        assert_eq!(storage.head("a/2").unwrap().size, 6);
+        assert!(storage.exists("b").unwrap());
+        assert!(!storage.exists("c").unwrap());
+        assert_eq!(storage.list("a/").unwrap(), vec!["a/1", "a/2"]);
+
+        for key in ["a/1", "a/2", "b"] {
+            storage.delete(key).unwrap();
+        }
+        assert!(storage.list("").unwrap().is_empty());
+    }
+
+    #[test]
+    #[ignore = "needs a MinIO instance"]
+    fn streams_objects_in_parts() {
+        let storage = storage("streams");
+        let content: Vec<u8> = (0..CHUNK_SIZE + 5).map(|i| (i % 251) as u8).collect();
+        let size = storage
+            .put_stream("large", &mut content.as_slice())
+            .unwrap();
+        assert_eq!(size, content.len() as u64);
+
+        let mut retrieved = vec![];
+        storage.get_stream("large", &mut retrieved).unwrap();
+        assert_eq!(retrieved, content);
+        storage.delete("large").unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs a MinIO instance"]
+    fn isolates_prefixes() {
+        let first = storage("first");
+        let second = storage("second");
+        first.put("object", b"first").unwrap();
+        assert!(!second.exists("object").unwrap());
+        assert!(second.list("").unwrap().is_empty());
+        first.delete("object").unwrap();
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::ObjectMetadata;
+
+/// Objects and bytes stored in a namespace, computed by the [super::Storage] from the
+/// metadata of the stored versions, so that it always agrees with the committee's logs. Every
+/// version counts towards the bytes of its requester, and an object counts towards the objects
+/// of the requester of its first version.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    pub objects: u64,
+    pub bytes: u64,
+    /// usage of the requests with a requester, by requester
+    #[serde(default)]
+    pub requesters: BTreeMap<String, RequesterUsage>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequesterUsage {
+    pub objects: u64,
+    pub bytes: u64,
+}
+
+impl Usage {
+    pub fn requester(&self, requester: &str) -> RequesterUsage {
+        self.requesters.get(requester).cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn add_version(&mut self, metadata: &ObjectMetadata) {
+        let objects = u64::from(metadata.version == 1);
+        self.objects += objects;
+        self.bytes += metadata.size;
+        if let Some(requester) = &metadata.requester {
+            let usage = self.requesters.entry(requester.clone()).or_default();
+            usage.objects += objects;
+            usage.bytes += metadata.size;
+        }
+    }
+
+    fn remove(&mut self, other: &Usage) {
+        self.objects = self.objects.saturating_sub(other.objects);
+        self.bytes = self.bytes.saturating_sub(other.bytes);
+        for (requester, other) in &other.requesters {
+            if let Some(usage) = self.requesters.get_mut(requester) {
+                usage.objects = usage.objects.saturating_sub(other.objects);
+                usage.bytes = usage.bytes.saturating_sub(other.bytes);
+                if *usage == RequesterUsage::default() {
+                    self.requesters.remove(requester);
+                }
+            }
+        }
+    }
+}
+
+/// [Usage] of a namespace kept by the [super::Storage] as versions are recorded, logged and
+/// deleted, so that it is known without reading the metadata of every object. It counts the
+/// same versions as a scan of the metadata (see [super::Storage::usage]).
+#[derive(Debug, Default)]
+pub(crate) struct UsageIndex {
+    /// usage of the logged versions of every object
+    total: Usage,
+    objects: HashMap<String, ObjectUsage>,
+    /// objects whose latest version expires, by expiry
+    expiring: BTreeSet<(u64, String)>,
+    /// versions recorded before the committee's signed log of their store, by request id
+    unlogged: HashMap<String, ObjectMetadata>,
+}
+
+#[derive(Debug, Default)]
+struct ObjectUsage {
+    usage: Usage,
+    /// expiry of the latest version
+    expires_at: Option<u64>,
+}
+
+impl UsageIndex {
+    /// Records a new latest version of its object, which only counts once it is logged.
+    pub(crate) fn record(&mut self, metadata: &ObjectMetadata, logged: bool) {
+        let object = self.objects.entry(metadata.key.clone()).or_default();
+        if let Some(expires_at) = object.expires_at.take() {
+            self.expiring.remove(&(expires_at, metadata.key.clone()));
+        }
+        if let Some(expires_at) = metadata.expires_at {
+            object.expires_at = Some(expires_at);
+            self.expiring.insert((expires_at, metadata.key.clone()));
+        }
+        if logged {
+            object.usage.add_version(metadata);
+            self.total.add_version(metadata);
+        } else {
+            self.unlogged
+                .insert(metadata.request_id.clone(), metadata.clone());
+        }
+    }
+
+    /// Counts the version stored by the request, once the committee's signed log is kept.
+    pub(crate) fn log(&mut self, request_id: &str) {
+        if let Some(metadata) = self.unlogged.remove(request_id) {
+            if let Some(object) = self.objects.get_mut(&metadata.key) {
+                object.usage.add_version(&metadata);
+                self.total.add_version(&metadata);
+            }
+        }
+    }
+
+    /// Forgets every version of the object.
+    pub(crate) fn remove(&mut self, key: &str) {
+        if let Some(object) = self.objects.remove(key) {
+            self.total.remove(&object.usage);
+            if let Some(expires_at) = object.expires_at {
+                self.expiring.remove(&(expires_at, key.to_owned()));
+            }
+        }
+        self.unlogged.retain(|_, metadata| metadata.key != key);
+    }
+
+    /// Usage at unix time `now` [s], without the objects whose latest version expired.
+    pub(crate) fn usage(&self, now: u64) -> Usage {
+        let mut usage = self.total.clone();
+        for (_, key) in self
+            .expiring
+            .iter()
+            .take_while(|(expires_at, _)| *expires_at <= now)
+        {
+            usage.remove(&self.objects[key].usage);
+        }
+        usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(version: u64, size: u64, requester: Option<&str>) -> ObjectMetadata {
+        ObjectMetadata {
+            version,
+            size,
+            requester: requester.map(str::to_owned),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn counts_objects_once_and_every_version() {
+        let mut usage = Usage::default();
+        usage.add_version(&metadata(1, 10, None));
+        usage.add_version(&metadata(2, 20, None));
+        usage.add_version(&metadata(1, 5, None));
+
+        assert_eq!(usage.objects, 2);
+        assert_eq!(usage.bytes, 35);
+        assert!(usage.requesters.is_empty());
+    }
+
+    #[test]
+    fn counts_the_usage_of_each_requester() {
+        let mut usage = Usage::default();
+        usage.add_version(&metadata(1, 10, Some("alice")));
+        usage.add_version(&metadata(2, 20, Some("bob")));
+        usage.add_version(&metadata(1, 5, Some("bob")));
+
+        assert_eq!(usage.objects, 2);
+        assert_eq!(usage.bytes, 35);
+        assert_eq!(
+            usage.requester("alice"),
+            RequesterUsage {
+                objects: 1,
+                bytes: 10
+            }
+        );
+        // the object of alice is updated by bob, whose bytes count towards bob only
+        assert_eq!(
+            usage.requester("bob"),
+            RequesterUsage {
+                objects: 1,
+                bytes: 25
+            }
+        );
+        assert_eq!(usage.requester("carol"), RequesterUsage::default());
+    }
+
+    #[test]
+    fn index_counts_the_logged_versions_of_the_objects_not_expired() {
+        let version = |key: &str, version, request_id: &str, expires_at| ObjectMetadata {
+            key: key.to_owned(),
+            request_id: request_id.to_owned(),
+            expires_at,
+            ..metadata(version, 10, Some("alice"))
+        };
+        let mut index = UsageIndex::default();
+        index.record(&version("a", 1, "1", None), true);
+        index.record(&version("b", 1, "2", Some(100)), false);
+        assert_eq!((index.usage(0).objects, index.usage(0).bytes), (1, 10));
+
+        index.log("2");
+        assert_eq!(index.usage(99).requester("alice").bytes, 20);
+        assert_eq!(index.usage(100).requester("alice").bytes, 10);
+
+        // the expiry of the latest version replaces the previous one
+        index.record(&version("b", 2, "3", None), true);
+        assert_eq!((index.usage(100).objects, index.usage(100).bytes), (2, 30));
+
+        index.remove("b");
+        index.log("3");
+        assert_eq!(index.usage(100), {
+            let mut usage = Usage::default();
+            usage.add_version(&version("a", 1, "1", None));
+            usage
+        });
+        index.remove("a");
+        assert_eq!(index.usage(100), Usage::default());
+    }
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// Metadata recorded with every version of an object.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectMetadata {
+    pub key: String,
+    /// version of the object, starting at 1 and incremented by every store
+    pub version: u64,
+    /// hex encoded Blake2b-256 digest of the object
+    pub digest: String,
+    /// size [bytes] of the object
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// id of the block of the store request
+    pub request_id: String,
+    /// unix time [s] of the store request, checked by every node against its clock, absent if
+    /// the request had no timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    /// unix time [s] after which the object is deleted by the garbage collection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    /// hex encoded public key of the requester who signed the store request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requester: Option<String>,
+}
+
+impl ObjectMetadata {
+    /// Whether the version expired at unix time `now` [s].
+    pub fn expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}